@@ -0,0 +1,63 @@
+//! A banned word found in the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Derivation;
+
+/// A banned word that a syntax can generate.
+///
+/// It's created by [`Generator::find_banned()`].
+///
+/// [`Generator::find_banned()`]: struct.Generator.html#method.find_banned
+#[derive(Clone, Debug, PartialEq)]
+pub struct BannedMatch {
+    word: String,
+    phrase: String,
+    derivation: Derivation,
+}
+impl BannedMatch {
+    /// Create an instance.
+    ///
+    /// # Parameter
+    /// - `word`: The banned word.
+    /// - `phrase`: The phrase that has the banned word.
+    /// - `derivation`: The derivation that generates the phrase.
+    pub(crate) fn new(word: String, phrase: String, derivation: Derivation) -> Self {
+        Self {
+            word,
+            phrase,
+            derivation,
+        }
+    }
+
+    /// The banned word.
+    pub fn word(self: &Self) -> &str {
+        &self.word
+    }
+
+    /// The phrase that has the banned word.
+    pub fn phrase(self: &Self) -> &str {
+        &self.phrase
+    }
+
+    /// The derivation that generates the phrase.
+    pub fn derivation(self: &Self) -> &Derivation {
+        &self.derivation
+    }
+}
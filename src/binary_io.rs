@@ -0,0 +1,285 @@
+//! Helpers to read and write the binary data
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::DecodeError;
+
+use std::convert::TryFrom;
+
+/// Calculate the 64-bit FNV-1a hash.
+///
+/// # Parameter
+/// - `data`: The data to be hashed.
+///
+/// # Return
+/// The hash value.
+///
+/// # Note
+/// The hash value must not change in any version, because it's stored in the binary data.
+pub(crate) fn fnv1a64(data: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in data.iter() {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    return h;
+}
+
+/// The writer to create the binary data. All numbers are written in little endian.
+pub(crate) struct ByteWriter {
+    /// The written data.
+    data: Vec<u8>,
+}
+impl ByteWriter {
+    /// Create an empty writer.
+    pub(crate) fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// Write a byte.
+    pub(crate) fn u8(self: &mut Self, x: u8) {
+        self.data.push(x);
+    }
+
+    /// Write a boolean value as a byte.
+    pub(crate) fn bool(self: &mut Self, x: bool) {
+        self.data.push(if x { 1 } else { 0 });
+    }
+
+    /// Write a 32-bit unsigned integer.
+    pub(crate) fn u32(self: &mut Self, x: u32) {
+        self.data.extend_from_slice(&x.to_le_bytes());
+    }
+
+    /// Write a 64-bit unsigned integer.
+    pub(crate) fn u64(self: &mut Self, x: u64) {
+        self.data.extend_from_slice(&x.to_le_bytes());
+    }
+
+    /// Write a 64-bit floating point number.
+    pub(crate) fn f64(self: &mut Self, x: f64) {
+        self.data.extend_from_slice(&x.to_bits().to_le_bytes());
+    }
+
+    /// Write a length or an index.
+    ///
+    /// # Note
+    /// It panics if `x` isn't less than 2^32, rather than writing the broken data.
+    pub(crate) fn len(self: &mut Self, x: usize) {
+        match u32::try_from(x) {
+            Ok(x) => self.u32(x),
+            Err(_) => panic!("The length {} is too large for the binary format.", x),
+        }
+    }
+
+    /// Write an unsigned integer in the variable length format (LEB128), so that a small number takes a byte.
+    pub(crate) fn var(self: &mut Self, x: u64) {
+        let mut x = x;
+        while x >= 0x80 {
+            self.data.push((x & 0x7f) as u8 | 0x80);
+            x >>= 7;
+        }
+        self.data.push(x as u8);
+    }
+
+    /// Write a byte sequence preceded by its length.
+    pub(crate) fn bytes(self: &mut Self, x: &[u8]) {
+        self.len(x.len());
+        self.data.extend_from_slice(x);
+    }
+
+    /// Append the data written by another writer.
+    pub(crate) fn append(self: &mut Self, other: ByteWriter) {
+        self.data.extend(other.data);
+    }
+
+    /// Finish writing, and wrap the written data with the header and the checksum.
+    ///
+    /// # Parameter
+    /// - `magic`: The magic number to identify the kind of the data.
+    /// - `version`: The version of the format.
+    ///
+    /// # Return
+    /// The binary data.
+    pub(crate) fn finish(self: Self, magic: &[u8; 4], version: u32) -> Vec<u8> {
+        let mut r = Vec::with_capacity(self.data.len() + 24);
+        r.extend_from_slice(magic);
+        r.extend_from_slice(&version.to_le_bytes());
+        r.extend_from_slice(&(self.data.len() as u64).to_le_bytes());
+        r.extend_from_slice(&self.data);
+        r.extend_from_slice(&fnv1a64(&self.data).to_le_bytes());
+        return r;
+    }
+}
+
+/// The reader to read the binary data created by [`ByteWriter`].
+pub(crate) struct ByteReader<'a> {
+    /// The payload.
+    data: &'a [u8],
+    /// The current position.
+    pos: usize,
+}
+impl<'a> ByteReader<'a> {
+    /// Validate the header and the checksum, and create a reader of the payload.
+    ///
+    /// # Parameter
+    /// - `data`: The binary data created by [`ByteWriter::finish()`].
+    /// - `magic`: The expected magic number.
+    /// - `version`: The expected version of the format.
+    ///
+    /// # Errors
+    /// An error is returned if the header or the checksum doesn't match.
+    pub(crate) fn new(data: &'a [u8], magic: &[u8; 4], version: u32) -> Result<Self, DecodeError> {
+        if data.len() < 16 || &data[0..4] != magic {
+            return Err(DecodeError::new("Unknown data format.".to_string()));
+        }
+        let mut header = ByteReader {
+            data: &data[4..16],
+            pos: 0,
+        };
+        let data_version = header.u32()?;
+        if data_version != version {
+            return Err(DecodeError::new(format!(
+                "Unsupported format version {}.",
+                data_version
+            )));
+        }
+        let len = header.u64()?;
+        if (data.len() as u64 - 16) < 8 || len != data.len() as u64 - 24 {
+            return Err(DecodeError::new("The data size is wrong.".to_string()));
+        }
+        let payload = &data[16..data.len() - 8];
+        let mut trailer = ByteReader {
+            data: &data[data.len() - 8..],
+            pos: 0,
+        };
+        if trailer.u64()? != fnv1a64(payload) {
+            return Err(DecodeError::new("The checksum is wrong.".to_string()));
+        }
+        return Ok(ByteReader {
+            data: payload,
+            pos: 0,
+        });
+    }
+
+    /// Take the bytes from the current position.
+    fn take(self: &mut Self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if self.data.len() - self.pos < n {
+            return Err(DecodeError::new("Unexpected end of the data.".to_string()));
+        }
+        let r = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        return Ok(r);
+    }
+
+    /// The number of the bytes that haven't been read.
+    pub(crate) fn remaining(self: &Self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Read a byte.
+    pub(crate) fn u8(self: &mut Self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Read a boolean value.
+    pub(crate) fn bool(self: &mut Self) -> Result<bool, DecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::new("A boolean value is broken.".to_string())),
+        }
+    }
+
+    /// Read a 32-bit unsigned integer.
+    pub(crate) fn u32(self: &mut Self) -> Result<u32, DecodeError> {
+        let mut b = [0u8; 4];
+        b.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(b))
+    }
+
+    /// Read a 64-bit unsigned integer.
+    pub(crate) fn u64(self: &mut Self) -> Result<u64, DecodeError> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(b))
+    }
+
+    /// Read a 64-bit floating point number.
+    pub(crate) fn f64(self: &mut Self) -> Result<f64, DecodeError> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    /// Read a length or an index.
+    pub(crate) fn len(self: &mut Self) -> Result<usize, DecodeError> {
+        Ok(self.u32()? as usize)
+    }
+
+    /// Read an unsigned integer in the variable length format.
+    pub(crate) fn var(self: &mut Self) -> Result<u64, DecodeError> {
+        let mut x: u64 = 0;
+        let mut shift: u32 = 0;
+        loop {
+            let b = self.u8()?;
+            if shift >= 64 || (shift == 63 && b > 1) {
+                return Err(DecodeError::new("A number is too large.".to_string()));
+            }
+            x |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(x);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read an index that must be less than `n`.
+    pub(crate) fn index(self: &mut Self, n: usize) -> Result<usize, DecodeError> {
+        let i = self.len()?;
+        if i < n {
+            Ok(i)
+        } else {
+            Err(DecodeError::new("An index is out of range.".to_string()))
+        }
+    }
+
+    /// Read a byte sequence preceded by its length.
+    pub(crate) fn bytes(self: &mut Self) -> Result<&'a [u8], DecodeError> {
+        let n = self.len()?;
+        self.take(n)
+    }
+
+    /// Read a UTF-8 string preceded by its length.
+    pub(crate) fn string(self: &mut Self) -> Result<String, DecodeError> {
+        match std::str::from_utf8(self.bytes()?) {
+            Ok(s) => Ok(s.to_string()),
+            Err(_) => Err(DecodeError::new("A string is not UTF-8.".to_string())),
+        }
+    }
+
+    /// Check all the data has been read.
+    pub(crate) fn finish(self: &Self) -> Result<(), DecodeError> {
+        if self.pos == self.data.len() {
+            Ok(())
+        } else {
+            Err(DecodeError::new(
+                "Unexpected data after the end.".to_string(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! The external context
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::ContextValue;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+/// The external context, which gives the values of the nonterminals that the syntax doesn't solve.
+///
+/// It's implemented for `HashMap<String, String>`, `BTreeMap<String, String>`, `HashMap<String, ContextValue>`, `BTreeMap<String, ContextValue>`, closures `Fn(&str) -> Option<String>`, and [`ChainedContext`]. The value is requested only when a phrase needs it, so it can be computed lazily.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let mut ph: tphrase::Generator = "main = {PLAYER} has {HP} HP.".parse()?;
+/// let hp = 42;
+/// let context = |name: &str| match name {
+///     "PLAYER" => Some("Alice".to_string()),
+///     "HP" => Some(hp.to_string()),
+///     _ => None,
+/// };
+/// assert_eq!(ph.generate_with_context(&context), "Alice has 42 HP.");
+/// # Ok(())
+/// # }
+/// ```
+pub trait Context {
+    /// Get the value of a nonterminal.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the nonterminal.
+    ///
+    /// # Return
+    /// The value, or None if the context doesn't have it.
+    ///
+    /// # Note
+    /// If the nonterminal has a list in [`value()`], it should be the representative text, e.g. [`ContextValue::representative()`], which is used where a text is selected without the random numbers, e.g. [`Generator::nth()`].
+    ///
+    /// [`value()`]: #method.value
+    /// [`Generator::nth()`]: struct.Generator.html#method.nth
+    fn get(&self, name: &str) -> Option<Cow<'_, str>>;
+
+    /// Get the value of a nonterminal, which may be a list or a number.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the nonterminal.
+    ///
+    /// # Return
+    /// The value, or None if the context doesn't have it. It's the text given by [`get()`] by default.
+    ///
+    /// # Note
+    /// - The item of a list is selected as if the list is a production rule assigned to the nonterminal, i.e. by the same random number generator, and it's recorded in [`Trace`] and [`Derivation`]. The temperature and [`Generator::set_option_history_window()`] are applied to it, too.
+    /// - The methods that select the options without the random numbers, i.e. [`Generator::nth()`], [`Generator::iter_all()`], [`Generator::probability_of()`], [`Generator::ambiguities()`], [`Generator::find_banned()`], and the repetition avoidance by [`Generator::avoid_repeat()`], use the text given by [`get()`] instead of a list, because the lists aren't counted in [`Generator::combination_number()`].
+    ///
+    /// [`get()`]: #tymethod.get
+    /// [`Trace`]: struct.Trace.html
+    /// [`Derivation`]: struct.Derivation.html
+    /// [`Generator::set_option_history_window()`]: struct.Generator.html#method.set_option_history_window
+    /// [`Generator::nth()`]: struct.Generator.html#method.nth
+    /// [`Generator::iter_all()`]: struct.Generator.html#method.iter_all
+    /// [`Generator::probability_of()`]: struct.Generator.html#method.probability_of
+    /// [`Generator::ambiguities()`]: struct.Generator.html#method.ambiguities
+    /// [`Generator::find_banned()`]: struct.Generator.html#method.find_banned
+    /// [`Generator::avoid_repeat()`]: struct.Generator.html#method.avoid_repeat
+    /// [`Generator::combination_number()`]: struct.Generator.html#method.combination_number
+    fn value(&self, name: &str) -> Option<ContextValue<'_>> {
+        self.get(name).map(ContextValue::Text)
+    }
+}
+
+impl<H: BuildHasher> Context for HashMap<String, String, H> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        HashMap::get(self, name).map(|v| Cow::Borrowed(v.as_str()))
+    }
+}
+
+impl Context for BTreeMap<String, String> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        BTreeMap::get(self, name).map(|v| Cow::Borrowed(v.as_str()))
+    }
+}
+
+impl<H: BuildHasher> Context for HashMap<String, ContextValue<'_>, H> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        HashMap::get(self, name).and_then(|v| v.representative())
+    }
+
+    fn value(&self, name: &str) -> Option<ContextValue<'_>> {
+        HashMap::get(self, name).map(|v| v.borrowed())
+    }
+}
+
+impl Context for BTreeMap<String, ContextValue<'_>> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        BTreeMap::get(self, name).and_then(|v| v.representative())
+    }
+
+    fn value(&self, name: &str) -> Option<ContextValue<'_>> {
+        BTreeMap::get(self, name).map(|v| v.borrowed())
+    }
+}
+
+impl<F: Fn(&str) -> Option<String>> Context for F {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        self(name).map(Cow::Owned)
+    }
+}
+
+/// The context that consists of the layered contexts.
+///
+/// The value is taken from the first layer that has the nonterminal, so the preceding layers override the succeeding ones.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let mut ph: tphrase::Generator = "main = {GREETING}, {NAME}!".parse()?;
+/// let defaults = tphrase::ExtContext::from([
+///     ("GREETING".to_string(), "Hello".to_string()),
+///     ("NAME".to_string(), "world".to_string()),
+/// ]);
+/// let live = |name: &str| if name == "NAME" { Some("Alice".to_string()) } else { None };
+/// let context = tphrase::ChainedContext::new().with(&live).with(&defaults);
+/// assert_eq!(ph.generate_with_context(&context), "Hello, Alice!");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ChainedContext<'a> {
+    /// The layers in the order of the priority.
+    layers: Vec<&'a dyn Context>,
+}
+impl<'a> ChainedContext<'a> {
+    /// Create an instance that has no layer.
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// Add a layer whose priority is lower than the existing layers.
+    ///
+    /// # Parameter
+    /// - `layer`: The context added as the last layer.
+    ///
+    /// # Return
+    /// The context that has the layer.
+    pub fn with(self: Self, layer: &'a dyn Context) -> Self {
+        let mut r = self;
+        r.push(layer);
+        return r;
+    }
+
+    /// Add a layer whose priority is lower than the existing layers.
+    ///
+    /// # Parameter
+    /// - `layer`: The context added as the last layer.
+    pub fn push(self: &mut Self, layer: &'a dyn Context) {
+        self.layers.push(layer);
+    }
+
+    /// The number of the layers.
+    pub fn len(self: &Self) -> usize {
+        self.layers.len()
+    }
+
+    /// Has the instance no layer?
+    pub fn is_empty(self: &Self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+impl<'a> Context for ChainedContext<'a> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.layers.iter().find_map(|layer| layer.get(name))
+    }
+
+    fn value(&self, name: &str) -> Option<ContextValue<'_>> {
+        self.layers.iter().find_map(|layer| layer.value(name))
+    }
+}
+
+/// The context whose lists are fixed to their representative texts, used where a text is selected without the random numbers.
+pub(crate) struct FixedContext<'a> {
+    /// The context that has the values.
+    inner: &'a dyn Context,
+}
+impl<'a> FixedContext<'a> {
+    /// Create an instance.
+    ///
+    /// # Parameter
+    /// - `inner`: The context that has the values.
+    pub(crate) fn new(inner: &'a dyn Context) -> Self {
+        Self { inner }
+    }
+}
+
+impl Context for FixedContext<'_> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.inner.get(name)
+    }
+
+    fn value(&self, name: &str) -> Option<ContextValue<'_>> {
+        return match self.inner.value(name)? {
+            ContextValue::List(_) => self.get(name).map(ContextValue::Text),
+            v => Some(v),
+        };
+    }
+}
@@ -0,0 +1,160 @@
+//! The value of the external context
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use std::borrow::Cow;
+
+/// The value of a nonterminal in the external context.
+///
+/// # Note
+/// A value can't be a fragment of the phrase syntax compiled at runtime. The texts, including the items of a list, are used as they are, so "{A}" in them isn't expanded.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// use std::collections::HashMap;
+/// use tphrase::ContextValue;
+/// let mut ph: tphrase::Generator = "main = {LEADER} and {MEMBER} paid {PRICE:.2}".parse()?;
+/// let context = HashMap::from([
+///     ("LEADER".to_string(), ContextValue::from("Alice")),
+///     ("MEMBER".to_string(), ContextValue::list(["Bob", "Carol"])),
+///     ("PRICE".to_string(), ContextValue::from(12.5)),
+/// ]);
+/// let s = ph.generate_with_context(&context);
+/// assert!(s == "Alice and Bob paid 12.50" || s == "Alice and Carol paid 12.50");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContextValue<'a> {
+    /// The text that the nonterminal is expanded into.
+    Text(Cow<'a, str>),
+    /// The texts and their weights. The nonterminal is expanded into one of them selected at random, as if it's a production rule.
+    List(Cow<'a, [(String, f64)]>),
+    /// The integer formatted by the locale of the generator.
+    Integer(i64),
+    /// The floating point number formatted by the locale of the generator.
+    Float(f64),
+}
+impl<'a> ContextValue<'a> {
+    /// Create a list whose items have the same weight.
+    ///
+    /// # Parameter
+    /// - `items`: The texts.
+    pub fn list<I: IntoIterator<Item = T>, T: Into<String>>(items: I) -> Self {
+        ContextValue::List(Cow::Owned(
+            items.into_iter().map(|t| (t.into(), 1.0)).collect(),
+        ))
+    }
+
+    /// Create a list whose items have the weights.
+    ///
+    /// # Parameter
+    /// - `items`: The texts and their weights. The weight that isn't a finite non-negative number is treated as 0.
+    pub fn weighted_list<I: IntoIterator<Item = (T, f64)>, T: Into<String>>(items: I) -> Self {
+        ContextValue::List(Cow::Owned(
+            items.into_iter().map(|(t, w)| (t.into(), w)).collect(),
+        ))
+    }
+
+    /// The text that represents the value where a text is selected without the random numbers.
+    ///
+    /// # Return
+    /// The text, the first item of the list, or the number formatted without a locale. [`None`] if the list is empty.
+    pub fn representative(self: &Self) -> Option<Cow<'_, str>> {
+        match self {
+            ContextValue::Text(t) => Some(Cow::Borrowed(t.as_ref())),
+            ContextValue::List(items) => items.first().map(|(t, _)| Cow::Borrowed(t.as_str())),
+            ContextValue::Integer(i) => Some(Cow::Owned(i.to_string())),
+            ContextValue::Float(f) => Some(Cow::Owned(f.to_string())),
+        }
+    }
+
+    /// The value as a number.
+    ///
+    /// # Return
+    /// The number, or the text parsed as a number. [`None`] if it's a list or the text isn't a number.
+    pub fn as_f64(self: &Self) -> Option<f64> {
+        match self {
+            ContextValue::Text(t) => t.trim().parse::<f64>().ok(),
+            ContextValue::List(_) => None,
+            ContextValue::Integer(i) => Some(*i as f64),
+            ContextValue::Float(f) => Some(*f),
+        }
+    }
+
+    /// The value that borrows the instance.
+    pub(crate) fn borrowed(self: &Self) -> ContextValue<'_> {
+        match self {
+            ContextValue::Text(t) => ContextValue::Text(Cow::Borrowed(t.as_ref())),
+            ContextValue::List(items) => ContextValue::List(Cow::Borrowed(items.as_ref())),
+            ContextValue::Integer(i) => ContextValue::Integer(*i),
+            ContextValue::Float(f) => ContextValue::Float(*f),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for ContextValue<'a> {
+    fn from(s: &'a str) -> Self {
+        ContextValue::Text(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for ContextValue<'_> {
+    fn from(s: String) -> Self {
+        ContextValue::Text(Cow::Owned(s))
+    }
+}
+
+impl From<i64> for ContextValue<'_> {
+    fn from(i: i64) -> Self {
+        ContextValue::Integer(i)
+    }
+}
+
+impl From<i32> for ContextValue<'_> {
+    fn from(i: i32) -> Self {
+        ContextValue::Integer(i64::from(i))
+    }
+}
+
+impl From<f64> for ContextValue<'_> {
+    fn from(f: f64) -> Self {
+        ContextValue::Float(f)
+    }
+}
+
+/// The cumulative weights to select an item of a list.
+///
+/// # Parameter
+/// - `items`: The texts and their weights.
+///
+/// # Return
+/// `weights[i]` is the sum of `weights[i-1]` and the weight of `items[i]`. The weight that isn't a finite non-negative number is treated as 0.
+pub(crate) fn list_weights(items: &[(String, f64)]) -> Vec<f64> {
+    let mut sum: f64 = 0.0;
+    let mut weights = Vec::with_capacity(items.len());
+    for (_, w) in items.iter() {
+        if w.is_finite() && *w >= 0.0 {
+            sum += w;
+        }
+        weights.push(sum);
+    }
+    return weights;
+}
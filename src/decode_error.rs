@@ -0,0 +1,58 @@
+//! DecodeError
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+/// The type that represents the error when decoding the binary data.
+///
+/// # Example
+/// ```rust
+/// let result: Result<tphrase::Syntax, _> = tphrase::Syntax::from_bytes(b"broken");
+/// assert!(result.is_err());
+/// if let Err(err) = result {
+///     assert_eq!(err.error_message(), "Unknown data format.");
+///     assert_eq!(err.to_string(), "decode error: \"Unknown data format.\"");
+/// }
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct DecodeError {
+    error_message: String,
+}
+impl DecodeError {
+    /// Create a new instance.
+    ///
+    /// # Note
+    /// - Against the common manner in Rust, the beginning of `msg` should be capital letter and the end is the period.
+    pub fn new(msg: String) -> Self {
+        Self { error_message: msg }
+    }
+    /// The error message.
+    pub fn error_message(self: &Self) -> &String {
+        &self.error_message
+    }
+}
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "decode error")?;
+        if !self.error_message.is_empty() {
+            write!(f, ": \"{}\"", self.error_message)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for DecodeError {}
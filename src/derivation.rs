@@ -0,0 +1,189 @@
+//! The derivation of a phrase
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::binary_io::{ByteReader, ByteWriter};
+use crate::selection_probability;
+use crate::DecodeError;
+use crate::DerivationError;
+use crate::Selector;
+
+/// The magic number of the binary format of [`Derivation`].
+const MAGIC: &[u8; 4] = b"TPHD";
+/// The version of the binary format of [`Derivation`].
+const VERSION: u32 = 1;
+
+/// The compact record of the options chosen to generate a phrase.
+///
+/// The first index selects the syntax in the generator (in the order of addition), and the others are the indices of the options chosen in the production rules in the order of generation, including the rules that have only one option. The indices don't depend on the texts, so the same derivation can generate the phrase from the syntax translated into another language as long as the structure of the syntax is the same.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut ph: tphrase::Generator = r#"
+///     main = {COLOR} {ANIMAL}
+///     COLOR = red | green | blue
+///     ANIMAL = cat | dog
+/// "#.parse()?;
+/// let (phrase, trace) = ph.generate_traced();
+/// let saved = trace.derivation().to_bytes();
+///
+/// let derivation = tphrase::Derivation::from_bytes(&saved)?;
+/// let no_context = tphrase::ExtContext::new();
+/// assert_eq!(ph.generate_from_derivation(&derivation, &no_context)?, phrase);
+///
+/// let ph_ja: tphrase::Generator = r#"
+///     main = {COLOR}{ANIMAL}
+///     COLOR = 赤い | 緑の | 青い
+///     ANIMAL = 猫 | 犬
+/// "#.parse()?;
+/// let blue_dog = tphrase::Derivation::new(vec![0, 0, 2, 1]);
+/// assert_eq!(ph.generate_from_derivation(&blue_dog, &no_context)?, "blue dog");
+/// assert_eq!(ph_ja.generate_from_derivation(&blue_dog, &no_context)?, "青い犬");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+pub struct Derivation {
+    indices: Vec<usize>,
+}
+impl Derivation {
+    /// Create a derivation from the indices.
+    ///
+    /// # Parameter
+    /// - `indices`: The index of the syntax followed by the indices of the chosen options.
+    pub fn new(indices: Vec<usize>) -> Self {
+        Self { indices }
+    }
+
+    /// The index of the syntax followed by the indices of the chosen options.
+    pub fn indices(self: &Self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Encode the derivation in the versioned binary format.
+    ///
+    /// # Return
+    /// The binary data.
+    pub fn to_bytes(self: &Self) -> Vec<u8> {
+        let mut w = ByteWriter::new();
+        w.var(self.indices.len() as u64);
+        for i in self.indices.iter() {
+            w.var(*i as u64);
+        }
+        return w.finish(MAGIC, VERSION);
+    }
+
+    /// Decode the binary data created by [`to_bytes()`].
+    ///
+    /// # Parameter
+    /// - `data`: The binary data.
+    ///
+    /// # Return
+    /// The derivation, or the error if the data is broken.
+    ///
+    /// [`to_bytes()`]: #method.to_bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = ByteReader::new(data, MAGIC, VERSION)?;
+        let n = r.var()?;
+        let mut indices = Vec::new();
+        for _ in 0..n {
+            let i = r.var()?;
+            if i > usize::MAX as u64 {
+                return Err(DecodeError::new("An index is out of range.".to_string()));
+            }
+            indices.push(i as usize);
+        }
+        r.finish()?;
+        return Ok(Self { indices });
+    }
+}
+
+/// The selector that selects the options recorded in a derivation.
+pub(crate) struct ReplaySelector<'a> {
+    /// The indices to be selected.
+    indices: &'a [usize],
+    /// The position of the next index.
+    pos: usize,
+    /// The first error that occurred.
+    error: Option<DerivationError>,
+    /// The probability that the selected options are selected by [`RngSelector`].
+    ///
+    /// [`RngSelector`]: ../utils/struct.RngSelector.html
+    probability: f64,
+}
+impl<'a> ReplaySelector<'a> {
+    /// Create a selector.
+    ///
+    /// # Parameter
+    /// - `derivation`: The derivation to be replayed.
+    pub(crate) fn new(derivation: &'a Derivation) -> Self {
+        Self {
+            indices: &derivation.indices,
+            pos: 0,
+            error: None,
+            probability: 1.0,
+        }
+    }
+
+    /// The probability that a generator selects the same options as the ones replayed so far.
+    pub(crate) fn probability(self: &Self) -> f64 {
+        self.probability
+    }
+
+    /// Finish the replay.
+    ///
+    /// # Return
+    /// The error if the derivation doesn't match the syntax.
+    pub(crate) fn finish(self: Self) -> Result<(), DerivationError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        if self.pos < self.indices.len() {
+            return Err(DerivationError::new(
+                "The derivation is longer than the syntax requires.".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+}
+impl Selector for ReplaySelector<'_> {
+    fn select(self: &mut Self, weights: &[f64], equalized_chance: bool) -> usize {
+        if self.error.is_some() {
+            return 0;
+        }
+        if self.pos >= self.indices.len() {
+            self.error = Some(DerivationError::new(
+                "The derivation is shorter than the syntax requires.".to_string(),
+            ));
+            return 0;
+        }
+        let i = self.indices[self.pos];
+        if i >= weights.len() {
+            self.error = Some(DerivationError::new(format!(
+                "The option index {} at {} is out of range.",
+                i, self.pos
+            )));
+            return 0;
+        }
+        self.pos += 1;
+        self.probability *= selection_probability(weights, equalized_chance, i);
+        return i;
+    }
+}
@@ -0,0 +1,78 @@
+//! The environment of the generation
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Context;
+use crate::Filter;
+use crate::FunctionRegistry;
+use crate::Locale;
+
+/// What the texts refer to while a phrase is generated.
+pub(crate) struct Environment<'a> {
+    /// The external context that has some nonterminals and the substitutions.
+    ext_context: &'a dyn Context,
+    /// The locale to format the numbers.
+    locale: &'a Locale,
+    /// The filters applied to the whole phrase.
+    post_filters: &'a [Filter],
+    /// The functions called in the texts.
+    functions: &'a FunctionRegistry,
+}
+impl<'a> Environment<'a> {
+    /// Create an instance.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `locale`: The locale to format the numbers.
+    /// - `post_filters`: The filters applied to the whole phrase.
+    /// - `functions`: The functions called in the texts.
+    pub(crate) fn new(
+        ext_context: &'a dyn Context,
+        locale: &'a Locale,
+        post_filters: &'a [Filter],
+        functions: &'a FunctionRegistry,
+    ) -> Self {
+        Self {
+            ext_context,
+            locale,
+            post_filters,
+            functions,
+        }
+    }
+
+    /// The external context.
+    pub(crate) fn ext_context(self: &Self) -> &'a dyn Context {
+        self.ext_context
+    }
+
+    /// The locale to format the numbers.
+    pub(crate) fn locale(self: &Self) -> &'a Locale {
+        self.locale
+    }
+
+    /// The filters applied to the whole phrase.
+    pub(crate) fn post_filters(self: &Self) -> &'a [Filter] {
+        self.post_filters
+    }
+
+    /// The functions called in the texts.
+    pub(crate) fn functions(self: &Self) -> &'a FunctionRegistry {
+        self.functions
+    }
+}
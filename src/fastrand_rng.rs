@@ -19,6 +19,7 @@
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 
 use crate::RandomNumberGenerator;
+use crate::SeedableRandomNumberGenerator;
 
 /// A type of [`RandomNumberGenerator`] using [`fastrand::Rng`]. The default random number generator of [`Generator`].
 ///
@@ -38,3 +39,18 @@ impl RandomNumberGenerator for FastrandRng {
         self.rng.f64()
     }
 }
+
+impl SeedableRandomNumberGenerator for FastrandRng {
+    type State = u64;
+    fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+    fn state(self: &Self) -> u64 {
+        self.rng.get_seed()
+    }
+    fn set_state(self: &mut Self, state: u64) {
+        self.rng.seed(state);
+    }
+}
@@ -0,0 +1,209 @@
+//! The filter to change the texts
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+/// The filter to change a text, applied in an expansion like "{NAME|capitalize}" or to the whole phrase by [`Generator::set_post_filters()`].
+///
+/// # Note
+/// The case is mapped by the Unicode rules independent of the language, e.g. "ß" is "SS" in uppercase and "Ss" in titlecase, and "ǆ" is "ǅ" in titlecase.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let mut ph: tphrase::Generator = "main = {ITEM|capitalize} is {ADJ|upper}.".parse()?;
+/// let context = tphrase::ExtContext::from([
+///     ("ITEM".to_string(), "¿el martillo".to_string()),
+///     ("ADJ".to_string(), "große".to_string()),
+/// ]);
+/// assert_eq!(ph.generate_with_context(&context), "¿El martillo is GROSSE.");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Generator::set_post_filters()`]: struct.Generator.html#method.set_post_filters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// "upper": Convert all the characters to uppercase.
+    Upper,
+    /// "lower": Convert all the characters to lowercase.
+    Lower,
+    /// "capitalize": Convert the first letter to titlecase, unless a digit precedes it. The preceding punctuation, e.g. "¿", is kept, and the other characters aren't changed.
+    Capitalize,
+    /// "title": Convert the first letter of each word to titlecase. The other characters aren't changed.
+    Title,
+    /// "trim": Remove the leading and trailing white spaces.
+    Trim,
+}
+impl Filter {
+    /// Get the filter by its name.
+    ///
+    /// # Parameter
+    /// - `name`: The name, i.e. "upper", "lower", "capitalize", "title", or "trim".
+    ///
+    /// # Return
+    /// The filter, or [`None`] if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name {
+            "upper" => Some(Filter::Upper),
+            "lower" => Some(Filter::Lower),
+            "capitalize" => Some(Filter::Capitalize),
+            "title" => Some(Filter::Title),
+            "trim" => Some(Filter::Trim),
+            _ => None,
+        };
+    }
+
+    /// The name of the filter.
+    pub fn name(self: &Self) -> &'static str {
+        match self {
+            Filter::Upper => "upper",
+            Filter::Lower => "lower",
+            Filter::Capitalize => "capitalize",
+            Filter::Title => "title",
+            Filter::Trim => "trim",
+        }
+    }
+
+    /// Apply the filter to a text.
+    ///
+    /// # Parameter
+    /// - `s`: The text.
+    ///
+    /// # Return
+    /// The filtered text.
+    pub fn apply(self: &Self, s: &str) -> String {
+        match self {
+            Filter::Upper => s.to_uppercase(),
+            Filter::Lower => s.to_lowercase(),
+            Filter::Capitalize => {
+                let mut r = String::new();
+                let mut done = false;
+                for c in s.chars() {
+                    if !done && c.is_alphanumeric() {
+                        done = true;
+                        if c.is_alphabetic() {
+                            r += &titlecase(c);
+                            continue;
+                        }
+                    }
+                    r.push(c);
+                }
+                r
+            }
+            Filter::Title => {
+                let mut r = String::new();
+                let mut in_word = false;
+                for c in s.chars() {
+                    if c.is_alphanumeric() {
+                        if !in_word && c.is_alphabetic() {
+                            r += &titlecase(c);
+                        } else {
+                            r.push(c);
+                        }
+                        in_word = true;
+                    } else {
+                        // The apostrophes and the combining marks don't split a word.
+                        in_word = in_word && (c == '\'' || c == '\u{2019}' || is_combining_mark(c));
+                        r.push(c);
+                    }
+                }
+                r
+            }
+            Filter::Trim => s.trim().to_string(),
+        }
+    }
+
+    /// Apply the filters to a text in order.
+    ///
+    /// # Parameter
+    /// - `filters`: The filters.
+    /// - `s`: The text.
+    ///
+    /// # Return
+    /// The filtered text.
+    pub(crate) fn apply_all(filters: &[Filter], s: String) -> String {
+        let mut r = s;
+        for f in filters.iter() {
+            r = f.apply(&r);
+        }
+        return r;
+    }
+}
+
+/// Map a character to titlecase.
+///
+/// # Note
+/// The characters whose titlecase differs from their uppercase are mapped by the table in SpecialCasing.txt and UnicodeData.txt of the Unicode Character Database.
+fn titlecase(c: char) -> String {
+    let s: &str = match c {
+        '\u{df}' => "Ss",
+        '\u{1c4}'..='\u{1c6}' => "\u{1c5}",
+        '\u{1c7}'..='\u{1c9}' => "\u{1c8}",
+        '\u{1ca}'..='\u{1cc}' => "\u{1cb}",
+        '\u{1f1}'..='\u{1f3}' => "\u{1f2}",
+        '\u{587}' => "\u{535}\u{582}",
+        '\u{1f80}'..='\u{1f87}' | '\u{1f90}'..='\u{1f97}' | '\u{1fa0}'..='\u{1fa7}' => {
+            // The capital letter with prosgegrammeni is 8 code points after.
+            return char::from_u32(c as u32 + 8).map_or_else(String::new, String::from);
+        }
+        '\u{1f88}'..='\u{1f8f}' | '\u{1f98}'..='\u{1f9f}' | '\u{1fa8}'..='\u{1faf}' => {
+            return c.to_string();
+        }
+        '\u{1fb3}' | '\u{1fbc}' => "\u{1fbc}",
+        '\u{1fc3}' | '\u{1fcc}' => "\u{1fcc}",
+        '\u{1ff3}' | '\u{1ffc}' => "\u{1ffc}",
+        '\u{1fb2}' => "\u{1fba}\u{345}",
+        '\u{1fb4}' => "\u{386}\u{345}",
+        '\u{1fc2}' => "\u{1fca}\u{345}",
+        '\u{1fc4}' => "\u{389}\u{345}",
+        '\u{1ff2}' => "\u{1ffa}\u{345}",
+        '\u{1ff4}' => "\u{38f}\u{345}",
+        '\u{1fb7}' => "\u{391}\u{342}\u{345}",
+        '\u{1fc7}' => "\u{397}\u{342}\u{345}",
+        '\u{1ff7}' => "\u{3a9}\u{342}\u{345}",
+        '\u{fb00}' => "Ff",
+        '\u{fb01}' => "Fi",
+        '\u{fb02}' => "Fl",
+        '\u{fb03}' => "Ffi",
+        '\u{fb04}' => "Ffl",
+        '\u{fb05}' | '\u{fb06}' => "St",
+        '\u{fb13}' => "\u{544}\u{576}",
+        '\u{fb14}' => "\u{544}\u{565}",
+        '\u{fb15}' => "\u{544}\u{56b}",
+        '\u{fb16}' => "\u{54e}\u{576}",
+        '\u{fb17}' => "\u{544}\u{56d}",
+        _ => return c.to_uppercase().collect(),
+    };
+    return s.to_string();
+}
+
+/// Is a character a combining mark that continues a word?
+///
+/// # Note
+/// Only the blocks of the combining diacritical marks are checked.
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{300}'..='\u{36f}'
+            | '\u{1ab0}'..='\u{1aff}'
+            | '\u{1dc0}'..='\u{1dff}'
+            | '\u{20d0}'..='\u{20ff}'
+            | '\u{fe20}'..='\u{fe2f}'
+    )
+}
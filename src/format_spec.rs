@@ -0,0 +1,332 @@
+//! The format spec of an expansion
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::ContextValue;
+use crate::Locale;
+
+/// The alignment of the formatted value in the width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    /// "<"
+    Left,
+    /// "^"
+    Center,
+    /// ">"
+    Right,
+}
+impl Align {
+    /// The alignment that a character means.
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        }
+    }
+}
+
+/// The format spec that follows ":" in an expansion, e.g. "03" in "{N:03}".
+///
+/// # Note
+/// The syntax is a subset of the format spec of Rust, and "," is added to group the digits:
+/// ```EBNF
+/// format_spec = [ [ fill ], align ], [ "+" ], [ "0" ], [ width ], [ "," ], [ ".", precision ] ;
+/// fill = ? [^{}] ? ;
+/// align = "<" | "^" | ">" ;
+/// width = { ? [0-9] ? } ; (* Up to 65535. *)
+/// precision = { ? [0-9] ? } ; (* Up to 65535. *)
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FormatSpec {
+    /// The source of the spec.
+    source: String,
+    /// The character to fill the width.
+    fill: char,
+    /// The alignment, or [`None`] for the default alignment.
+    align: Option<Align>,
+    /// Show "+" for the non-negative numbers?
+    plus: bool,
+    /// Fill the width with "0" after the sign of the numbers?
+    zero: bool,
+    /// The minimum width in characters.
+    width: usize,
+    /// Group the digits of the integer part?
+    grouping: bool,
+    /// The number of the digits after the decimal separator, or the maximum number of the characters of the texts.
+    precision: Option<usize>,
+}
+impl Default for FormatSpec {
+    /// The spec that doesn't change the value.
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            fill: ' ',
+            align: None,
+            plus: false,
+            zero: false,
+            width: 0,
+            grouping: false,
+            precision: None,
+        }
+    }
+}
+impl FormatSpec {
+    /// Parse a format spec.
+    ///
+    /// # Parameter
+    /// - `spec`: The format spec.
+    ///
+    /// # Return
+    /// The spec, or [`None`] if `spec` is empty or invalid.
+    pub(crate) fn parse(spec: &str) -> Option<Self> {
+        let chars: Vec<char> = spec.chars().collect();
+        if chars.is_empty() {
+            return None;
+        }
+        let mut r = Self {
+            source: spec.to_string(),
+            ..Self::default()
+        };
+        let mut i = 0;
+        if let Some(align) = chars.get(1).and_then(|c| Align::from_char(*c)) {
+            if chars[0] == '{' || chars[0] == '}' {
+                return None;
+            }
+            r.fill = chars[0];
+            r.align = Some(align);
+            i = 2;
+        } else if let Some(align) = Align::from_char(chars[0]) {
+            r.align = Some(align);
+            i = 1;
+        }
+        if chars.get(i) == Some(&'+') {
+            r.plus = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            r.zero = true;
+            i += 1;
+        }
+        if let Some(width) = parse_number(&chars, &mut i) {
+            r.width = width?;
+        }
+        if chars.get(i) == Some(&',') {
+            r.grouping = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            r.precision = Some(parse_number(&chars, &mut i)??);
+        }
+        return if i == chars.len() { Some(r) } else { None };
+    }
+
+    /// The source of the spec.
+    pub(crate) fn source(self: &Self) -> &str {
+        &self.source
+    }
+
+    /// Format a text.
+    ///
+    /// # Parameter
+    /// - `s`: The text.
+    ///
+    /// # Return
+    /// The text truncated to the precision and padded to the width, aligned to the left by default.
+    ///
+    /// # Note
+    /// The text that is a decimal number after it's truncated, e.g. "-5" or "1.5", is padded with "0" after the sign if "0" is specified, as if it's a number.
+    pub(crate) fn format_text(self: &Self, s: &str) -> String {
+        let body: String = match self.precision {
+            Some(p) => s.chars().take(p).collect(),
+            None => s.to_string(),
+        };
+        if self.zero {
+            let (sign, digits) = match body.strip_prefix(['+', '-']) {
+                Some(digits) => body.split_at(body.len() - digits.len()),
+                None => ("", body.as_str()),
+            };
+            if is_decimal(digits) {
+                return self.pad(sign, digits, Align::Right);
+            }
+        }
+        return self.pad("", &body, Align::Left);
+    }
+
+    /// Format a value in the external context.
+    ///
+    /// # Parameter
+    /// - `value`: The value.
+    /// - `locale`: The locale to format the numbers.
+    ///
+    /// # Return
+    /// The formatted value, or [`None`] if it's a list.
+    pub(crate) fn format_value(
+        self: &Self,
+        value: &ContextValue<'_>,
+        locale: &Locale,
+    ) -> Option<String> {
+        match value {
+            ContextValue::Text(t) => Some(self.format_text(t)),
+            ContextValue::List(_) => None,
+            ContextValue::Integer(i) => Some(self.format_integer(*i, locale)),
+            ContextValue::Float(f) => Some(self.format_float(*f, locale)),
+        }
+    }
+
+    /// The length of a formatted text.
+    ///
+    /// # Parameter
+    /// - `len`: The length of the text before it's formatted.
+    ///
+    /// # Return
+    /// The length after [`format_text()`] is applied, which doesn't decrease as `len` increases.
+    ///
+    /// [`format_text()`]: #method.format_text
+    pub(crate) fn text_length(self: &Self, len: usize) -> usize {
+        let len = match self.precision {
+            Some(p) => len.min(p),
+            None => len,
+        };
+        return len.max(self.width);
+    }
+
+    /// Format an integer.
+    fn format_integer(self: &Self, i: i64, locale: &Locale) -> String {
+        let digits = i.unsigned_abs().to_string();
+        let body = if self.grouping {
+            locale.group(&digits)
+        } else {
+            digits
+        };
+        return self.pad(self.sign(i < 0), &body, Align::Right);
+    }
+
+    /// Format a floating point number.
+    fn format_float(self: &Self, f: f64, locale: &Locale) -> String {
+        if f.is_nan() {
+            return self.pad("", "NaN", Align::Right);
+        }
+        let s = match self.precision {
+            Some(p) => format!("{:.*}", p, f.abs()),
+            None => f.abs().to_string(),
+        };
+        let body = match s.split_once('.') {
+            _ if f.is_infinite() => s,
+            Some((int, frac)) => {
+                let mut r = if self.grouping {
+                    locale.group(int)
+                } else {
+                    int.to_string()
+                };
+                r += locale.decimal_separator();
+                r += frac;
+                r
+            }
+            None if self.grouping => locale.group(&s),
+            None => s,
+        };
+        return self.pad(self.sign(f < 0.0), &body, Align::Right);
+    }
+
+    /// The sign of a number.
+    fn sign(self: &Self, negative: bool) -> &'static str {
+        if negative {
+            "-"
+        } else if self.plus {
+            "+"
+        } else {
+            ""
+        }
+    }
+
+    /// Pad a value to the width.
+    ///
+    /// # Parameter
+    /// - `sign`: The sign of the number, or the empty string.
+    /// - `body`: The value without the sign.
+    /// - `default_align`: The alignment if it isn't specified.
+    fn pad(self: &Self, sign: &str, body: &str, default_align: Align) -> String {
+        let len = sign.chars().count() + body.chars().count();
+        let n = self.width.saturating_sub(len);
+        let mut r = String::new();
+        if n == 0 {
+            r += sign;
+            r += body;
+            return r;
+        }
+        if self.zero && self.align.is_none() && default_align == Align::Right {
+            r += sign;
+            for _ in 0..n {
+                r.push('0');
+            }
+            r += body;
+            return r;
+        }
+        let (left, right) = match self.align.unwrap_or(default_align) {
+            Align::Left => (0, n),
+            Align::Center => (n / 2, n - n / 2),
+            Align::Right => (n, 0),
+        };
+        for _ in 0..left {
+            r.push(self.fill);
+        }
+        r += sign;
+        r += body;
+        for _ in 0..right {
+            r.push(self.fill);
+        }
+        return r;
+    }
+}
+
+/// Is a text a decimal number without the sign?
+///
+/// # Parameter
+/// - `s`: The text.
+///
+/// # Return
+/// `true` if `s` has the digits and at most one ".", e.g. "5", "1.5", or ".5".
+fn is_decimal(s: &str) -> bool {
+    return s.chars().any(|c| c.is_ascii_digit())
+        && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && s.chars().filter(|c| *c == '.').count() <= 1;
+}
+
+/// Parse the digits of a number in a format spec.
+///
+/// # Parameter
+/// - `chars`: The characters of the spec.
+/// - `i`: The position of the digits, which is moved after them.
+///
+/// # Return
+/// [`None`] if there is no digit, `Some(None)` if the number is more than 65535, or the number.
+fn parse_number(chars: &[char], i: &mut usize) -> Option<Option<usize>> {
+    let start = *i;
+    while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+        *i += 1;
+    }
+    if start == *i {
+        return None;
+    }
+    let s: String = chars[start..*i].iter().collect();
+    return Some(s.parse::<u16>().ok().map(usize::from));
+}
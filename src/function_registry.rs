@@ -0,0 +1,101 @@
+//! The functions called in the phrase syntax
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Context;
+use crate::KeyedRng;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The type of the function called by "{@name args}" in the phrase syntax.
+///
+/// The parameters are the arguments split by the spaces, the external context, and the random number generator seeded for the call. The function returns the text that the call is expanded into.
+///
+/// # Note
+/// The random number generator is seeded by the random number generator of [`Generator`], so the phrases are reproducible by [`Generator::with_seed()`]. It's seeded by the call itself where the options are selected without the random numbers, e.g. [`Generator::nth()`] and [`Generator::generate_from_derivation()`].
+///
+/// [`Generator`]: struct.Generator.html
+/// [`Generator::with_seed()`]: struct.Generator.html#method.with_seed
+/// [`Generator::nth()`]: struct.Generator.html#method.nth
+/// [`Generator::generate_from_derivation()`]: struct.Generator.html#method.generate_from_derivation
+pub type Function = dyn Fn(&[&str], &dyn Context, &mut KeyedRng) -> String + Send + Sync;
+
+/// The functions registered by their names.
+#[derive(Clone, Default)]
+pub(crate) struct FunctionRegistry {
+    /// The functions.
+    functions: HashMap<String, Arc<Function>>,
+}
+impl FunctionRegistry {
+    /// Create an empty registry.
+    pub(crate) fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Register a function.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function.
+    /// - `function`: The function. It replaces the function already registered by the same name.
+    pub(crate) fn insert(self: &mut Self, name: &str, function: Arc<Function>) {
+        self.functions.insert(name.to_string(), function);
+    }
+
+    /// Unregister a function.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function.
+    ///
+    /// # Return
+    /// `true` if the function was registered.
+    pub(crate) fn remove(self: &mut Self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    /// The function registered by a name.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function.
+    ///
+    /// # Return
+    /// The function, or [`None`] if it isn't registered.
+    pub(crate) fn get(self: &Self, name: &str) -> Option<&Function> {
+        self.functions.get(name).map(|f| f.as_ref())
+    }
+
+    /// Is a function registered by a name?
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function.
+    pub(crate) fn contains(self: &Self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+}
+impl std::fmt::Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&String> = self.functions.keys().collect();
+        names.sort();
+        f.debug_struct("FunctionRegistry")
+            .field("functions", &names)
+            .finish()
+    }
+}
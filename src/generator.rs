@@ -18,12 +18,61 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 
+use crate::apply_temperature;
+use crate::choose;
 use crate::parser::data::Syntax;
 use crate::select_and_generate_text;
+use crate::selection_probability;
+use crate::Ambiguity;
+use crate::BannedFinder;
+use crate::BannedMatch;
 use crate::CompileError;
+use crate::Context;
+use crate::Derivation;
+use crate::DerivationError;
+use crate::Environment;
 use crate::ExtContext;
+use crate::ExtLengthBounds;
+use crate::Filter;
+use crate::FixedContext;
+use crate::Function;
+use crate::FunctionRegistry;
+use crate::History;
+use crate::HistorySelector;
+use crate::KeyedRng;
+use crate::LengthRange;
+use crate::LengthSampler;
+use crate::LengthUnit;
+use crate::Locale;
+use crate::Permutation;
+use crate::Phrases;
 use crate::RandomNumberGenerator;
+use crate::RangeAnalyzer;
+use crate::ReplaySelector;
+use crate::RetryStats;
+use crate::RngSelector;
+use crate::SeedableRandomNumberGenerator;
+use crate::ShuffleBag;
 use crate::Substitutor;
+use crate::TemperatureSelector;
+use crate::Trace;
+use crate::TraceSelector;
+use crate::WeightError;
+use crate::ENUMERATION_LIMIT;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// The maximum number of the attempts to generate a phrase not in the history.
+const HISTORY_MAX_ATTEMPTS: usize = 100;
+
+/// The maximum number of the attempts to generate a phrase not longer than the limit.
+const LENGTH_MAX_ATTEMPTS: usize = 100;
+
+/// The default maximum number of the attempts to generate a phrase that satisfies a predicate.
+const DEFAULT_MAX_ATTEMPTS: usize = 100;
 
 /// The type of Syntax ID. Used when removing a syntax from a generator.
 pub type SyntaxId = usize;
@@ -68,6 +117,20 @@ pub type SyntaxId = usize;
 /// # }
 /// ```
 ///
+/// The phrase generator is [`Send`] and [`Sync`] with the default random number generator and substitutor, so it can be moved to or shared with other threads.
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let ph: tphrase::Generator = "main = Hello, World!".parse()?;
+/// let handle = std::thread::spawn(move || {
+///     let mut ph = ph;
+///     ph.generate()
+/// });
+/// assert_eq!(handle.join().unwrap(), "Hello, World!");
+/// # Ok(())
+/// # }
+/// ```
+///
 /// [`Err`] from [`add()`] and [`from_str()`] holds some human readable error messages.
 /// ```rust
 /// let mut ph_result: Result<tphrase::Generator, _> = "start = Hello, World!".parse();
@@ -103,6 +166,26 @@ pub struct Generator<
     ids: Vec<SyntaxId>,
     /// Random number generator.
     rng: R,
+    /// The shuffle bag used if the repetition is avoided.
+    shuffle_bag: Option<ShuffleBag>,
+    /// The number of the recent phrases to be avoided.
+    phrase_window: usize,
+    /// The number of the recent options to be avoided for each nonterminal.
+    option_windows: HashMap<String, usize>,
+    /// The history of the recent phrases and options.
+    history: History,
+    /// The maximum number of the attempts to generate a phrase that satisfies a predicate.
+    max_attempts: usize,
+    /// The statistics of the retries.
+    retry_stats: RetryStats,
+    /// The temperature to sharpen or flatten the chance.
+    temperature: f64,
+    /// The locale to format the numbers in the external context.
+    locale: Locale,
+    /// The filters applied to the whole phrase.
+    post_filters: Vec<Filter>,
+    /// The functions called in the phrase syntaxes.
+    functions: FunctionRegistry,
 }
 impl<R: RandomNumberGenerator, S: Substitutor> Default for Generator<R, S> {
     fn default() -> Self {
@@ -132,6 +215,16 @@ impl<R: RandomNumberGenerator, S: Substitutor> Generator<R, S> {
             equalized_chance: false,
             ids: Vec::new(),
             rng: R::new(),
+            shuffle_bag: None,
+            phrase_window: 0,
+            option_windows: HashMap::new(),
+            history: History::new(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_stats: RetryStats::new(),
+            temperature: 1.0,
+            locale: Locale::default(),
+            post_filters: Vec::new(),
+            functions: FunctionRegistry::new(),
         }
     }
 
@@ -159,145 +252,1938 @@ impl<R: RandomNumberGenerator, S: Substitutor> Generator<R, S> {
     /// # Note
     /// - The empty generator creates "nil".
     /// - `self` is mut because `rng` is mut.
-    pub fn generate_with_context(self: &mut Self, ext_context: &ExtContext) -> String {
+    pub fn generate_with_context(self: &mut Self, ext_context: &dyn Context) -> String {
+        if let Some(derivation) = self.next_derivation_from_shuffle_bag() {
+            return select_and_generate_text(
+                &self.syntaxes,
+                &self.weights,
+                self.equalized_chance,
+                &Environment::new(
+                    &FixedContext::new(ext_context),
+                    &self.locale,
+                    &self.post_filters,
+                    &self.functions,
+                ),
+                &mut ReplaySelector::new(&derivation),
+            );
+        }
+        if self.uses_history() {
+            return self.generate_avoiding_history(ext_context, false).0;
+        }
         select_and_generate_text(
             &self.syntaxes,
             &self.weights,
             self.equalized_chance,
-            &ext_context,
-            &mut self.rng,
+            &Environment::new(
+                ext_context,
+                &self.locale,
+                &self.post_filters,
+                &self.functions,
+            ),
+            &mut TemperatureSelector::new(RngSelector::new(&mut self.rng), self.temperature),
         )
     }
 
-    /// Add a phrase syntax.
+    /// Generate a phrase with the derivation trace.
+    ///
+    /// # Return
+    /// A phrase and the trace how it's derived.
+    ///
+    /// # Note
+    /// - The empty generator creates "nil" and the empty trace.
+    /// - The random number generator is used in the same way as [`generate()`], so the phrase is the same as the one that [`generate()`] would create.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = {A}{B}\nA = a | b\nB = c | d".parse()?;
+    /// let (phrase, trace) = ph.generate_traced();
+    /// let root = trace.root().unwrap();
+    /// let a = &root.children()[0];
+    /// assert_eq!(a.nonterminal(), Some("A"));
+    /// assert_eq!(a.post_gsub(), ["a", "b"][a.option_index()]);
+    /// assert_eq!(phrase, root.post_gsub());
+    /// println!("{}", trace);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`generate()`]: #method.generate
+    pub fn generate_traced(self: &mut Self) -> (String, Trace) {
+        let no_context = super::ExtContext::new();
+        return self.generate_traced_with_context(&no_context);
+    }
+
+    /// Generate a phrase with the derivation trace using an external context.
     ///
     /// # Parameter
-    /// - `syntax`: The phrase syntax to be added.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
     ///
     /// # Return
-    /// ID for the syntax added into the instance, or a human readable error message if no phrase syntax is added.
+    /// A phrase and the trace how it's derived.
     ///
     /// # Note
-    /// - Only the phrase syntax that contains the nonterminal "main" can be added.
-    /// - The recursive reference to a nonterminal is not allowed.
-    /// - The syntax ID is unique only in `self`.
-    pub fn add(self: &mut Self, syntax: Syntax<S>) -> Result<SyntaxId, CompileError> {
-        self.add_with_start_condition(syntax, "main")
+    /// See [`generate_traced()`].
+    ///
+    /// [`generate_traced()`]: #method.generate_traced
+    pub fn generate_traced_with_context(
+        self: &mut Self,
+        ext_context: &dyn Context,
+    ) -> (String, Trace) {
+        if let Some(derivation) = self.next_derivation_from_shuffle_bag() {
+            let mut selector = TraceSelector::new(ReplaySelector::new(&derivation));
+            let s = select_and_generate_text(
+                &self.syntaxes,
+                &self.weights,
+                self.equalized_chance,
+                &Environment::new(
+                    &FixedContext::new(ext_context),
+                    &self.locale,
+                    &self.post_filters,
+                    &self.functions,
+                ),
+                &mut selector,
+            );
+            return (s, selector.into_trace(&self.ids));
+        }
+        if self.uses_history() {
+            let (s, trace) = self.generate_avoiding_history(ext_context, true);
+            return (s, trace.unwrap_or_default());
+        }
+        let mut selector = TraceSelector::new(TemperatureSelector::new(
+            RngSelector::new(&mut self.rng),
+            self.temperature,
+        ));
+        let s = select_and_generate_text(
+            &self.syntaxes,
+            &self.weights,
+            self.equalized_chance,
+            &Environment::new(
+                ext_context,
+                &self.locale,
+                &self.post_filters,
+                &self.functions,
+            ),
+            &mut selector,
+        );
+        return (s, selector.into_trace(&self.ids));
     }
 
-    /// Add a phrase syntax.
+    /// Is the history used?
+    fn uses_history(self: &Self) -> bool {
+        self.phrase_window > 0 || !self.option_windows.is_empty()
+    }
+
+    /// Generate a phrase avoiding the recent phrases and options, and add it into the history.
     ///
     /// # Parameter
-    /// - `syntax`: The phrase syntax to be added.
-    /// - `start_condition`: The name of the nonterminal where is the start condition.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `traced`: Create the trace.
     ///
     /// # Return
-    /// ID for the syntax added into the instance, or a human readable error message if no phrase syntax is added.
+    /// A phrase, and the trace if `traced` is true.
     ///
     /// # Note
-    /// - Only the phrase syntax that contains the start condition can be added.
-    /// - The recursive reference to a nonterminal is not allowed.
-    /// - The syntax ID is unique only in `self`.
-    pub fn add_with_start_condition(
+    /// The phrase in the history is rejected up to [`HISTORY_MAX_ATTEMPTS`] times, which is equivalent to the renormalization of the weights among the other phrases. The last attempt is accepted if all the attempts are rejected.
+    fn generate_avoiding_history(
         self: &mut Self,
-        mut syntax: Syntax<S>,
-        start_condition: &str,
-    ) -> Result<SyntaxId, CompileError> {
-        syntax.bind_syntax(start_condition)?;
-        let new_weight = syntax.weight();
-        self.syntaxes.push(syntax);
-        self.weights.push(self.weight() + new_weight);
-        let id = match self.ids.last() {
-            Some(x) => {
-                if *x < std::usize::MAX {
-                    *x + 1
-                } else {
-                    let mut compile_error = CompileError::new();
-                    compile_error.add_error_message("Too many syntaxes".to_string());
-                    return Err(compile_error);
-                }
+        ext_context: &dyn Context,
+        traced: bool,
+    ) -> (String, Option<Trace>) {
+        let mut result = (String::new(), None);
+        let mut pending = Vec::new();
+        for _ in 0..HISTORY_MAX_ATTEMPTS {
+            let selector = HistorySelector::new(
+                &self.history,
+                &self.option_windows,
+                TemperatureSelector::new(RngSelector::new(&mut self.rng), self.temperature),
+            );
+            if traced {
+                let mut selector = TraceSelector::new(selector);
+                let s = select_and_generate_text(
+                    &self.syntaxes,
+                    &self.weights,
+                    self.equalized_chance,
+                    &Environment::new(
+                        ext_context,
+                        &self.locale,
+                        &self.post_filters,
+                        &self.functions,
+                    ),
+                    &mut selector,
+                );
+                pending = selector.inner().pending().to_vec();
+                result = (s, Some(selector.into_trace(&self.ids)));
+            } else {
+                let mut selector = selector;
+                let s = select_and_generate_text(
+                    &self.syntaxes,
+                    &self.weights,
+                    self.equalized_chance,
+                    &Environment::new(
+                        ext_context,
+                        &self.locale,
+                        &self.post_filters,
+                        &self.functions,
+                    ),
+                    &mut selector,
+                );
+                pending = selector.pending().to_vec();
+                result = (s, None);
             }
-            None => 1,
-        };
-        self.ids.push(id);
-        return Ok(id);
+            if !self.history.contains_phrase(&result.0, self.phrase_window) {
+                break;
+            }
+        }
+        for (name, i) in pending.iter() {
+            if let Some(w) = self.option_windows.get(name) {
+                self.history.push_option(name, *i, *w);
+            }
+        }
+        if self.phrase_window > 0 {
+            self.history
+                .push_phrase(result.0.clone(), self.phrase_window);
+        }
+        return result;
     }
 
-    /// Remove a phrase syntax.
+    /// The derivation of the next phrase if the repetition is avoided.
+    ///
+    /// # Return
+    /// The derivation, or [`None`] if the repetition isn't avoided or the instance is empty.
+    fn next_derivation_from_shuffle_bag(self: &mut Self) -> Option<Derivation> {
+        let n = self.combination_number();
+        let k = self.shuffle_bag.as_mut()?.next(n, &mut self.rng)?;
+        return self.nth_derivation(k);
+    }
+
+    /// Generate the distinct phrases selected randomly.
     ///
     /// # Parameter
-    /// - `id`: ID for the phrase syntax.
+    /// - `k`: The number of the phrases.
     ///
     /// # Return
-    /// [`Ok`] if the phrase syntax is deleted. [`Err`] if ID doesn't exist in `self`.
+    /// `k` phrases generated by the distinct derivations, or all the [`combination_number()`] phrases in random order if `k` is greater.
     ///
     /// # Note
-    /// - This is an O(n) function, because it's assumed that the function is not used frequently.
-    /// -  The ID for the removed phrase syntax may be reused by [`add()`].
+    /// - Every derivation has the same chance regardless of the weights.
+    /// - Some phrases may be the same if the syntax is ambiguous.
+    /// - The derivations aren't stored, so it works for the syntax that has a huge number of the combinations.
     ///
-    /// [`add()`]: #method.add
-    pub fn remove(self: &mut Self, syntax_id: SyntaxId) -> Result<(), SyntaxRemoveError> {
-        let i = match self.ids.binary_search(&syntax_id) {
-            Ok(x) => x,
-            Err(_) => return Err(SyntaxRemoveError::new()),
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = {A}{A}{A}\nA = 0|1|2|3|4|5|6|7|8|9".parse()?;
+    /// let phrases = ph.sample_distinct(10);
+    /// assert_eq!(phrases.len(), 10);
+    /// let set: std::collections::HashSet<_> = phrases.iter().collect();
+    /// assert_eq!(set.len(), 10);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`combination_number()`]: #method.combination_number
+    pub fn sample_distinct(self: &mut Self, k: usize) -> Vec<String> {
+        let no_context = super::ExtContext::new();
+        return self.sample_distinct_with_context(k, &no_context);
+    }
+
+    /// Generate the distinct phrases selected randomly, using an external context.
+    ///
+    /// # Parameter
+    /// - `k`: The number of the phrases.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// `k` phrases generated by the distinct derivations.
+    ///
+    /// # Note
+    /// See [`sample_distinct()`].
+    ///
+    /// [`sample_distinct()`]: #method.sample_distinct
+    pub fn sample_distinct_with_context(
+        self: &mut Self,
+        k: usize,
+        ext_context: &dyn Context,
+    ) -> Vec<String> {
+        let permutation = Permutation::with_rng(self.combination_number(), &mut self.rng);
+        return (0..k.min(permutation.len()))
+            .filter_map(|i| self.nth(permutation.get(i), ext_context))
+            .collect();
+    }
+
+    /// Generate a phrase not longer than a limit.
+    ///
+    /// # Parameter
+    /// - `max_len`: The maximum length of the phrase.
+    /// - `unit`: The unit of the length.
+    ///
+    /// # Return
+    /// A phrase, or [`None`] if no phrase fits in the limit.
+    ///
+    /// # Note
+    /// See [`generate_with_max_len_and_context()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = r#"
+    ///     main = {size} apple
+    ///     size = small | big | enormous
+    /// "#.parse()?;
+    /// assert_eq!(
+    ///     ph.generate_with_max_len(9, tphrase::LengthUnit::Chars),
+    ///     Some("big apple".to_string())
+    /// );
+    /// assert_eq!(ph.generate_with_max_len(8, tphrase::LengthUnit::Chars), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`generate_with_max_len_and_context()`]: #method.generate_with_max_len_and_context
+    pub fn generate_with_max_len(
+        self: &mut Self,
+        max_len: usize,
+        unit: LengthUnit,
+    ) -> Option<String> {
+        self.generate_with_max_len_and_context(max_len, unit, &ExtContext::new())
+    }
+
+    /// Generate a phrase not longer than a limit, using an external context.
+    ///
+    /// # Parameter
+    /// - `max_len`: The maximum length of the phrase.
+    /// - `unit`: The unit of the length.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// A phrase, or [`None`] if no phrase fits in the limit.
+    ///
+    /// # Note
+    /// - The phrase is selected by the weights among the phrases that fit in the limit, i.e. the chance of each phrase is proportional to the one in [`generate_with_context()`].
+    /// - The length of a phrase is estimated as the sum of the lengths of its parts, which is exact with [`LengthUnit::Chars`] unless a gsub changes the length. The generated phrase is measured again, and the generation is retried some times if it's too long, so [`None`] may be returned even if a phrase fits.
+    /// - The repetition avoidance and the history aren't used.
+    ///
+    /// [`generate_with_context()`]: #method.generate_with_context
+    pub fn generate_with_max_len_and_context(
+        self: &mut Self,
+        max_len: usize,
+        unit: LengthUnit,
+        ext_context: &dyn Context,
+    ) -> Option<String> {
+        if self.syntaxes.is_empty() {
+            let s = self.generate_with_context(ext_context);
+            return if unit.measure(&s) <= max_len {
+                Some(s)
+            } else {
+                None
+            };
+        }
+        let mut sampler =
+            LengthSampler::new(unit, max_len, ext_context, &self.locale, self.temperature);
+        sampler.fit_max_len(&self.syntaxes);
+        let width = sampler.max_len().checked_add(1)?;
+        let syntax_weights = if self.equalized_chance {
+            Cow::Borrowed(&self.weights[..])
+        } else {
+            apply_temperature(&self.weights, self.temperature)
         };
-        self.ids.remove(i);
-        self.syntaxes.remove(i);
-        self.weights.pop();
-        let mut sum: f64 = 0.0;
-        if i >= 1 {
-            sum = self.weights[i - 1];
+        let mut weights = Vec::new();
+        for (i, syntax) in self.syntaxes.iter().enumerate() {
+            let p = selection_probability(&syntax_weights, self.equalized_chance, i);
+            for x in sampler.syntax_distribution(syntax) {
+                weights.push(p * x);
+            }
         }
-        for j in i..self.syntaxes.len() {
-            sum += self.syntaxes[j].weight();
-            self.weights[j] = sum;
+        for _ in 0..LENGTH_MAX_ATTEMPTS {
+            let k = choose(&weights, &mut self.rng)?;
+            let (i, len) = (k / width, k % width);
+            let mut indices = vec![i];
+            sampler.sample_syntax(&self.syntaxes[i], len, &mut self.rng, &mut indices);
+            let s = self
+                .generate_from_derivation(&Derivation::new(indices), ext_context)
+                .ok()?;
+            if unit.measure(&s) <= max_len {
+                return Some(s);
+            }
         }
-        return Ok(());
+        return None;
     }
 
-    /// Clear the syntaxes and create an empty phrase generator.
-    pub fn clear(self: &mut Self) {
-        self.syntaxes.clear();
-        self.weights.clear();
-        self.ids.clear();
+    /// The range of the length of the phrases generated by the instance.
+    ///
+    /// # Parameter
+    /// - `unit`: The unit of the length.
+    /// - `ext_bounds`: The bounds of the length of the values in the external context. The nonterminal that isn't in the syntaxes nor in the bounds is measured as its name, as the generated phrase has it.
+    ///
+    /// # Return
+    /// The range covering all the syntaxes.
+    ///
+    /// # Note
+    /// - The length of a text is estimated as the sum of the length of its parts, and the gsubs are assumed not to change the length. [`LengthRange::is_approximate()`] is `true` if the estimation may be wrong.
+    /// - The syntaxes and the options that are never selected because their weight is 0 are ignored.
+    /// - The empty generator has the range of "nil".
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = Hello, {NAME}! | Hi!
+    /// "#.parse()?;
+    /// let bounds = tphrase::ExtLengthBounds::from([
+    ///     ("NAME".to_string(), tphrase::LengthRange::new(1, 20)),
+    /// ]);
+    /// let range = ph.length_range(tphrase::LengthUnit::Chars, &bounds);
+    /// assert_eq!((range.min(), range.max()), (3, 28));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`LengthRange::is_approximate()`]: struct.LengthRange.html#method.is_approximate
+    pub fn length_range(
+        self: &Self,
+        unit: LengthUnit,
+        ext_bounds: &ExtLengthBounds,
+    ) -> LengthRange {
+        let mut analyzer = RangeAnalyzer::new(unit, ext_bounds);
+        let mut range: Option<LengthRange> = None;
+        for (i, syntax) in self.syntaxes.iter().enumerate() {
+            if selection_probability(&self.weights, self.equalized_chance, i) > 0.0 {
+                if let Some(x) = analyzer.syntax_range(syntax) {
+                    range = Some(range.map_or(x, |y| y.union(x)));
+                }
+            }
+        }
+        return range.unwrap_or(LengthRange::exact(unit.measure("nil")));
     }
 
-    /// Equalize the chance to select each phrase syntax.
+    /// The ranges of the length of the texts generated by the nonterminals in the instance.
     ///
     /// # Parameter
-    /// - `enable`: equalized if `enable` is true. If not, the chance depends on the weight of the phrase syntax. (Default)
-    pub fn equalize_chance(self: &mut Self, enable: bool) {
-        self.equalized_chance = enable;
+    /// - `unit`: The unit of the length.
+    /// - `ext_bounds`: The bounds of the length of the values in the external context.
+    ///
+    /// # Return
+    /// The ranges of the nonterminals referred directly or indirectly by the start conditions. The range of a nonterminal in multiple syntaxes covers all of them.
+    ///
+    /// # Note
+    /// See [`length_range()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = r#"
+    ///     main = {size} apple
+    ///     size = small | big
+    /// "#.parse()?;
+    /// let _ = ph.add(r#"
+    ///     main = {size} orange
+    ///     size = enormous
+    /// "#.parse()?)?;
+    /// let ranges = ph.length_ranges(tphrase::LengthUnit::Chars, &tphrase::ExtLengthBounds::new());
+    /// assert_eq!(ranges["main"].to_string(), "9..=15");
+    /// assert_eq!(ranges["size"].to_string(), "3..=8");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`length_range()`]: #method.length_range
+    pub fn length_ranges(
+        self: &Self,
+        unit: LengthUnit,
+        ext_bounds: &ExtLengthBounds,
+    ) -> BTreeMap<String, LengthRange> {
+        let mut analyzer = RangeAnalyzer::new(unit, ext_bounds);
+        let mut ranges: BTreeMap<String, LengthRange> = BTreeMap::new();
+        for syntax in self.syntaxes.iter() {
+            for (name, x) in analyzer.nonterminal_ranges(syntax) {
+                ranges
+                    .entry(name)
+                    .and_modify(|y| *y = y.union(x))
+                    .or_insert(x);
+            }
+        }
+        return ranges;
     }
 
-    /// The number of the syntaxes in the instance.
+    /// Generate a phrase that satisfies a predicate.
+    ///
+    /// # Parameter
+    /// - `predicate`: The predicate that returns `true` for an acceptable phrase.
     ///
     /// # Return
-    /// The number of the syntaxes in the instance.
-    pub fn number_of_syntax(self: &Self) -> usize {
-        self.syntaxes.len()
+    /// A phrase, or [`None`] if no acceptable phrase is generated in the attempts.
+    ///
+    /// # Note
+    /// See [`generate_where_with_context()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = {A}{B}\nA = a | b\nB = x | y".parse()?;
+    /// let s = ph.generate_where(|s| !s.contains("bx")).unwrap();
+    /// assert!(s == "ax" || s == "ay" || s == "by");
+    /// assert_eq!(ph.generate_where(|s| s.len() > 2), None);
+    /// assert_eq!(ph.retry_stats().failures(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`generate_where_with_context()`]: #method.generate_where_with_context
+    pub fn generate_where<F: FnMut(&str) -> bool>(self: &mut Self, predicate: F) -> Option<String> {
+        self.generate_where_with_context(&ExtContext::new(), predicate)
     }
 
-    /// The sum of the weight of the syntaxes in the instance.
+    /// Generate a phrase that satisfies a predicate, using an external context.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `predicate`: The predicate that returns `true` for an acceptable phrase.
     ///
     /// # Return
-    /// The sum of the weight of the syntaxes in the instance.
-    pub fn weight(self: &Self) -> f64 {
-        if let Some(x) = self.weights.last() {
-            *x
-        } else {
-            0.0
+    /// A phrase, or [`None`] if no acceptable phrase is generated in the attempts.
+    ///
+    /// # Note
+    /// - The phrases are generated by [`generate_with_context()`] until one of them is accepted, at most [`max_attempts()`] times.
+    /// - The attempts are recorded in [`retry_stats()`].
+    ///
+    /// [`generate_with_context()`]: #method.generate_with_context
+    /// [`max_attempts()`]: #method.max_attempts
+    /// [`retry_stats()`]: #method.retry_stats
+    pub fn generate_where_with_context<F: FnMut(&str) -> bool>(
+        self: &mut Self,
+        ext_context: &dyn Context,
+        mut predicate: F,
+    ) -> Option<String> {
+        let mut attempts = 0;
+        while attempts < self.max_attempts {
+            attempts += 1;
+            let s = self.generate_with_context(ext_context);
+            if predicate(&s) {
+                self.retry_stats.record(attempts as u64, true);
+                return Some(s);
+            }
         }
+        self.retry_stats.record(attempts as u64, false);
+        return None;
     }
 
-    /// The number of the possible phrases generated by the instance.
+    /// The maximum number of the attempts in [`generate_where()`]. (Default: 100)
+    ///
+    /// [`generate_where()`]: #method.generate_where
+    pub fn max_attempts(self: &Self) -> usize {
+        self.max_attempts
+    }
+
+    /// Set the maximum number of the attempts in [`generate_where()`].
+    ///
+    /// # Parameter
+    /// - `max_attempts`: The maximum number of the attempts.
+    ///
+    /// [`generate_where()`]: #method.generate_where
+    pub fn set_max_attempts(self: &mut Self, max_attempts: usize) {
+        self.max_attempts = max_attempts;
+    }
+
+    /// The statistics of the retries in [`generate_where()`] since the instance is created or [`reset_retry_stats()`] is called.
+    ///
+    /// [`generate_where()`]: #method.generate_where
+    /// [`reset_retry_stats()`]: #method.reset_retry_stats
+    pub fn retry_stats(self: &Self) -> RetryStats {
+        self.retry_stats
+    }
+
+    /// Clear the statistics of the retries.
+    pub fn reset_retry_stats(self: &mut Self) {
+        self.retry_stats = RetryStats::new();
+    }
+
+    /// Find the banned words that the instance can generate.
+    ///
+    /// # Parameter
+    /// - `words`: The banned words.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
     ///
     /// # Return
-    /// The the number of the possible phrases generated by the instance.
-    pub fn combination_number(self: &Self) -> usize {
-        self.syntaxes
-            .iter()
-            .fold(0, |acc, x| acc + x.combination_number())
+    /// The banned words found, with a phrase and its derivation for each of them. The same word may be reported with different derivations if it's made in different ways.
+    ///
+    /// # Note
+    /// - All the literals are checked, including the concatenation of the adjacent parts, without generating all the phrases.
+    /// - The gsubs are ignored in the check, and each phrase found is regenerated from the derivation to confirm it has the word. So the word made only by a gsub isn't found, and the word removed by a gsub isn't reported.
+    /// - The syntaxes and the options that are never selected because their weight is 0 are ignored.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{B}
+    ///     A = ap | or
+    ///     B = ple | ange
+    /// "#.parse()?;
+    /// let found = ph.find_banned(&["apple"], &tphrase::ExtContext::new());
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].word(), "apple");
+    /// assert_eq!(found[0].phrase(), "apple");
+    /// assert_eq!(found[0].derivation().indices(), [0, 0, 0, 0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_banned(self: &Self, words: &[&str], ext_context: &dyn Context) -> Vec<BannedMatch> {
+        let mut finder = BannedFinder::new(words, ext_context);
+        let mut found = Vec::new();
+        for (i, syntax) in self.syntaxes.iter().enumerate() {
+            if selection_probability(&self.weights, self.equalized_chance, i) <= 0.0 {
+                continue;
+            }
+            for (w, mut indices) in finder.find(syntax) {
+                indices.insert(0, i);
+                let derivation = Derivation::new(indices);
+                if let Ok(phrase) =
+                    self.generate_from_derivation(&derivation, &FixedContext::new(ext_context))
+                {
+                    if phrase.contains(words[w]) {
+                        found.push(BannedMatch::new(words[w].to_string(), phrase, derivation));
+                    }
+                }
+            }
+        }
+        return found;
+    }
+
+    /// Generate a phrase using an external context and a random number generator.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `rng`: The random number generator used instead of the one that the instance has.
+    ///
+    /// # Return
+    /// A phrase.
+    ///
+    /// # Note
+    /// - The empty generator creates "nil".
+    /// - `self` isn't mut, so an instance can be shared with the threads that have their own random number generators.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = "main = A | B | C".parse()?;
+    /// let ph = std::sync::Arc::new(ph);
+    /// let mut handles = Vec::new();
+    /// for _ in 0..4 {
+    ///     let ph = std::sync::Arc::clone(&ph);
+    ///     handles.push(std::thread::spawn(move || {
+    ///         let mut rng: tphrase::FastrandRng = tphrase::RandomNumberGenerator::new();
+    ///         ph.generate_with_rng(&tphrase::ExtContext::new(), &mut rng)
+    ///     }));
+    /// }
+    /// for h in handles {
+    ///     let s = h.join().unwrap();
+    ///     assert!(s == "A" || s == "B" || s == "C");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_with_rng<R2: RandomNumberGenerator>(
+        self: &Self,
+        ext_context: &dyn Context,
+        rng: &mut R2,
+    ) -> String {
+        select_and_generate_text(
+            &self.syntaxes,
+            &self.weights,
+            self.equalized_chance,
+            &Environment::new(
+                ext_context,
+                &self.locale,
+                &self.post_filters,
+                &self.functions,
+            ),
+            &mut TemperatureSelector::new(RngSelector::new(rng), self.temperature),
+        )
+    }
+
+    /// Generate the phrase recorded in a derivation.
+    ///
+    /// # Parameter
+    /// - `derivation`: The derivation, which is usually created by [`Trace::derivation()`].
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The phrase, or the error if the derivation doesn't match the syntaxes.
+    ///
+    /// # Note
+    /// - The derivation matches the syntaxes if the number of the syntaxes and the number of the options in each production rule are the same as the ones when it was recorded. The texts can be different, e.g. translated into another language.
+    /// - The empty generator creates "nil" from the empty derivation.
+    /// - The random number generator isn't used.
+    ///
+    /// [`Trace::derivation()`]: struct.Trace.html#method.derivation
+    pub fn generate_from_derivation(
+        self: &Self,
+        derivation: &Derivation,
+        ext_context: &dyn Context,
+    ) -> Result<String, DerivationError> {
+        let mut selector = ReplaySelector::new(derivation);
+        let s = select_and_generate_text(
+            &self.syntaxes,
+            &self.weights,
+            self.equalized_chance,
+            &Environment::new(
+                ext_context,
+                &self.locale,
+                &self.post_filters,
+                &self.functions,
+            ),
+            &mut selector,
+        );
+        selector.finish()?;
+        return Ok(s);
+    }
+
+    /// The probability that the instance selects the options recorded in a derivation.
+    ///
+    /// # Parameter
+    /// - `derivation`: The derivation.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The probability, or the error if the derivation doesn't match the syntaxes.
+    ///
+    /// # Note
+    /// - The probability is calculated from the weights of the syntaxes and the options, with the equalized chance taken into account.
+    /// - The random number generator is assumed to be uniform.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{B}
+    ///     A = "a" 3 | "b" 1
+    ///     B := c | d
+    /// "#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// let d = tphrase::Derivation::new(vec![0, 0, 1, 0]);
+    /// assert_eq!(ph.probability_of_derivation(&d, &no_context)?, 0.125);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn probability_of_derivation(
+        self: &Self,
+        derivation: &Derivation,
+        ext_context: &dyn Context,
+    ) -> Result<f64, DerivationError> {
+        let mut selector =
+            TemperatureSelector::new(ReplaySelector::new(derivation), self.temperature);
+        let _ = select_and_generate_text(
+            &self.syntaxes,
+            &self.weights,
+            self.equalized_chance,
+            &Environment::new(
+                ext_context,
+                &self.locale,
+                &self.post_filters,
+                &self.functions,
+            ),
+            &mut selector,
+        );
+        let p = selector.inner().probability();
+        selector.into_inner().finish()?;
+        return Ok(p);
+    }
+
+    /// The probability that the instance generates a phrase.
+    ///
+    /// # Parameter
+    /// - `phrase`: The phrase.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The sum of the probabilities of all the derivations that generate `phrase`, or [`None`] if the number of the derivations is larger than [`ENUMERATION_LIMIT`].
+    ///
+    /// # Note
+    /// - All the [`combination_number()`] derivations are generated to find the ones that generate `phrase`, because the gsubs, the filters, and the functions can change the phrase arbitrarily.
+    /// - See also [`probability_of_derivation()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = "main = {A}{A}\nA = a | aa".parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// assert_eq!(ph.probability_of("aaa", &no_context), Some(0.5));
+    /// assert_eq!(ph.probability_of("b", &no_context), Some(0.0));
+    ///
+    /// let ph: tphrase::Generator = "main = {A}{A}{A}{A}{A}{A}{A}{A}\nA = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9".parse()?;
+    /// assert_eq!(ph.probability_of("00000000", &no_context), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ENUMERATION_LIMIT`]: constant.ENUMERATION_LIMIT.html
+    /// [`combination_number()`]: #method.combination_number
+    /// [`probability_of_derivation()`]: #method.probability_of_derivation
+    pub fn probability_of(self: &Self, phrase: &str, ext_context: &dyn Context) -> Option<f64> {
+        let mut sum: f64 = 0.0;
+        for k in 0..self.enumerable_count()? {
+            if let Some(derivation) = self.nth_derivation(k) {
+                let mut selector =
+                    TemperatureSelector::new(ReplaySelector::new(&derivation), self.temperature);
+                let s = select_and_generate_text(
+                    &self.syntaxes,
+                    &self.weights,
+                    self.equalized_chance,
+                    &Environment::new(
+                        &FixedContext::new(ext_context),
+                        &self.locale,
+                        &self.post_filters,
+                        &self.functions,
+                    ),
+                    &mut selector,
+                );
+                if s == phrase {
+                    sum += selector.inner().probability();
+                }
+            }
+        }
+        return Some(sum);
+    }
+
+    /// The number of the distinct phrases that the instance can generate.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The number of the distinct phrases after gsubs, or [`None`] if the number of the derivations is larger than [`ENUMERATION_LIMIT`].
+    ///
+    /// # Note
+    /// - It's less than [`combination_number()`] if the syntax is ambiguous, i.e. some phrases are generated by more than one derivation.
+    /// - All the [`combination_number()`] phrases are generated, and the distinct phrases are stored in memory.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{B}
+    ///     A = a | {_E}
+    ///     B = a | {_E}
+    ///     _E = ""
+    /// "#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// assert_eq!(ph.combination_number(), 4);
+    /// assert_eq!(ph.distinct_phrase_count(&no_context), Some(3));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ENUMERATION_LIMIT`]: constant.ENUMERATION_LIMIT.html
+    /// [`combination_number()`]: #method.combination_number
+    pub fn distinct_phrase_count(self: &Self, ext_context: &dyn Context) -> Option<usize> {
+        self.enumerable_count()?;
+        return Some(self.iter_all(ext_context).distinct().count());
+    }
+
+    /// The phrases generated by more than one derivation.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The ambiguous phrases in the order of the first derivation in [`nth()`], or [`None`] if the number of the derivations is larger than [`ENUMERATION_LIMIT`].
+    ///
+    /// # Note
+    /// - All the [`combination_number()`] phrases are generated, and the distinct phrases are stored in memory.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{B}
+    ///     A = a | {_E}
+    ///     B = a | {_E}
+    ///     _E = ""
+    /// "#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// let ambiguities = ph.ambiguities(&no_context).unwrap();
+    /// assert_eq!(ambiguities.len(), 1);
+    /// assert_eq!(ambiguities[0].phrase(), "a");
+    /// assert_eq!(ambiguities[0].derivations().len(), 2);
+    /// assert_eq!(ambiguities[0].probability(), 0.5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ENUMERATION_LIMIT`]: constant.ENUMERATION_LIMIT.html
+    /// [`nth()`]: #method.nth
+    /// [`combination_number()`]: #method.combination_number
+    pub fn ambiguities(self: &Self, ext_context: &dyn Context) -> Option<Vec<Ambiguity>> {
+        let mut found: HashMap<String, usize> = HashMap::new();
+        let mut phrases: Vec<(String, Vec<usize>, f64)> = Vec::new();
+        for k in 0..self.enumerable_count()? {
+            if let Some(derivation) = self.nth_derivation(k) {
+                let mut selector =
+                    TemperatureSelector::new(ReplaySelector::new(&derivation), self.temperature);
+                let s = select_and_generate_text(
+                    &self.syntaxes,
+                    &self.weights,
+                    self.equalized_chance,
+                    &Environment::new(
+                        &FixedContext::new(ext_context),
+                        &self.locale,
+                        &self.post_filters,
+                        &self.functions,
+                    ),
+                    &mut selector,
+                );
+                let p = selector.inner().probability();
+                match found.get(&s) {
+                    Some(i) => {
+                        phrases[*i].1.push(k);
+                        phrases[*i].2 += p;
+                    }
+                    None => {
+                        found.insert(s.clone(), phrases.len());
+                        phrases.push((s, vec![k], p));
+                    }
+                }
+            }
+        }
+        return Some(
+            phrases
+                .into_iter()
+                .filter(|(_, ks, _)| ks.len() > 1)
+                .map(|(s, ks, p)| {
+                    let derivations = ks.iter().filter_map(|k| self.nth_derivation(*k)).collect();
+                    Ambiguity::new(s, derivations, p)
+                })
+                .collect(),
+        );
+    }
+
+    /// Generate the k-th phrase.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the phrase in the range of `0..combination_number()`.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The phrase, or [`None`] if `k` is out of range.
+    ///
+    /// # Note
+    /// - The phrases are indexed in the order of the syntaxes, the options, and the expansions in the options (the first expansion is the most significant), regardless of the weights.
+    /// - Some indices may yield the same phrase if the syntax is ambiguous.
+    /// - The random number generator isn't used.
+    /// - The index is `usize`, but [`combination_number()`] saturates at `usize::MAX`, so the phrases after it can't be indexed. Use [`nth_exact()`] for them.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{B} | z
+    ///     A = a | b
+    ///     B = 1 | 2 | 3
+    /// "#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// let all: Vec<_> = (0..ph.combination_number())
+    ///     .map(|k| ph.nth(k, &no_context).unwrap())
+    ///     .collect();
+    /// assert_eq!(all, ["a1", "a2", "a3", "b1", "b2", "b3", "z"]);
+    /// assert_eq!(ph.nth(7, &no_context), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`combination_number()`]: #method.combination_number
+    /// [`nth_exact()`]: #method.nth_exact
+    pub fn nth(self: &Self, k: usize, ext_context: &dyn Context) -> Option<String> {
+        return self.nth_exact(k as u128, ext_context);
+    }
+
+    /// Generate the k-th phrase with the `u128` index.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the phrase in the range of `0..combination_number_exact()`.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The phrase, or [`None`] if `k` is out of range.
+    ///
+    /// # Note
+    /// - See [`nth()`].
+    /// - If [`combination_number_exact()`] overflows `u128`, all the indices are in range.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{A}
+    ///     A = {B}{B}{B}{B}
+    ///     B = {C}{C}{C}{C}
+    ///     C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    /// "#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// let last = ph.nth_exact(10u128.pow(32) - 1, &no_context).unwrap();
+    /// assert_eq!(last, "9".repeat(32));
+    /// assert_eq!(ph.nth_exact(10u128.pow(32), &no_context), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`nth()`]: #method.nth
+    /// [`combination_number_exact()`]: #method.combination_number_exact
+    pub fn nth_exact(self: &Self, k: u128, ext_context: &dyn Context) -> Option<String> {
+        let derivation = self.nth_derivation_exact(k)?;
+        return self
+            .generate_from_derivation(&derivation, &FixedContext::new(ext_context))
+            .ok();
+    }
+
+    /// The derivation of the k-th phrase.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the phrase in the range of `0..combination_number()`.
+    ///
+    /// # Return
+    /// The derivation, or [`None`] if `k` is out of range.
+    ///
+    /// # Note
+    /// See [`nth()`].
+    ///
+    /// [`nth()`]: #method.nth
+    pub fn nth_derivation(self: &Self, k: usize) -> Option<Derivation> {
+        return self.nth_derivation_exact(k as u128);
+    }
+
+    /// The derivation of the k-th phrase with the `u128` index.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the phrase in the range of `0..combination_number_exact()`.
+    ///
+    /// # Return
+    /// The derivation, or [`None`] if `k` is out of range.
+    ///
+    /// # Note
+    /// See [`nth_exact()`].
+    ///
+    /// [`nth_exact()`]: #method.nth_exact
+    pub fn nth_derivation_exact(self: &Self, k: u128) -> Option<Derivation> {
+        let mut k = k;
+        for (i, syntax) in self.syntaxes.iter().enumerate() {
+            match syntax.combination_number() {
+                Some(c) if k >= c => k -= c,
+                _ => {
+                    let mut indices = vec![i];
+                    syntax.unrank(k, &mut indices);
+                    return Some(Derivation::new(indices));
+                }
+            }
+        }
+        return None;
+    }
+
+    /// The index of the phrase generated by a derivation. It's the inverse of [`nth_derivation()`].
+    ///
+    /// # Parameter
+    /// - `derivation`: The derivation.
+    ///
+    /// # Return
+    /// The index in the range of `0..combination_number()`, or [`None`] if the derivation doesn't match the syntaxes or the index overflows `usize`.
+    ///
+    /// # Note
+    /// Use [`rank_exact()`] for the index that overflows `usize`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = {A}{A}\nA = a | b | c".parse()?;
+    /// let (phrase, trace) = ph.generate_traced();
+    /// let k = ph.rank(&trace.derivation()).unwrap();
+    /// assert_eq!(ph.nth(k, &tphrase::ExtContext::new()).unwrap(), phrase);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`nth_derivation()`]: #method.nth_derivation
+    /// [`rank_exact()`]: #method.rank_exact
+    pub fn rank(self: &Self, derivation: &Derivation) -> Option<usize> {
+        return usize::try_from(self.rank_exact(derivation)?).ok();
+    }
+
+    /// The `u128` index of the phrase generated by a derivation. It's the inverse of [`nth_derivation_exact()`].
+    ///
+    /// # Parameter
+    /// - `derivation`: The derivation.
+    ///
+    /// # Return
+    /// The index in the range of `0..combination_number_exact()`, or [`None`] if the derivation doesn't match the syntaxes or the index overflows `u128`.
+    ///
+    /// [`nth_derivation_exact()`]: #method.nth_derivation_exact
+    pub fn rank_exact(self: &Self, derivation: &Derivation) -> Option<u128> {
+        let indices = derivation.indices();
+        let i = *indices.first()?;
+        let syntax = self.syntaxes.get(i)?;
+        let mut pos: usize = 1;
+        let k = syntax.rank(indices, &mut pos)?;
+        if pos != indices.len() {
+            return None;
+        }
+        let mut offset: u128 = 0;
+        for x in self.syntaxes[..i].iter() {
+            offset = offset.checked_add(x.combination_number()?)?;
+        }
+        return offset.checked_add(k);
+    }
+
+    /// Iterate over all the phrases that the instance can generate.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The lazy iterator over the phrases in the order of [`nth()`].
+    ///
+    /// # Note
+    /// - The number of the phrases is [`combination_number()`], which may be huge. The iterator doesn't store them, so it can be stopped at any time, e.g. by [`Iterator::take()`].
+    /// - Some phrases may be duplicated if the syntax is ambiguous. Use [`Phrases::distinct()`] to skip them.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{B}
+    ///     A = a | b
+    ///     B = 1 | 2
+    /// "#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// let all: Vec<_> = ph.iter_all(&no_context).collect();
+    /// assert_eq!(all, ["a1", "a2", "b1", "b2"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`nth()`]: #method.nth
+    /// [`combination_number()`]: #method.combination_number
+    /// [`Phrases::distinct()`]: struct.Phrases.html#method.distinct
+    pub fn iter_all<'a>(self: &'a Self, ext_context: &'a dyn Context) -> Phrases<'a, S> {
+        Phrases::new(
+            &self.syntaxes,
+            ext_context,
+            self.locale.clone(),
+            self.post_filters.clone(),
+            self.functions.clone(),
+        )
+    }
+
+    /// Generate a phrase derived from a key.
+    ///
+    /// # Parameter
+    /// - `key`: The key.
+    ///
+    /// # Return
+    /// A phrase.
+    ///
+    /// # Note
+    /// - The same key always yields the same phrase if the syntaxes added into the instance are the same, regardless of the random number generator of the instance. It's stable across the process restarts and the versions of TPhrase for Rust as long as [`SELECTION_ALGORITHM_VERSION`] is the same.
+    /// - The phrase depends on the order of the options and the weights, so it may change if the syntaxes (or the translation) change.
+    /// - The temperature isn't applied, so [`set_temperature()`] doesn't change the phrase for a key.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {PREFIX}{SUFFIX}
+    ///     PREFIX = Gor | Mag | Tur | Zog
+    ///     SUFFIX = ash | uk | dug | nar
+    /// "#.parse()?;
+    /// let name = ph.generate_for_key(b"orc-1234");
+    /// assert_eq!(ph.generate_for_key(b"orc-1234"), name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`SELECTION_ALGORITHM_VERSION`]: constant.SELECTION_ALGORITHM_VERSION.html
+    /// [`set_temperature()`]: #method.set_temperature
+    pub fn generate_for_key(self: &Self, key: &[u8]) -> String {
+        let no_context = super::ExtContext::new();
+        return self.generate_for_key_with_context(key, &no_context);
+    }
+
+    /// Generate a phrase derived from a key using an external context.
+    ///
+    /// # Parameter
+    /// - `key`: The key.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// A phrase.
+    ///
+    /// # Note
+    /// See [`generate_for_key()`].
+    ///
+    /// [`generate_for_key()`]: #method.generate_for_key
+    pub fn generate_for_key_with_context(
+        self: &Self,
+        key: &[u8],
+        ext_context: &dyn Context,
+    ) -> String {
+        select_and_generate_text(
+            &self.syntaxes,
+            &self.weights,
+            self.equalized_chance,
+            &Environment::new(
+                ext_context,
+                &self.locale,
+                &self.post_filters,
+                &self.functions,
+            ),
+            &mut RngSelector::new(&mut KeyedRng::from_key(key)),
+        )
+    }
+
+    /// Add a phrase syntax.
+    ///
+    /// # Parameter
+    /// - `syntax`: The phrase syntax to be added.
+    ///
+    /// # Return
+    /// ID for the syntax added into the instance, or a human readable error message if no phrase syntax is added.
+    ///
+    /// # Note
+    /// - Only the phrase syntax that contains the nonterminal "main" can be added.
+    /// - The recursive reference to a nonterminal is not allowed.
+    /// - The functions called in the syntax must be registered by [`register_function()`] in advance.
+    /// - The syntax ID is unique only in `self`.
+    ///
+    /// [`register_function()`]: #method.register_function
+    pub fn add(self: &mut Self, syntax: Syntax<S>) -> Result<SyntaxId, CompileError> {
+        self.add_with_start_condition(syntax, "main")
+    }
+
+    /// Add a phrase syntax.
+    ///
+    /// # Parameter
+    /// - `syntax`: The phrase syntax to be added.
+    /// - `start_condition`: The name of the nonterminal where is the start condition.
+    ///
+    /// # Return
+    /// ID for the syntax added into the instance, or a human readable error message if no phrase syntax is added.
+    ///
+    /// # Note
+    /// - Only the phrase syntax that contains the start condition can be added.
+    /// - The recursive reference to a nonterminal is not allowed.
+    /// - The functions called in the syntax must be registered by [`register_function()`] in advance.
+    /// - The syntax ID is unique only in `self`.
+    ///
+    /// [`register_function()`]: #method.register_function
+    pub fn add_with_start_condition(
+        self: &mut Self,
+        mut syntax: Syntax<S>,
+        start_condition: &str,
+    ) -> Result<SyntaxId, CompileError> {
+        if !syntax.is_bound(start_condition) {
+            syntax.bind_syntax(start_condition)?;
+        }
+        let mut err_msg = Vec::new();
+        for name in syntax.function_names() {
+            if !self.functions.contains(&name) {
+                let mut msg = "The function \"".to_string();
+                msg += &name;
+                msg += "\" is not registered.";
+                err_msg.push(msg);
+            }
+        }
+        if !err_msg.is_empty() {
+            let mut compile_error = CompileError::new();
+            compile_error.add_error_messages(err_msg);
+            return Err(compile_error);
+        }
+        let new_weight = syntax.weight();
+        self.syntaxes.push(syntax);
+        self.weights.push(self.weight() + new_weight);
+        let id = match self.ids.last() {
+            Some(x) => {
+                if *x < usize::MAX {
+                    *x + 1
+                } else {
+                    let mut compile_error = CompileError::new();
+                    compile_error.add_error_message("Too many syntaxes".to_string());
+                    return Err(compile_error);
+                }
+            }
+            None => 1,
+        };
+        self.ids.push(id);
+        if let Some(bag) = &mut self.shuffle_bag {
+            bag.reset();
+        }
+        return Ok(id);
+    }
+
+    /// Remove a phrase syntax.
+    ///
+    /// # Parameter
+    /// - `id`: ID for the phrase syntax.
+    ///
+    /// # Return
+    /// [`Ok`] if the phrase syntax is deleted. [`Err`] if ID doesn't exist in `self`.
+    ///
+    /// # Note
+    /// - This is an O(n) function, because it's assumed that the function is not used frequently.
+    /// -  The ID for the removed phrase syntax may be reused by [`add()`].
+    ///
+    /// [`add()`]: #method.add
+    pub fn remove(self: &mut Self, syntax_id: SyntaxId) -> Result<(), SyntaxRemoveError> {
+        let i = match self.ids.binary_search(&syntax_id) {
+            Ok(x) => x,
+            Err(_) => return Err(SyntaxRemoveError::new()),
+        };
+        self.ids.remove(i);
+        self.syntaxes.remove(i);
+        self.weights.pop();
+        let mut sum: f64 = 0.0;
+        if i >= 1 {
+            sum = self.weights[i - 1];
+        }
+        for j in i..self.syntaxes.len() {
+            sum += self.syntaxes[j].weight();
+            self.weights[j] = sum;
+        }
+        if let Some(bag) = &mut self.shuffle_bag {
+            bag.reset();
+        }
+        return Ok(());
+    }
+
+    /// Override the weight of a nonterminal in a phrase syntax without parsing it again.
+    ///
+    /// # Parameter
+    /// - `syntax_id`: ID for the phrase syntax.
+    /// - `nonterminal`: The nonterminal.
+    /// - `weight`: The weight of the nonterminal. The default value is used if `weight` is [`None`].
+    ///
+    /// # Return
+    /// [`Ok`] if the weight is overridden. [`Err`] if ID or the nonterminal doesn't exist, or the weight isn't a finite non-negative number.
+    ///
+    /// # Note
+    /// - See [`Syntax::set_weight()`].
+    /// - The weight to select the phrase syntax is updated, too.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut ph: tphrase::Generator = tphrase::Generator::new();
+    /// let id = ph.add(r#"
+    ///     main = {A} | {B}
+    ///     A = a1 | a2
+    ///     B = b
+    /// "#.parse()?)?;
+    /// ph.set_weight(id, "A", Some(0.0))?;
+    /// assert_eq!(ph.generate(), "b");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Syntax::set_weight()`]: struct.Syntax.html#method.set_weight
+    pub fn set_weight(
+        self: &mut Self,
+        syntax_id: SyntaxId,
+        nonterminal: &str,
+        weight: Option<f64>,
+    ) -> Result<(), WeightError> {
+        let i = self.syntax_index(syntax_id)?;
+        self.syntaxes[i].set_weight(nonterminal, weight)?;
+        self.update_weights();
+        return Ok(());
+    }
+
+    /// Override the weight of an option of a nonterminal in a phrase syntax without parsing it again.
+    ///
+    /// # Parameter
+    /// - `syntax_id`: ID for the phrase syntax.
+    /// - `nonterminal`: The nonterminal.
+    /// - `index`: The index of the option, starting from 0 in the order in the syntax.
+    /// - `weight`: The weight of the option. The default value is used if `weight` is [`None`].
+    ///
+    /// # Return
+    /// [`Ok`] if the weight is overridden. [`Err`] if ID, the nonterminal, or the option doesn't exist, or the weight isn't a finite non-negative number.
+    ///
+    /// # Note
+    /// - See [`Syntax::set_option_weight()`].
+    /// - The weight to select the phrase syntax is updated, too.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut ph: tphrase::Generator = tphrase::Generator::new();
+    /// let id = ph.add("main = x | y".parse()?)?;
+    /// ph.set_option_weight(id, "main", 0, Some(0.0))?;
+    /// assert_eq!(ph.generate(), "y");
+    /// ph.set_option_weight(id, "main", 0, None)?;
+    /// assert_eq!(ph.probability_of("x", &tphrase::ExtContext::new()), Some(0.5));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Syntax::set_option_weight()`]: struct.Syntax.html#method.set_option_weight
+    pub fn set_option_weight(
+        self: &mut Self,
+        syntax_id: SyntaxId,
+        nonterminal: &str,
+        index: usize,
+        weight: Option<f64>,
+    ) -> Result<(), WeightError> {
+        let i = self.syntax_index(syntax_id)?;
+        self.syntaxes[i].set_option_weight(nonterminal, index, weight)?;
+        self.update_weights();
+        return Ok(());
+    }
+
+    /// Override the weight of the options of a nonterminal in a phrase syntax that have a label without parsing it again.
+    ///
+    /// # Parameter
+    /// - `syntax_id`: ID for the phrase syntax.
+    /// - `nonterminal`: The nonterminal.
+    /// - `label`: The label of the options, which is their source text.
+    /// - `weight`: The weight of the options. The default value is used if `weight` is [`None`].
+    ///
+    /// # Return
+    /// [`Ok`] if the weight is overridden. [`Err`] if ID, the nonterminal, or the option doesn't exist, or the weight isn't a finite non-negative number.
+    ///
+    /// # Note
+    /// - See [`Syntax::set_option_weight_by_label()`].
+    /// - The weight to select the phrase syntax is updated, too.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut ph: tphrase::Generator = tphrase::Generator::new();
+    /// let id = ph.add("main = x | 'y' 2".parse()?)?;
+    /// ph.set_option_weight_by_label(id, "main", "y", Some(0.0))?;
+    /// assert_eq!(ph.generate(), "x");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Syntax::set_option_weight_by_label()`]: struct.Syntax.html#method.set_option_weight_by_label
+    pub fn set_option_weight_by_label(
+        self: &mut Self,
+        syntax_id: SyntaxId,
+        nonterminal: &str,
+        label: &str,
+        weight: Option<f64>,
+    ) -> Result<(), WeightError> {
+        let i = self.syntax_index(syntax_id)?;
+        self.syntaxes[i].set_option_weight_by_label(nonterminal, label, weight)?;
+        self.update_weights();
+        return Ok(());
+    }
+
+    /// The index of a phrase syntax.
+    ///
+    /// # Parameter
+    /// - `syntax_id`: ID for the phrase syntax.
+    ///
+    /// # Return
+    /// The index, or [`Err`] if ID doesn't exist.
+    fn syntax_index(self: &Self, syntax_id: SyntaxId) -> Result<usize, WeightError> {
+        match self.ids.binary_search(&syntax_id) {
+            Ok(i) => Ok(i),
+            Err(_) => {
+                let mut msg = "The syntax ID ".to_string();
+                msg += &syntax_id.to_string();
+                msg += " doesn't exist.";
+                Err(WeightError::new(msg))
+            }
+        }
+    }
+
+    /// Recalculate the weights to select the phrase syntaxes.
+    fn update_weights(self: &mut Self) {
+        let mut sum: f64 = 0.0;
+        for (i, syntax) in self.syntaxes.iter().enumerate() {
+            sum += syntax.weight();
+            self.weights[i] = sum;
+        }
+    }
+
+    /// Clear the syntaxes and create an empty phrase generator.
+    pub fn clear(self: &mut Self) {
+        self.syntaxes.clear();
+        self.weights.clear();
+        self.ids.clear();
+        if let Some(bag) = &mut self.shuffle_bag {
+            bag.reset();
+        }
+    }
+
+    /// Equalize the chance to select each phrase syntax.
+    ///
+    /// # Parameter
+    /// - `enable`: equalized if `enable` is true. If not, the chance depends on the weight of the phrase syntax. (Default)
+    pub fn equalize_chance(self: &mut Self, enable: bool) {
+        self.equalized_chance = enable;
+    }
+
+    /// Set the temperature to sharpen or flatten the chance to select the options.
+    ///
+    /// # Parameter
+    /// - `temperature`: The temperature, which is a non-negative number. (Default: 1.0)
+    ///
+    /// # Return
+    /// [`Ok`] if the temperature is set. [`Err`] if it's negative or NaN.
+    ///
+    /// # Note
+    /// - Each weight `w` is replaced with `w ^ (1 / temperature)` when an option or a phrase syntax is selected, so the temperature below 1 biases toward the common options, and the one above 1 toward the rare options. The temperature 1 doesn't change anything.
+    /// - The temperature 0 selects only the options that have the maximum weight, and the infinite temperature equalizes the chance of the options whose weight isn't 0. The option whose weight is 0 is never selected.
+    /// - The equalized chance, by ":=" or [`equalize_chance()`], isn't affected.
+    /// - It affects the generation and the probability, e.g. [`generate()`], [`generate_with_max_len()`], and [`probability_of()`], except for [`avoid_repeat()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut ph: tphrase::Generator = r#"main = "common" 3 | rare"#.parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// assert_eq!(ph.probability_of("rare", &no_context), Some(0.25));
+    /// ph.set_temperature(0.5)?;
+    /// assert!((ph.probability_of("rare", &no_context).unwrap() - 0.1).abs() < 1e-9);
+    /// ph.set_temperature(f64::INFINITY)?;
+    /// assert_eq!(ph.probability_of("rare", &no_context), Some(0.5));
+    /// ph.set_temperature(0.0)?;
+    /// assert_eq!(ph.generate(), "common");
+    /// assert!(ph.set_temperature(-1.0).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`equalize_chance()`]: #method.equalize_chance
+    /// [`generate()`]: #method.generate
+    /// [`generate_with_max_len()`]: #method.generate_with_max_len
+    /// [`probability_of()`]: #method.probability_of
+    /// [`avoid_repeat()`]: #method.avoid_repeat
+    pub fn set_temperature(self: &mut Self, temperature: f64) -> Result<(), WeightError> {
+        if temperature.is_nan() || temperature < 0.0 {
+            let mut msg = "The temperature ".to_string();
+            msg += &temperature.to_string();
+            msg += " is not a non-negative number.";
+            return Err(WeightError::new(msg));
+        }
+        self.temperature = temperature;
+        return Ok(());
+    }
+
+    /// The temperature set by [`set_temperature()`].
+    ///
+    /// [`set_temperature()`]: #method.set_temperature
+    pub fn temperature(self: &Self) -> f64 {
+        self.temperature
+    }
+
+    /// Set the locale to format the numbers in the external context.
+    ///
+    /// # Parameter
+    /// - `locale`: The locale. (Default: [`Locale::default()`])
+    ///
+    /// # Note
+    /// The locale gives the decimal separator of [`ContextValue::Float`] and the grouping separator of the numbers whose format spec has ",", e.g. "{PRICE:,.2}".
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = {N:,} / {PI:.3}".parse()?;
+    /// let context = std::collections::HashMap::from([
+    ///     ("N".to_string(), tphrase::ContextValue::from(1234567)),
+    ///     ("PI".to_string(), tphrase::ContextValue::from(std::f64::consts::PI)),
+    /// ]);
+    /// assert_eq!(ph.generate_with_context(&context), "1,234,567 / 3.142");
+    /// ph.set_locale(tphrase::Locale::from_tag("fr").unwrap());
+    /// assert_eq!(ph.generate_with_context(&context), "1\u{202f}234\u{202f}567 / 3,142");
+    /// ph.set_locale(tphrase::Locale::from_tag("hi").unwrap());
+    /// assert_eq!(ph.generate_with_context(&context), "12,34,567 / 3.142");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Locale::default()`]: struct.Locale.html#impl-Default-for-Locale
+    /// [`ContextValue::Float`]: enum.ContextValue.html#variant.Float
+    pub fn set_locale(self: &mut Self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// The locale set by [`set_locale()`].
+    ///
+    /// [`set_locale()`]: #method.set_locale
+    pub fn locale(self: &Self) -> &Locale {
+        &self.locale
+    }
+
+    /// Set the filters applied to the whole phrase in order.
+    ///
+    /// # Parameter
+    /// - `filters`: The filters. (Default: empty)
+    ///
+    /// # Note
+    /// - The filters are applied after the gsubs of the start condition, so the phrases given by all the methods, e.g. [`generate()`], [`nth()`], and [`probability_of()`], are filtered. The trace given by [`generate_traced()`] has the phrase before the filters.
+    /// - The filters are assumed not to change the length in [`generate_with_max_len()`] and [`length_range()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = r#"main = " {= hello | hi}, world. ""#.parse()?;
+    /// ph.set_post_filters(vec![tphrase::Filter::Trim, tphrase::Filter::Capitalize]);
+    /// let s = ph.generate();
+    /// assert!(s == "Hello, world." || s == "Hi, world.");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`generate()`]: #method.generate
+    /// [`nth()`]: #method.nth
+    /// [`probability_of()`]: #method.probability_of
+    /// [`generate_traced()`]: #method.generate_traced
+    /// [`generate_with_max_len()`]: #method.generate_with_max_len
+    /// [`length_range()`]: #method.length_range
+    pub fn set_post_filters(self: &mut Self, filters: Vec<Filter>) {
+        self.post_filters = filters;
+    }
+
+    /// The filters set by [`set_post_filters()`].
+    ///
+    /// [`set_post_filters()`]: #method.set_post_filters
+    pub fn post_filters(self: &Self) -> &[Filter] {
+        &self.post_filters
+    }
+
+    /// Register a function called by "{@name args}" in the phrase syntaxes.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function, which consists of "[A-Za-z0-9_.]".
+    /// - `function`: The function. See [`Function`]. It replaces the function already registered by the same name.
+    ///
+    /// # Note
+    /// - Register the functions before [`add()`], which reports the function that isn't registered as an error.
+    /// - The call is expanded into itself without "{" and "}", e.g. "@ordinal N", if the function is unregistered after [`add()`].
+    /// - The function gets [`KeyedRng`] instead of the random number generator of the instance, because [`Function`] is a trait object that can't be generic over the type of the random number generator, e.g. the one given to [`generate_with_rng()`], and it's called where no random number generator is given, e.g. [`nth()`]. [`KeyedRng`] is seeded by a random number of the instance, so the function can't change the random numbers used by the following selections.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// use tphrase::Context;
+    /// let mut ph: tphrase::Generator = tphrase::Generator::new();
+    /// ph.register_function("ordinal", |args, context, _rng| {
+    ///     let n: u64 = args
+    ///         .first()
+    ///         .and_then(|a| context.get(a))
+    ///         .and_then(|v| v.parse().ok())
+    ///         .unwrap_or(0);
+    ///     let suffix = match (n % 10, n % 100) {
+    ///         (_, 11..=13) => "th",
+    ///         (1, _) => "st",
+    ///         (2, _) => "nd",
+    ///         (3, _) => "rd",
+    ///         _ => "th",
+    ///     };
+    ///     n.to_string() + suffix
+    /// });
+    /// ph.add("main = the {@ordinal N} floor".parse()?)?;
+    /// let context = tphrase::ExtContext::from([("N".to_string(), "22".to_string())]);
+    /// assert_eq!(ph.generate_with_context(&context), "the 22nd floor");
+    ///
+    /// let err = ph.add("main = {@unknown}".parse()?).unwrap_err();
+    /// assert_eq!(*err.error_messages(), [r#"The function "unknown" is not registered."#]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Function`]: type.Function.html
+    /// [`KeyedRng`]: struct.KeyedRng.html
+    /// [`add()`]: #method.add
+    /// [`generate_with_rng()`]: #method.generate_with_rng
+    /// [`nth()`]: #method.nth
+    pub fn register_function<F>(self: &mut Self, name: &str, function: F)
+    where
+        F: Fn(&[&str], &dyn Context, &mut KeyedRng) -> String + Send + Sync + 'static,
+    {
+        let function: Arc<Function> = Arc::new(function);
+        self.functions.insert(name, function);
+    }
+
+    /// Unregister a function registered by [`register_function()`].
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function.
+    ///
+    /// # Return
+    /// `true` if the function was registered.
+    ///
+    /// [`register_function()`]: #method.register_function
+    pub fn unregister_function(self: &mut Self, name: &str) -> bool {
+        self.functions.remove(name)
+    }
+
+    /// Avoid generating the same derivation again until a fraction of all the derivations is used, like a shuffle bag.
+    ///
+    /// # Parameter
+    /// - `fraction`: The fraction in the range of (0.0, 1.0], or [`None`] to disable it. (Default) The bag is refilled after `ceil(fraction * combination_number())` phrases are generated from it.
+    ///
+    /// # Note
+    /// - Every derivation in the bag has the same chance regardless of the weights and the equalized chance.
+    /// - It affects [`generate()`], [`generate_with_context()`], [`generate_traced()`], and [`generate_traced_with_context()`].
+    /// - The bag is refilled when a syntax is added or removed, or this function is called.
+    /// - The derivations aren't stored, so it works for the syntax that has a huge number of the combinations.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = A | B | C | D".parse()?;
+    /// ph.avoid_repeat(Some(1.0));
+    /// let mut phrases: Vec<_> = (0..4).map(|_| ph.generate()).collect();
+    /// phrases.sort();
+    /// assert_eq!(phrases, ["A", "B", "C", "D"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`generate()`]: #method.generate
+    /// [`generate_with_context()`]: #method.generate_with_context
+    /// [`generate_traced()`]: #method.generate_traced
+    /// [`generate_traced_with_context()`]: #method.generate_traced_with_context
+    pub fn avoid_repeat(self: &mut Self, fraction: Option<f64>) {
+        self.shuffle_bag = fraction.map(ShuffleBag::new);
+    }
+
+    /// Avoid generating the phrases that are generated recently.
+    ///
+    /// # Parameter
+    /// - `window`: The number of the recent phrases to be avoided, or 0 to disable it. (Default)
+    ///
+    /// # Note
+    /// - The chance of the phrases in the window is moved to the other phrases in proportion to their chance.
+    /// - A phrase in the window may be generated if (almost) all the phrases are in the window.
+    /// - It affects [`generate()`], [`generate_with_context()`], [`generate_traced()`], and [`generate_traced_with_context()`], but it doesn't if [`avoid_repeat()`] is enabled.
+    /// - The history is stored in the instance, and it can be saved by [`history()`] and restored by [`set_history()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = A | B | C".parse()?;
+    /// ph.set_history_window(2);
+    /// let mut last = vec![ph.generate(), ph.generate()];
+    /// for _ in 0..100 {
+    ///     let s = ph.generate();
+    ///     assert!(!last.contains(&s));
+    ///     last.remove(0);
+    ///     last.push(s);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`generate()`]: #method.generate
+    /// [`generate_with_context()`]: #method.generate_with_context
+    /// [`generate_traced()`]: #method.generate_traced
+    /// [`generate_traced_with_context()`]: #method.generate_traced_with_context
+    /// [`avoid_repeat()`]: #method.avoid_repeat
+    /// [`history()`]: #method.history
+    /// [`set_history()`]: #method.set_history
+    pub fn set_history_window(self: &mut Self, window: usize) {
+        self.phrase_window = window;
+    }
+
+    /// Avoid selecting the options of a nonterminal that are selected recently.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal. A local nonterminal can't be specified.
+    /// - `window`: The number of the recent selections of the nonterminal to be avoided, or 0 to disable it. (Default)
+    ///
+    /// # Note
+    /// - The weights of the options in the window are ignored, and the weights of the others are renormalized.
+    /// - The expansions in a phrase count, so `{A}{A}` doesn't select the same option of "A" twice if the window is more than 0.
+    /// - See also [`set_history_window()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = {A}{A}\nA = a | b".parse()?;
+    /// ph.set_option_history_window("A", 1);
+    /// for _ in 0..10 {
+    ///     let s = ph.generate();
+    ///     assert!(s == "ab" || s == "ba");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_history_window()`]: #method.set_history_window
+    pub fn set_option_history_window(self: &mut Self, nonterminal: &str, window: usize) {
+        if window == 0 {
+            self.option_windows.remove(nonterminal);
+        } else {
+            self.option_windows.insert(nonterminal.to_string(), window);
+        }
+    }
+
+    /// The history of the recent phrases and options.
+    pub fn history(self: &Self) -> &History {
+        &self.history
+    }
+
+    /// Replace the history of the recent phrases and options, e.g. with the one restored from a save file.
+    ///
+    /// # Parameter
+    /// - `history`: The history.
+    pub fn set_history(self: &mut Self, history: History) {
+        self.history = history;
+    }
+
+    /// The number of the syntaxes in the instance.
+    ///
+    /// # Return
+    /// The number of the syntaxes in the instance.
+    pub fn number_of_syntax(self: &Self) -> usize {
+        self.syntaxes.len()
+    }
+
+    /// The sum of the weight of the syntaxes in the instance.
+    ///
+    /// # Return
+    /// The sum of the weight of the syntaxes in the instance.
+    pub fn weight(self: &Self) -> f64 {
+        if let Some(x) = self.weights.last() {
+            *x
+        } else {
+            0.0
+        }
+    }
+
+    /// The number of the possible phrases generated by the instance.
+    ///
+    /// # Return
+    /// The the number of the possible phrases generated by the instance.
+    ///
+    /// # Note
+    /// The number saturates at `usize::MAX`. Use [`combination_number_exact()`] to detect the overflow.
+    ///
+    /// [`combination_number_exact()`]: #method.combination_number_exact
+    pub fn combination_number(self: &Self) -> usize {
+        match self.combination_number_exact() {
+            Some(x) => usize::try_from(x).unwrap_or(usize::MAX),
+            None => usize::MAX,
+        }
+    }
+
+    /// The exact number of the possible phrases generated by the instance.
+    ///
+    /// # Return
+    /// The the number of the possible phrases generated by the instance, or [`None`] if it overflows `u128`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{A}
+    ///     A = {B}{B}{B}{B}
+    ///     B = {C}{C}{C}{C}
+    ///     C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    /// "#.parse()?;
+    /// assert_eq!(ph.combination_number_exact(), Some(10u128.pow(32)));
+    /// assert_eq!(ph.combination_number(), usize::MAX);
+    ///
+    /// let ph: tphrase::Generator = r#"
+    ///     main = {A}{A}{A}
+    ///     A = {B}{B}{B}{B}
+    ///     B = {C}{C}{C}{C}
+    ///     C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    /// "#.parse()?;
+    /// assert_eq!(ph.combination_number_exact(), None);
+    /// assert_eq!(ph.combination_number(), usize::MAX);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn combination_number_exact(self: &Self) -> Option<u128> {
+        let mut sum: u128 = 0;
+        for x in self.syntaxes.iter() {
+            sum = sum.checked_add(x.combination_number()?)?;
+        }
+        return Some(sum);
+    }
+
+    /// The number of the derivations to enumerate.
+    ///
+    /// # Return
+    /// The number of the possible phrases, or [`None`] if it's larger than [`ENUMERATION_LIMIT`].
+    ///
+    /// [`ENUMERATION_LIMIT`]: constant.ENUMERATION_LIMIT.html
+    fn enumerable_count(self: &Self) -> Option<usize> {
+        let n = self.combination_number_exact()?;
+        if n > ENUMERATION_LIMIT {
+            return None;
+        }
+        return usize::try_from(n).ok();
+    }
+}
+
+impl<R: SeedableRandomNumberGenerator, S: Substitutor> Generator<R, S> {
+    /// Create an empty generator with a seeded random number generator.
+    ///
+    /// # Parameter
+    /// - `seed`: The seed of the random number generator.
+    ///
+    /// # Note
+    /// The generators created with the same seed generate the same phrases if the same syntaxes are added in the same order.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let syntax: tphrase::Syntax = "main = A | B | C | D | E | F".parse()?;
+    /// let mut ph1: tphrase::Generator = tphrase::Generator::with_seed(1234);
+    /// let _ = ph1.add(syntax.clone())?;
+    /// let mut ph2: tphrase::Generator = tphrase::Generator::with_seed(1234);
+    /// let _ = ph2.add(syntax)?;
+    /// for _ in 0..10 {
+    ///     assert_eq!(ph1.generate(), ph2.generate());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_seed(seed: u64) -> Self {
+        let mut ph = Self::new();
+        ph.rng = R::from_seed(seed);
+        return ph;
+    }
+
+    /// The internal state of the random number generator.
+    ///
+    /// # Return
+    /// The state, which [`set_rng_state()`] can restore.
+    ///
+    /// [`set_rng_state()`]: #method.set_rng_state
+    pub fn rng_state(self: &Self) -> R::State {
+        self.rng.state()
+    }
+
+    /// Restore the internal state of the random number generator.
+    ///
+    /// # Parameter
+    /// - `state`: The state returned by [`rng_state()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut ph: tphrase::Generator = "main = A | B | C | D | E | F".parse()?;
+    /// let state = ph.rng_state();
+    /// let s1 = ph.generate();
+    /// ph.set_rng_state(state);
+    /// assert_eq!(ph.generate(), s1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`rng_state()`]: #method.rng_state
+    pub fn set_rng_state(self: &mut Self, state: R::State) {
+        self.rng.set_state(state);
     }
 }
 
@@ -0,0 +1,294 @@
+//! The history of the generated phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::binary_io::{ByteReader, ByteWriter};
+use crate::DecodeError;
+use crate::Selector;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// The magic number of the binary format of [`History`].
+const MAGIC: &[u8; 4] = b"TPHH";
+/// The version of the binary format of [`History`].
+const VERSION: u32 = 1;
+
+/// The history of the recently generated phrases and the recently selected options, used to avoid the repetition.
+///
+/// The windows are set by [`Generator::set_history_window()`] and [`Generator::set_option_history_window()`], and the instance keeps the records only within the windows.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut ph: tphrase::Generator = "main = A | B | C".parse()?;
+/// ph.set_history_window(2);
+/// let _ = ph.generate();
+/// let saved = ph.history().to_bytes();
+///
+/// let mut ph2: tphrase::Generator = "main = A | B | C".parse()?;
+/// ph2.set_history_window(2);
+/// ph2.set_history(tphrase::History::from_bytes(&saved)?);
+/// assert_eq!(ph2.history(), ph.history());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Generator::set_history_window()`]: struct.Generator.html#method.set_history_window
+/// [`Generator::set_option_history_window()`]: struct.Generator.html#method.set_option_history_window
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct History {
+    /// The recent phrases, the oldest first.
+    phrases: VecDeque<String>,
+    /// The indices of the recent options for each nonterminal, the oldest first.
+    options: BTreeMap<String, VecDeque<usize>>,
+}
+impl History {
+    /// Create an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear the history.
+    pub fn clear(self: &mut Self) {
+        self.phrases.clear();
+        self.options.clear();
+    }
+
+    /// The recent phrases.
+    ///
+    /// # Return
+    /// The iterator over the phrases, the oldest first.
+    pub fn recent_phrases<'a>(self: &'a Self) -> impl Iterator<Item = &'a str> + 'a {
+        self.phrases.iter().map(|s| s.as_str())
+    }
+
+    /// The indices of the recent options of a nonterminal.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal.
+    ///
+    /// # Return
+    /// The iterator over the indices, the oldest first.
+    pub fn recent_options<'a>(
+        self: &'a Self,
+        nonterminal: &str,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.options
+            .get(nonterminal)
+            .into_iter()
+            .flat_map(|x| x.iter().copied())
+    }
+
+    /// Encode the history in the versioned binary format.
+    ///
+    /// # Return
+    /// The binary data.
+    pub fn to_bytes(self: &Self) -> Vec<u8> {
+        let mut w = ByteWriter::new();
+        w.len(self.phrases.len());
+        for s in self.phrases.iter() {
+            w.bytes(s.as_bytes());
+        }
+        w.len(self.options.len());
+        for (name, indices) in self.options.iter() {
+            w.bytes(name.as_bytes());
+            w.len(indices.len());
+            for i in indices.iter() {
+                w.var(*i as u64);
+            }
+        }
+        return w.finish(MAGIC, VERSION);
+    }
+
+    /// Decode the binary data created by [`to_bytes()`].
+    ///
+    /// # Parameter
+    /// - `data`: The binary data.
+    ///
+    /// # Return
+    /// The history, or the error if the data is broken.
+    ///
+    /// [`to_bytes()`]: #method.to_bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = ByteReader::new(data, MAGIC, VERSION)?;
+        let mut history = Self::new();
+        for _ in 0..r.len()? {
+            history.phrases.push_back(r.string()?);
+        }
+        for _ in 0..r.len()? {
+            let name = r.string()?;
+            let mut indices = VecDeque::new();
+            for _ in 0..r.len()? {
+                let i = r.var()?;
+                if i > usize::MAX as u64 {
+                    return Err(DecodeError::new("An index is out of range.".to_string()));
+                }
+                indices.push_back(i as usize);
+            }
+            history.options.insert(name, indices);
+        }
+        r.finish()?;
+        return Ok(history);
+    }
+
+    /// Is a phrase in the window?
+    ///
+    /// # Parameter
+    /// - `phrase`: The phrase.
+    /// - `window`: The number of the recent phrases to be checked.
+    pub(crate) fn contains_phrase(self: &Self, phrase: &str, window: usize) -> bool {
+        self.phrases.iter().rev().take(window).any(|s| s == phrase)
+    }
+
+    /// Add a phrase, and forget the phrases out of the window.
+    ///
+    /// # Parameter
+    /// - `phrase`: The phrase.
+    /// - `window`: The number of the phrases to be kept.
+    pub(crate) fn push_phrase(self: &mut Self, phrase: String, window: usize) {
+        self.phrases.push_back(phrase);
+        while self.phrases.len() > window {
+            self.phrases.pop_front();
+        }
+    }
+
+    /// Add an index of the option of a nonterminal, and forget the indices out of the window.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal.
+    /// - `index`: The index of the option.
+    /// - `window`: The number of the indices to be kept.
+    pub(crate) fn push_option(self: &mut Self, nonterminal: &str, index: usize, window: usize) {
+        let indices = self.options.entry(nonterminal.to_string()).or_default();
+        indices.push_back(index);
+        while indices.len() > window {
+            indices.pop_front();
+        }
+    }
+}
+
+/// The selector that avoids the options selected recently, delegating the selection to another selector.
+pub(crate) struct HistorySelector<'a, Sel: Selector> {
+    /// The history before the generation.
+    history: &'a History,
+    /// The windows of the nonterminals.
+    windows: &'a HashMap<String, usize>,
+    /// The selector that actually selects the options.
+    inner: Sel,
+    /// The nonterminal and the window of the rules being generated, or [`None`] if the rule isn't watched.
+    stack: Vec<Option<(String, usize)>>,
+    /// The options selected in the generation.
+    pending: Vec<(String, usize)>,
+}
+impl<'a, Sel: Selector> HistorySelector<'a, Sel> {
+    /// Create a selector.
+    ///
+    /// # Parameter
+    /// - `history`: The history before the generation.
+    /// - `windows`: The windows of the nonterminals.
+    /// - `inner`: The selector that actually selects the options.
+    pub(crate) fn new(
+        history: &'a History,
+        windows: &'a HashMap<String, usize>,
+        inner: Sel,
+    ) -> Self {
+        Self {
+            history,
+            windows,
+            inner,
+            stack: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// The options selected in the generation, in order of selection.
+    pub(crate) fn pending(self: &Self) -> &[(String, usize)] {
+        &self.pending
+    }
+}
+impl<Sel: Selector> Selector for HistorySelector<'_, Sel> {
+    fn select(self: &mut Self, weights: &[f64], equalized_chance: bool) -> usize {
+        let (name, window) = match self.stack.last() {
+            Some(Some((name, window))) => (name, *window),
+            _ => return self.inner.select(weights, equalized_chance),
+        };
+        let recent: Vec<usize> = {
+            let in_generation = self
+                .pending
+                .iter()
+                .filter(|(x, _)| x == name)
+                .map(|(_, i)| *i);
+            let all: Vec<usize> = self
+                .history
+                .recent_options(name)
+                .chain(in_generation)
+                .collect();
+            all[all.len().saturating_sub(window)..].to_vec()
+        };
+        let mut allowed = Vec::new();
+        let mut allowed_weights = Vec::new();
+        let mut sum: f64 = 0.0;
+        for i in 0..weights.len() {
+            if recent.contains(&i) {
+                continue;
+            }
+            let w = if equalized_chance {
+                1.0
+            } else if i == 0 {
+                weights[0]
+            } else {
+                weights[i] - weights[i - 1]
+            };
+            if w > 0.0 {
+                sum += w;
+                allowed.push(i);
+                allowed_weights.push(sum);
+            }
+        }
+        let i = if allowed.is_empty() {
+            self.inner.select(weights, equalized_chance)
+        } else {
+            allowed[self.inner.select(&allowed_weights, false)]
+        };
+        self.pending.push((name.clone(), i));
+        return i;
+    }
+
+    fn enter_rule(self: &mut Self, nonterminal: Option<&str>) {
+        self.inner.enter_rule(nonterminal);
+        let watched = nonterminal.and_then(|x| match self.windows.get(x) {
+            Some(w) if *w > 0 => Some((x.to_string(), *w)),
+            _ => None,
+        });
+        self.stack.push(watched);
+    }
+
+    fn leave_rule(self: &mut Self, pre_gsub: &str, post_gsub: &str) {
+        self.inner.leave_rule(pre_gsub, post_gsub);
+        self.stack.pop();
+    }
+
+    fn use_context(self: &mut Self, name: &str) {
+        self.inner.use_context(name);
+    }
+
+    fn seed(self: &mut Self) -> Option<u64> {
+        self.inner.seed()
+    }
+}
@@ -0,0 +1,83 @@
+//! KeyedRng
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::binary_io::fnv1a64;
+use crate::RandomNumberGenerator;
+use crate::SeedableRandomNumberGenerator;
+
+/// A type of [`RandomNumberGenerator`] whose sequence is derived from a key. Used by [`Generator::generate_for_key()`].
+///
+/// The algorithm is fixed to make the generated phrases stable in all versions of TPhrase for Rust:
+/// 1. The seed is the 64-bit FNV-1a hash of the key. ([`from_seed()`] uses the specified seed instead.)
+/// 1. The state is updated by SplitMix64, that is, `state += 0x9E3779B97F4A7C15` and the output is the state mixed with `z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9; z = (z ^ (z >> 27)) * 0x94D049BB133111EB; z ^ (z >> 31)`.
+/// 1. [`next()`] returns the upper 53 bits of the output divided by 2^53.
+///
+/// # Example
+/// ```rust
+/// use tphrase::RandomNumberGenerator;
+/// let mut rng1 = tphrase::KeyedRng::from_key(b"orc-1234");
+/// let mut rng2 = tphrase::KeyedRng::from_key(b"orc-1234");
+/// assert_eq!(rng1.next(), rng2.next());
+/// ```
+///
+/// [`Generator::generate_for_key()`]: struct.Generator.html#method.generate_for_key
+/// [`from_seed()`]: #method.from_seed
+/// [`next()`]: #method.next
+#[derive(Clone, Debug)]
+pub struct KeyedRng {
+    state: u64,
+}
+impl KeyedRng {
+    /// Create a random number generator derived from a key.
+    ///
+    /// # Parameter
+    /// - `key`: The key.
+    pub fn from_key(key: &[u8]) -> Self {
+        Self {
+            state: fnv1a64(key),
+        }
+    }
+}
+impl RandomNumberGenerator for KeyedRng {
+    /// Create a random number generator derived from the empty key.
+    fn new() -> Self {
+        Self::from_key(b"")
+    }
+    fn next(self: &mut Self) -> f64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        return (z >> 11) as f64 / (1u64 << 53) as f64;
+    }
+}
+impl SeedableRandomNumberGenerator for KeyedRng {
+    type State = u64;
+    fn from_seed(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    fn state(self: &Self) -> u64 {
+        self.state
+    }
+    fn set_state(self: &mut Self, state: u64) {
+        self.state = state;
+    }
+}
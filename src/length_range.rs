@@ -0,0 +1,138 @@
+//! The range of the length of the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+/// The range of the length of the phrases.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let ph: tphrase::Generator = r#"
+///     main = {size} apple
+///     size = small | big | enormous
+/// "#.parse()?;
+/// let range = ph.length_range(tphrase::LengthUnit::Chars, &tphrase::ExtLengthBounds::new());
+/// assert_eq!(range.min(), 9);
+/// assert_eq!(range.max(), 14);
+/// assert!(!range.is_approximate());
+/// assert_eq!(range.to_string(), "9..=14");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LengthRange {
+    /// The minimum length.
+    min: usize,
+    /// The maximum length.
+    max: usize,
+    /// Is the range approximate?
+    approximate: bool,
+}
+impl LengthRange {
+    /// Create an instance.
+    ///
+    /// # Parameter
+    /// - `min`: The minimum length.
+    /// - `max`: The maximum length. It's raised to `min` if it's smaller.
+    pub fn new(min: usize, max: usize) -> Self {
+        Self {
+            min,
+            max: max.max(min),
+            approximate: false,
+        }
+    }
+
+    /// Create an instance whose minimum and maximum are the same.
+    ///
+    /// # Parameter
+    /// - `len`: The length.
+    pub fn exact(len: usize) -> Self {
+        Self::new(len, len)
+    }
+
+    /// The minimum length.
+    pub fn min(self: &Self) -> usize {
+        self.min
+    }
+
+    /// The maximum length.
+    pub fn max(self: &Self) -> usize {
+        self.max
+    }
+
+    /// Is the range approximate?
+    ///
+    /// # Note
+    /// The range is approximate if a gsub may change the length, or if the length of the concatenated texts may differ from the sum of their lengths in the unit, e.g. a grapheme cluster across the parts.
+    pub fn is_approximate(self: &Self) -> bool {
+        self.approximate
+    }
+
+    /// Mark the range approximate or not.
+    ///
+    /// # Parameter
+    /// - `approximate`: Is the range approximate?
+    ///
+    /// # Return
+    /// The marked range.
+    pub fn with_approximate(self: Self, approximate: bool) -> Self {
+        Self {
+            approximate,
+            ..self
+        }
+    }
+
+    /// The range of the length of the concatenated texts.
+    pub(crate) fn concat(self: Self, other: Self) -> Self {
+        Self {
+            min: self.min.saturating_add(other.min),
+            max: self.max.saturating_add(other.max),
+            approximate: self.approximate || other.approximate,
+        }
+    }
+
+    /// The range of the lengths mapped by a function that doesn't decrease as the length increases.
+    pub(crate) fn map<F: Fn(usize) -> usize>(self: Self, f: F) -> Self {
+        Self {
+            min: f(self.min),
+            max: f(self.max),
+            approximate: self.approximate,
+        }
+    }
+
+    /// The range that covers both ranges.
+    pub(crate) fn union(self: Self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            approximate: self.approximate || other.approximate,
+        }
+    }
+}
+
+impl std::fmt::Display for LengthRange {
+    /// Write the range as "min..=max", followed by " (approximate)" if it's approximate.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..={}", self.min, self.max)?;
+        if self.approximate {
+            write!(f, " (approximate)")?;
+        }
+        Ok(())
+    }
+}
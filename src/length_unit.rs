@@ -0,0 +1,59 @@
+//! The unit of the length of a phrase
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+/// The unit to measure the length of a phrase.
+///
+/// # Example
+/// ```rust
+/// assert_eq!(tphrase::LengthUnit::Chars.measure("Hello"), 5);
+/// assert_eq!(tphrase::LengthUnit::Chars.measure("こんにちは"), 5);
+/// ```
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    /// The number of the Unicode scalar values. (Default)
+    #[default]
+    Chars,
+    /// The number of the extended grapheme clusters. (Needs the feature "unicode-segmentation".)
+    #[cfg(feature = "unicode-segmentation")]
+    Graphemes,
+    /// The display width in a terminal, where a wide character is 2. (Needs the feature "unicode-width".)
+    #[cfg(feature = "unicode-width")]
+    DisplayWidth,
+}
+impl LengthUnit {
+    /// Measure the length of a string.
+    ///
+    /// # Parameter
+    /// - `s`: The string.
+    ///
+    /// # Return
+    /// The length in the unit.
+    pub fn measure(self: &Self, s: &str) -> usize {
+        match self {
+            LengthUnit::Chars => s.chars().count(),
+            #[cfg(feature = "unicode-segmentation")]
+            LengthUnit::Graphemes => {
+                unicode_segmentation::UnicodeSegmentation::graphemes(s, true).count()
+            }
+            #[cfg(feature = "unicode-width")]
+            LengthUnit::DisplayWidth => unicode_width::UnicodeWidthStr::width(s),
+        }
+    }
+}
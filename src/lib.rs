@@ -75,6 +75,12 @@
 //!
 //! "{GENDER}-siblings" is followed by 2 so the weight of "{GENDER}-siblings" is 2. The quotation is necessary if it's followed by a weight.
 //!
+//! The external context can be any [`Context`], e.g. `HashMap`, `BTreeMap`, a closure, or [`ChainedContext`] that layers them. The value is requested only when the phrase needs it.
+//!
+//! The value in the external context can be a list of the weighted texts ([`ContextValue::List`]). One of them is selected at random as if the list is a production rule assigned to the nonterminal.
+//!
+//! The value can also be a number ([`ContextValue::Integer`] or [`ContextValue::Float`]). It's formatted with the decimal and grouping separators of the [`Locale`] of the generator, see "Format Spec".
+//!
 //! If you will make it translatable, the external contexts should be the range in the predefined variations and use in order to restrict the context, instead of to introduce extensibility, that is, you should tell the translator the possible combinations before translating.
 //!
 //! ## Multiple Phrase Syntaxes
@@ -158,6 +164,28 @@
 //!
 //! It can use to create a common library with some assignments, but the number of the combinations should be low enough for the translators to accept them.
 //!
+//! ## Precompiled Syntax
+//!
+//! ```rust
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut syntax: tphrase::Syntax = r#"
+//!     main = {HELLO}, {WORLD}!
+//!     HELLO = Hi | Greetings | Hello | Good morning
+//!     WORLD = world | guys | folks
+//! "#.parse()?;
+//! syntax.bind_syntax("main")?;
+//! let bytes = syntax.to_bytes();
+//!
+//! // e.g. at the next launch
+//! let mut ph: tphrase::Generator = tphrase::Generator::new();
+//! let _ = ph.add(tphrase::Syntax::from_bytes(&bytes)?)?;
+//! assert_eq!(ph.combination_number(), 12);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Syntax::to_bytes()`] encodes a bound syntax into the binary data, and [`Syntax::from_bytes()`] restores it without the parser. It may reduce the startup time if you have many syntaxes.
+//!
 //! # Syntax of the Phrase Syntax
 //! ## Overview
 //! The phrase syntax consists of assignments. The order of the assignments doesn't affect the generated text. The recursive reference is not allowed. The multiple definition for a nonterminal occurs an error.
@@ -233,6 +261,12 @@
 //! ```
 //! The chance of "text1" is 25%, "text2" is 25%, "{C}" is 50%. The chance of "{C}" is lower than no weight.
 //!
+//! The weight can be "{w:NAME}" instead of the number, then the weight is the value of "NAME" in the external context when a phrase is generated. The weight is 0 if the external context doesn't have "NAME" or the value isn't a non-negative number. The weight can be a simple expression, too, e.g. "{w:RAIN_LEVEL * 2 + 1}", which has the numbers, the nonterminals, "+", "-", "*", "/", and the parentheses in 256 characters. Its weight is 0 if a nonterminal in it isn't a number, or the result isn't a finite non-negative number.
+//! ```tphrase
+//! A = sunny | "rainy" {w:RAIN_LEVEL}
+//! ```
+//! The chance of "rainy" is 75% if "RAIN_LEVEL" is "3", and 0% if it's not given. The weight propagated to the higher layers is the default weight, because the external context isn't given there. The number of the combination isn't affected.
+//!
 //! The text doesn't need to enclose quotations ('"', "'", or "`") if it meets these requirements:
 //!    1. The text is not empty.
 //!    1. The text has neither newline, "|", "~", nor "}" except for a part of an expansion.
@@ -251,8 +285,35 @@
 //! 1. "{(}" and "{)}" will be expanded into "{" and "}".
 //! 1. If the beginning of the expansion is "{*", the expansion will be expanded into the empty string. (It's effectively a comment block.)
 //! 1. If the beginning of the expansion is "{=" or "{:=", the content (except the first "=" or ":=") is considered as a production rule. For example, "{= A|B|C}" will be expanded into the result of the production rule "A|B|C". The syntax of the content is expressed by EBNF: `content = space_nl_opt, production_rule, space_nl_opt ;` "{:=" is, of course, the equalized select version of "{=".
+//! 1. If the expansion is a nonterminal followed by ":" and a valid format spec, e.g. "{N:03}" or "{PRICE:,.2}", the result of the nonterminal is formatted by the spec. See "Format Spec".
+//! 1. If the expansion is a nonterminal (or a nonterminal with a format spec) followed by "|" and the names of the filters, e.g. "{NAME|capitalize}" or "{N:>5|upper}", the result is changed by the filters in order after the format spec. See "Filter".
+//! 1. If the beginning of the expansion is "{@" followed by a name "[A-Za-z0-9_.]+", the expansion is a function call, e.g. "{@ordinal N}". The words following the name are the arguments. The function registered by [`Generator::register_function()`] is called, and the expansion is expanded into its result. [`Generator::add()`] reports the function that isn't registered as an error.
 //! 1. The other expansion will be expanded into itself removed outer "{" and "}". (I recommend that the nonterminal is noticeable to find it easily unless you will leave it unsolved.)
 //!
+//! ## Format Spec
+//! The format spec is `[[fill]align][+][0][width][,][.precision]`:
+//!
+//! - `fill`: The character to pad the result (the default is the space). It must be followed by `align`.
+//! - `align`: "<" (left), ">" (right), or "^" (center). The numbers are aligned to the right and the texts are aligned to the left by default.
+//! - `+`: The sign is written even for the non-negative numbers.
+//! - `0`: The numbers are padded with "0" after the sign unless `align` is specified. The texts that are decimal numbers, e.g. "5" or "-1.5", are padded in the same way and aligned to the right.
+//! - `width`: The minimum number of characters of the result.
+//! - `,`: The integral part of the numbers is grouped by the grouping separator of the locale.
+//! - `precision`: The number of the fractional digits of the floating point numbers, or the maximum number of characters of the texts. It's ignored for the integers.
+//!
+//! The decimal separator, the grouping separator, and the group sizes are given by the [`Locale`] of the generator. The texts in the external context, the lists, and the results of the production rules are formatted as texts, and the unsolved nonterminal is expanded into its name without formatting.
+//!
+//! ## Filter
+//! The filter changes the result of an expansion. The names of the filters are:
+//!
+//! - "upper": Convert all the characters to uppercase.
+//! - "lower": Convert all the characters to lowercase.
+//! - "capitalize": Convert the first letter to titlecase, e.g. "¿qué?" to "¿Qué?".
+//! - "title": Convert the first letter of each word to titlecase.
+//! - "trim": Remove the leading and trailing white spaces.
+//!
+//! The case is mapped by the Unicode rules independent of the language. The expansion that has an unknown filter name is expanded into itself as before. The Rust coders can apply the filters to the whole phrase by [`Generator::set_post_filters()`].
+//!
 //! ## Gsub (Global substitution)
 //! Gsub is the function to substitute the resulting string selected from the options. You can specify any number (including zero) of gsubs that substitute the string. 1st gsub specifies the substitution of the selected text out of the options, and then the result of the preceding substitution is substituted by the next gsub's.
 //!
@@ -290,13 +351,24 @@
 //!
 //! options = text, space_opt, [ { "|", space_one_nl_opt, text, space_opt } ] ;
 //! text = text_begin, [ text_body, [ text_postfix ] ] |
-//!        '"', [ { ? [^"{] ? | expansion } ], '"', space_opt, [ weight ] |
-//!        "'", [ { ? [^'{] ? | expansion } ], "'", space_opt, [ weight ] |
-//!        "`", [ { ? [^`{] ? | expansion } ], "`", space_opt, [ weight ] ;
+//!        '"', [ { ? [^"{] ? | expansion } ], '"', space_opt, [ weight | context_weight ] |
+//!        "'", [ { ? [^'{] ? | expansion } ], "'", space_opt, [ weight | context_weight ] |
+//!        "`", [ { ? [^`{] ? | expansion } ], "`", space_opt, [ weight | context_weight ] ;
 //! text_begin = ? [^ \t\n"'`|~{}] ? | expansion ; (* "}" is the next to the text when it's in {= ...}. *)
 //! text_body = { ? [^\n|~{}] ? | expansion } ;
 //! text_postfix = ? space_opt(?=($|[\n|~}])) ? ; (* text_postfix greedily matches with space_opt preceding the end of the text, newline, "|", "~", or "}", but it consumes only space_opt. *)
+//! context_weight = "{w:", weight_expr, "}" ;
+//! weight_expr = term, [ { ( "+" | "-" ), term } ] ; (* The spaces are allowed between the tokens. *)
+//! term = factor, [ { ( "*" | "/" ), factor } ] ;
+//! factor = weight | nonterminal | "-", factor | "(", weight_expr, ")" ; (* A token that matches "weight" is a number, not a nonterminal. *)
 //! expansion = "{", [ { ? [^}] ? } ], "}" ;
+//! formatted_expansion = "{", nonterminal, [ ":", format_spec ], [ { "|", filter } ], "}" ; (* A special form of expansion, which has a format spec or a filter at least. *)
+//! format_spec = [ [ ? [^}] ? ], ( "<" | ">" | "^" ) ], [ "+" ], [ "0" ], [ { ? [0-9] ? } ], [ "," ], [ ".", { ? [0-9] ? } ] ; (* It mustn't be empty. *)
+//! filter = "upper" | "lower" | "capitalize" | "title" | "trim" ;
+//! function_call = "{@", function_name, [ { white_space, argument } ], [ white_space ], "}" ; (* A special form of expansion. *)
+//! function_name = { ? [A-Za-z0-9_.] ? } ;
+//! argument = { ? [^\s}] ? } ;
+//! white_space = { ? \s ? } ;
 //!
 //! gsubs = [ { "~", space_one_nl_opt, sep, { pat }, sep2, [ { pat } ], sep2, [ gsub_limit ], space_opt } ] ; (* 'sep2' is the same character of 'sep'. *)
 //! sep = ? [^ \t\n{] ? ; (* '{' may be the beginning of the comment block. *)
@@ -304,13 +376,33 @@
 //! gsub_limit = "g" | { ? [0-9] ? } ;
 //! ```
 //!
+//! # Selection Algorithm
+//! The phrase generated with the same syntaxes and the same sequence of the random numbers is the same as long as [`SELECTION_ALGORITHM_VERSION`] is the same. The algorithm of the version 3 is:
+//!
+//! 1. [`Generator`] selects a phrase syntax, the production rule of the start condition selects a text, and then the expansions and the anonymous rules in the text are expanded from left to right. (depth-first)
+//! 1. A selection from one option doesn't consume any random number.
+//! 1. A selection from `n` (> 1) options consumes a random number `r` in the range of [0.0, 1.0). The index of the selected option is `floor(r * n)` if the chance is equalized, or the first index `i` that satisfies `W[i] >= r * W[n-1]` where `W[i]` is the sum of the weights from the 0th option to the i-th option. The 0th option is selected if the index is out of range.
+//! 1. An expansion of a list in the external context selects an item in the same way as the options, where the weights are the ones of the items and the chance isn't equalized. An empty list doesn't consume any random number.
+//! 1. A call of a registered function "{@name args}" consumes a random number `r`, and the function gets [`KeyedRng`] seeded by `floor(r * 2^53)`, even if the function doesn't use it.
+//!
+//! The changes from the previous versions are:
+//!
+//! - Version 2: An expansion of a list in the external context consumes a random number.
+//! - Version 3: A call of a registered function consumes a random number.
+//!
+//! [`Generator::generate_for_key()`] uses the random numbers derived from the key by [`KeyedRng`], whose algorithm is also stable, so the same key always yields the same phrase with the same syntaxes.
+//!
 //! # Features
-//! TPhrase has two features:
+//! TPhrase has two default features:
 //! - "fastrand": define [`FastrandRng`] and [`DefaultRng`]
 //! - "regex": define [`RegexGsub`] and [`DefaultSubst`]
 //!
 //! They are enalbed by default.
 //!
+//! And two optional features, disabled by default:
+//! - "unicode-segmentation": define [`LengthUnit::Graphemes`]
+//! - "unicode-width": define [`LengthUnit::DisplayWidth`]
+//!
 //! Disabling the features, the default generic types of [`Generator`] is removed. 1st default generic type needs "fastrand" and "regex", 2nd needs "regex".
 //!
 //! The tests and examples support only the default features.
@@ -340,33 +432,90 @@
 //!
 //! Unless you explicitly state otherwise, any contribution intentionally submitted for inclusion in the work by you, as defined in the Apache-2.0 license, shall be dual licensed as above, without any additional terms or conditions.
 
+mod ambiguity;
+mod banned_match;
+mod binary_io;
 mod compile_error;
+mod context;
+mod context_value;
+mod decode_error;
+mod derivation;
+mod derivation_error;
+mod environment;
 #[cfg(feature = "fastrand")]
 mod fastrand_rng;
+mod filter;
+mod format_spec;
+mod function_registry;
 mod generator;
+mod history;
+mod keyed_rng;
+mod length_range;
+mod length_unit;
+mod locale;
 mod parser;
+mod phrases;
 mod random_number_generator;
 #[cfg(feature = "regex")]
 mod regex_substitutor;
+mod retry_stats;
+mod shuffle_bag;
 mod substitutor;
+mod temperature;
+mod trace;
 mod utils;
+mod weight_error;
+mod weight_expr;
 
+pub use ambiguity::Ambiguity;
+pub use banned_match::BannedMatch;
 pub use compile_error::CompileError;
+pub(crate) use context::FixedContext;
+pub use context::{ChainedContext, Context};
+pub(crate) use context_value::list_weights;
+pub use context_value::ContextValue;
+pub use decode_error::DecodeError;
+pub use derivation::Derivation;
+pub(crate) use derivation::ReplaySelector;
+pub use derivation_error::DerivationError;
+pub(crate) use environment::Environment;
 #[cfg(feature = "fastrand")]
 pub use fastrand_rng::FastrandRng;
+pub use filter::Filter;
+pub(crate) use format_spec::FormatSpec;
+pub use function_registry::Function;
+pub(crate) use function_registry::FunctionRegistry;
 pub use generator::Generator;
 pub use generator::SyntaxId;
 pub use generator::SyntaxRemoveError;
+pub use history::History;
+pub(crate) use history::HistorySelector;
+pub use keyed_rng::KeyedRng;
+pub use length_range::LengthRange;
+pub use length_unit::LengthUnit;
+pub use locale::Locale;
 pub use parser::data::Syntax;
+pub(crate) use parser::data::{choose, BannedFinder, LengthSampler, RangeAnalyzer};
 pub use parser::parse;
 pub use parser::parse_str;
+pub use phrases::Phrases;
 pub use random_number_generator::RandomNumberGenerator;
+pub use random_number_generator::SeedableRandomNumberGenerator;
 #[cfg(feature = "regex")]
 pub use regex_substitutor::RegexGsub;
+pub use retry_stats::RetryStats;
+pub(crate) use shuffle_bag::{Permutation, ShuffleBag};
 pub use substitutor::Substitutor;
 pub use substitutor::SubstitutorAddError;
-pub(crate) use utils::{select_and_generate_text, TextGenerator};
+pub(crate) use temperature::{apply_temperature, TemperatureSelector};
+pub(crate) use trace::TraceSelector;
+pub use trace::{Trace, TraceNode};
+pub(crate) use utils::{
+    select_and_generate_text, selection_probability, RngSelector, Selector, TextGenerator,
+};
 pub use utils::{trunc_syntax, trunc_syntax_str};
+pub use weight_error::WeightError;
+pub(crate) use weight_expr::WeightExpr;
 
 /// The default random number generator of [`Generator`].
 #[cfg(feature = "fastrand")]
@@ -374,8 +523,14 @@ pub type DefaultRng = FastrandRng;
 /// The default substitutor of [`Generator`].
 #[cfg(feature = "regex")]
 pub type DefaultSubst = RegexGsub;
-/// The type of the external context.
+/// The version of the algorithm to select an option. See "Selection Algorithm" in the crate document.
+pub const SELECTION_ALGORITHM_VERSION: u32 = 3;
+/// The maximum number of the derivations that the methods enumerating all the derivations, i.e. [`Generator::probability_of()`], [`Generator::distinct_phrase_count()`], and [`Generator::ambiguities()`], accept. See [`Generator::combination_number_exact()`].
+pub const ENUMERATION_LIMIT: u128 = 1 << 24;
+/// The type of the external context held in a map. It implements [`Context`].
 pub type ExtContext = std::collections::HashMap<String, String>;
+/// The type of the bounds of the length of the values in the external context.
+pub type ExtLengthBounds = std::collections::HashMap<String, LengthRange>;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,167 @@
+//! The locale to format the numbers
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+/// The separators to format the numbers in the external context.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let mut ph: tphrase::Generator = "main = {PRICE:,.2} EUR".parse()?;
+/// let context = std::collections::HashMap::from([
+///     ("PRICE".to_string(), tphrase::ContextValue::from(1234.5)),
+/// ]);
+/// assert_eq!(ph.generate_with_context(&context), "1,234.50 EUR");
+/// ph.set_locale(tphrase::Locale::from_tag("de-DE").unwrap());
+/// assert_eq!(ph.generate_with_context(&context), "1.234,50 EUR");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Locale {
+    /// The separator between the integer part and the fractional part.
+    decimal_separator: String,
+    /// The separator between the groups of the digits.
+    grouping_separator: String,
+    /// The number of the digits in the lowest group.
+    primary_group: usize,
+    /// The number of the digits in the other groups.
+    secondary_group: usize,
+}
+impl Locale {
+    /// Create an instance whose groups have 3 digits.
+    ///
+    /// # Parameter
+    /// - `decimal_separator`: The separator between the integer part and the fractional part.
+    /// - `grouping_separator`: The separator between the groups of the digits.
+    pub fn new(decimal_separator: &str, grouping_separator: &str) -> Self {
+        Self {
+            decimal_separator: decimal_separator.to_string(),
+            grouping_separator: grouping_separator.to_string(),
+            primary_group: 3,
+            secondary_group: 3,
+        }
+    }
+
+    /// Create an instance from a language tag.
+    ///
+    /// # Parameter
+    /// - `tag`: The language tag, e.g. "en", "de-DE", or "fr_FR". The case doesn't matter.
+    ///
+    /// # Return
+    /// The locale, or [`None`] if neither the tag nor its language is known.
+    ///
+    /// # Note
+    /// The separators are built in, so no external data is used. The known languages are en, ja, zh, ko, th, he, hi, de, es, it, nl, pt, id, tr, da, el, ro, hr, sl, vi, fr, ru, pl, cs, sk, uk, sv, fi, nb, no, hu, bg, lt, lv, and et. The regional variants de-CH, de-LI, en-IN, and pt-PT are distinguished.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().to_ascii_lowercase().replace('_', "-");
+        let locale = match tag.as_str() {
+            "de-ch" | "de-li" => Some(Self::new(".", "\u{2019}")),
+            "en-in" => Some(Self::new(".", ",").with_group_sizes(3, 2)),
+            "pt-pt" => Some(Self::new(",", "\u{a0}")),
+            _ => None,
+        };
+        if locale.is_some() {
+            return locale;
+        }
+        let language = tag.split('-').next().unwrap_or("");
+        return match language {
+            "en" | "ja" | "zh" | "ko" | "th" | "he" => Some(Self::new(".", ",")),
+            "hi" => Some(Self::new(".", ",").with_group_sizes(3, 2)),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+            | "vi" => Some(Self::new(",", ".")),
+            "fr" => Some(Self::new(",", "\u{202f}")),
+            "ru" | "pl" | "cs" | "sk" | "uk" | "sv" | "fi" | "nb" | "no" | "hu" | "bg" | "lt"
+            | "lv" | "et" => Some(Self::new(",", "\u{a0}")),
+            _ => None,
+        };
+    }
+
+    /// Set the sizes of the groups of the digits.
+    ///
+    /// # Parameter
+    /// - `primary`: The number of the digits in the lowest group, or 0 not to group the digits.
+    /// - `secondary`: The number of the digits in the other groups, or 0 to use `primary`.
+    ///
+    /// # Return
+    /// The locale that has the sizes.
+    pub fn with_group_sizes(self: Self, primary: usize, secondary: usize) -> Self {
+        Self {
+            primary_group: primary,
+            secondary_group: if secondary == 0 { primary } else { secondary },
+            ..self
+        }
+    }
+
+    /// The separator between the integer part and the fractional part.
+    pub fn decimal_separator(self: &Self) -> &str {
+        &self.decimal_separator
+    }
+
+    /// The separator between the groups of the digits.
+    pub fn grouping_separator(self: &Self) -> &str {
+        &self.grouping_separator
+    }
+
+    /// The number of the digits in the lowest group.
+    pub fn primary_group(self: &Self) -> usize {
+        self.primary_group
+    }
+
+    /// The number of the digits in the groups except the lowest one.
+    pub fn secondary_group(self: &Self) -> usize {
+        self.secondary_group
+    }
+
+    /// Insert the grouping separators into the digits.
+    ///
+    /// # Parameter
+    /// - `digits`: The digits of the integer part.
+    ///
+    /// # Return
+    /// The grouped digits.
+    pub(crate) fn group(self: &Self, digits: &str) -> String {
+        let chars: Vec<char> = digits.chars().collect();
+        if self.primary_group == 0 || chars.len() <= self.primary_group {
+            return digits.to_string();
+        }
+        // The positions where a separator precedes, counted from the end.
+        let mut sizes = vec![self.primary_group];
+        let mut n = self.primary_group;
+        while n + self.secondary_group < chars.len() {
+            n += self.secondary_group;
+            sizes.push(n);
+        }
+        let mut r = String::new();
+        for (i, c) in chars.iter().enumerate() {
+            if i > 0 && sizes.contains(&(chars.len() - i)) {
+                r += &self.grouping_separator;
+            }
+            r.push(*c);
+        }
+        return r;
+    }
+}
+
+impl Default for Locale {
+    /// The locale whose decimal separator is "." and grouping separator is ",", grouping every 3 digits.
+    fn default() -> Self {
+        Self::new(".", ",")
+    }
+}
@@ -37,6 +37,10 @@ struct CharFeeder<'a, I: Iterator<Item = char>> {
     line: usize,
     /// The column number at the current position.
     column: usize,
+    /// The codepoints passed while recording.
+    record: String,
+    /// The number of the recordings in progress.
+    num_recordings: usize,
 }
 
 impl<'a, I: Iterator<Item = char>> CharFeeder<'a, I> {
@@ -54,6 +58,8 @@ impl<'a, I: Iterator<Item = char>> CharFeeder<'a, I> {
             num_c: 0,
             line: 1,
             column: 1,
+            record: String::new(),
+            num_recordings: 0,
         };
         for i in 0..=Self::LOOK_AHEAD {
             match s.it.next() {
@@ -111,6 +117,9 @@ impl<'a, I: Iterator<Item = char>> CharFeeder<'a, I> {
         } else {
             self.column += 1;
         }
+        if self.num_recordings > 0 {
+            self.record.push(self.c[0]);
+        }
         self.c[0] = self.c[1];
         match self.it.next() {
             None => {
@@ -122,15 +131,57 @@ impl<'a, I: Iterator<Item = char>> CharFeeder<'a, I> {
             }
         }
     }
+
+    /// Start recording the codepoints passed by [`next()`].
+    ///
+    /// # Return
+    /// The position to pass to [`stop_recording()`].
+    ///
+    /// [`next()`]: #method.next
+    /// [`stop_recording()`]: #method.stop_recording
+    fn start_recording(self: &mut Self) -> usize {
+        self.num_recordings += 1;
+        self.record.len()
+    }
+
+    /// Stop recording.
+    ///
+    /// # Parameter
+    /// - `start`: The position returned by [`start_recording()`].
+    ///
+    /// # Return
+    /// The codepoints passed since [`start_recording()`].
+    ///
+    /// # Note
+    /// The recordings can be nested.
+    ///
+    /// [`start_recording()`]: #method.start_recording
+    fn stop_recording(self: &mut Self, start: usize) -> String {
+        let r = self.record[start..].to_string();
+        self.num_recordings -= 1;
+        if self.num_recordings == 0 {
+            self.record.clear();
+        }
+        return r;
+    }
+
+    /// Cancel all the recordings, which are left by an error.
+    fn cancel_recordings(self: &mut Self) {
+        self.num_recordings = 0;
+        self.record.clear();
+    }
 }
 
+use self::data::GsubParam;
 use self::data::ProductionRule;
 use self::data::Syntax;
 use self::data::Text;
 use self::data::TextOptions;
+use crate::Filter;
+use crate::FormatSpec;
 use crate::Substitutor;
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::WeightExpr;
+use std::sync::{Arc, RwLock};
 
 /// String in [`Err`] is the human readable error message. It's not an [`std::error::Error`] because `ParseResult` is private.
 type ParseResult<T> = Result<T, String>;
@@ -197,6 +248,7 @@ pub fn parse<S: Substitutor, I: Iterator<Item = char>>(
         if let Err(e) = parse_assignment(&mut it, &mut syntax) {
             err_msg.push(e);
             // Recovering from the error
+            it.cancel_recordings();
             let mut cont_line = false;
             while !it.is_end() {
                 let c = it.c();
@@ -385,10 +437,10 @@ fn parse_assignment<S: Substitutor, I: Iterator<Item = char>>(
             rule.equalize_chance(true);
         }
         if let Err(err_msg) = syntax.add_production_rule(&nonterminal, rule) {
-            return parse_error(&it, &err_msg);
+            return parse_error(it, &err_msg);
         }
     } else {
-        return parse_error(&it, "The end of the text or \"\\n\" is expected.");
+        return parse_error(it, "The end of the text or \"\\n\" is expected.");
     }
     return Ok(());
 }
@@ -417,7 +469,7 @@ fn parse_nonterminal<I: Iterator<Item = char>>(it: &mut CharFeeder<I>) -> ParseR
         }
     }
     if nonterminal.is_empty() {
-        return parse_error(&it, "A nonterminal \"[A-Za-z0-9_.]+\" is expected.");
+        return parse_error(it, "A nonterminal \"[A-Za-z0-9_.]+\" is expected.");
     }
     return Ok(nonterminal);
 }
@@ -446,7 +498,7 @@ fn parse_weight<I: Iterator<Item = char>>(it: &mut CharFeeder<I>) -> ParseResult
             it.next();
             c = it.c();
         } else {
-            return parse_error(&it, "A number is expected. (\".\" is not a number.)");
+            return parse_error(it, "A number is expected. (\".\" is not a number.)");
         }
     } else if c.is_ascii_digit() {
         while {
@@ -519,8 +571,8 @@ fn parse_production_rule<S: Substitutor, I: Iterator<Item = char>>(
     term_char: char,
 ) -> ParseResult<ProductionRule<S>> {
     let options = parse_options(it)?;
-    let gsubs = parse_gsubs(it)?;
-    let rule = ProductionRule::new(options, gsubs);
+    let (gsubs, gsub_params) = parse_gsubs(it)?;
+    let rule = ProductionRule::new(options, gsubs, gsub_params);
     if term_char != '\0' {
         skip_space_nl(it)?;
         if it.c() == term_char {
@@ -601,9 +653,9 @@ fn parse_text<S: Substitutor, I: Iterator<Item = char>>(
 /// # Related EBNF
 /// ```EBNF
 /// text = ...
-///     '"', [ { ? [^"{] ? | expansion } ], '"', space_opt, [ number ] |
-///     "'", [ { ? [^'{] ? | expansion } ], "'", space_opt, [ number ] |
-///     "`", [ { ? [^`{] ? | expansion } ], "`", space_opt, [ number ] ;
+///     '"', [ { ? [^"{] ? | expansion } ], '"', space_opt, [ number | context_weight ] |
+///     "'", [ { ? [^'{] ? | expansion } ], "'", space_opt, [ number | context_weight ] |
+///     "`", [ { ? [^`{] ? | expansion } ], "`", space_opt, [ number | context_weight ] ;
 /// ```
 fn parse_quoted_text<S: Substitutor, I: Iterator<Item = char>>(
     it: &mut CharFeeder<I>,
@@ -612,6 +664,7 @@ fn parse_quoted_text<S: Substitutor, I: Iterator<Item = char>>(
     let mut s = String::new();
     let quote = it.c();
     it.next();
+    let start = it.start_recording();
     while !it.is_end() && it.c() != quote {
         if it.c() == '{' {
             parse_expansion(it, &mut text, &mut s)?;
@@ -628,15 +681,63 @@ fn parse_quoted_text<S: Substitutor, I: Iterator<Item = char>>(
         msg += " is expected.";
         return parse_error(it, &msg);
     }
+    text.set_label(it.stop_recording(start));
     if !s.is_empty() {
         text.add_string(s);
     }
     it.next();
     skip_space(it)?;
-    text.set_weight(parse_weight(it)?);
+    if it.c() == '{' && it.next_c() == 'w' {
+        text.set_weight_expr(parse_context_weight(it)?);
+    } else {
+        text.set_weight(parse_weight(it)?);
+    }
     return Ok(text);
 }
 
+/// Parse a weight referring to the external context.
+///
+/// # Parameter
+/// - `it`: The character feeder.
+///
+/// # Return
+/// The expression of the weight referring to the external context.
+///
+/// # Related EBNF
+/// ```EBNF
+/// context_weight = "{w:", weight_expr, "}" ;
+/// ```
+/// See [`WeightExpr`] for "weight_expr".
+fn parse_context_weight<I: Iterator<Item = char>>(
+    it: &mut CharFeeder<I>,
+) -> ParseResult<WeightExpr> {
+    it.next();
+    it.next();
+    if it.is_end() || it.c() != ':' {
+        return parse_error(it, "\":\" is expected after \"{w\".");
+    }
+    it.next();
+    let mut source = String::new();
+    while !it.is_end() && it.c() != '}' && it.c() != '\n' {
+        source.push(it.c());
+        it.next();
+    }
+    if it.is_end() || it.c() != '}' {
+        return parse_error(it, "The end of the context weight \"}\" is expected.");
+    }
+    let expr = match WeightExpr::parse(&source) {
+        Some(expr) => expr,
+        None => {
+            return parse_error(
+                it,
+                "The context weight must be a nonterminal or a simple expression.",
+            )
+        }
+    };
+    it.next();
+    return Ok(expr);
+}
+
 /// Parse a non quoted text.
 ///
 /// # Parameter
@@ -660,6 +761,7 @@ fn parse_non_quoted_text<S: Substitutor, I: Iterator<Item = char>>(
     let mut text = Text::new();
     let mut s = String::new();
     let mut spaces = String::new(); // The candidate for "text_postfix" (trailing spaces)
+    let start = it.start_recording();
 
     loop {
         let c = it.c();
@@ -700,6 +802,8 @@ fn parse_non_quoted_text<S: Substitutor, I: Iterator<Item = char>>(
             }
         };
     }
+    let label = it.stop_recording(start);
+    text.set_label(label.trim_end_matches([' ', '\t']).to_string());
     return Ok(text);
 }
 
@@ -752,7 +856,7 @@ fn parse_expansion<S: Substitutor, I: Iterator<Item = char>>(
         if c == ':' {
             rule.equalize_chance(true);
         }
-        text.add_anonymous_rule(Rc::new(RefCell::new(rule)));
+        text.add_anonymous_rule(Arc::new(RwLock::new(rule)));
         return Ok(());
     } else {
         let is_comment = c == '*';
@@ -768,6 +872,19 @@ fn parse_expansion<S: Substitutor, I: Iterator<Item = char>>(
                         s.clear();
                     }
                     text.add_expansion(name);
+                } else if let Some((nonterminal, spec, filters)) = split_formatted_expansion(&name)
+                {
+                    if !s.is_empty() {
+                        text.add_string(s.clone());
+                        s.clear();
+                    }
+                    text.add_formatted_expansion(nonterminal.to_string(), spec, filters);
+                } else if let Some((function, args)) = split_function_call(&name) {
+                    if !s.is_empty() {
+                        text.add_string(s.clone());
+                        s.clear();
+                    }
+                    text.add_call(function, args);
                 } else if !is_comment {
                     *s += &name;
                 }
@@ -783,21 +900,89 @@ fn parse_expansion<S: Substitutor, I: Iterator<Item = char>>(
     return parse_error(it, "The end of the brace expansion is expected.");
 }
 
+/// Split the content of an expansion into the nonterminal, the format spec, and the filters.
+///
+/// # Parameter
+/// - `content`: The string enclosed by "{" and "}".
+///
+/// # Return
+/// The nonterminal, the format spec, and the filters, or [`None`] if the content isn't a formatted expansion.
+///
+/// # Note
+/// The filters are taken from the end, so the fill character of the format spec can be "|".
+///
+/// # Related EBNF
+/// ```EBNF
+/// formatted_expansion = "{", nonterminal, [ ":", format_spec ], [ { "|", filter } ], "}" ;
+/// ```
+fn split_formatted_expansion(content: &str) -> Option<(&str, Option<FormatSpec>, Vec<Filter>)> {
+    let mut head = content;
+    let mut filters = Vec::new();
+    while let Some((rest, name)) = head.rsplit_once('|') {
+        match Filter::from_name(name) {
+            Some(f) => filters.push(f),
+            None => break,
+        }
+        head = rest;
+    }
+    filters.reverse();
+    let (nonterminal, spec) = match head.split_once(':') {
+        Some((nonterminal, spec)) => (nonterminal, Some(FormatSpec::parse(spec)?)),
+        None if !filters.is_empty() => (head, None),
+        None => return None,
+    };
+    if nonterminal.is_empty() || !nonterminal.chars().all(is_nonterminal_char) {
+        return None;
+    }
+    return Some((nonterminal, spec, filters));
+}
+
+/// Split the content of an expansion into the name and the arguments of a function call.
+///
+/// # Parameter
+/// - `content`: The string enclosed by "{" and "}".
+///
+/// # Return
+/// The name and the arguments, or [`None`] if the content isn't a function call.
+///
+/// # Related EBNF
+/// ```EBNF
+/// function_call = "{@", function_name, [ { white_space, argument } ], [ white_space ], "}" ;
+/// function_name = { ? [A-Za-z0-9_.] ? } ;
+/// argument = { ? [^\s}] ? } ;
+/// white_space = { ? \s ? } ;
+/// ```
+fn split_function_call(content: &str) -> Option<(String, Vec<String>)> {
+    let rest = content.strip_prefix('@')?;
+    if rest.starts_with(|c: char| c.is_whitespace()) {
+        return None;
+    }
+    let mut words = rest.split_whitespace();
+    let name = words.next()?;
+    if !name.chars().all(is_nonterminal_char) {
+        return None;
+    }
+    return Some((name.to_string(), words.map(|w| w.to_string()).collect()));
+}
+
 /// Parse a gsubs.
 ///
 /// # Parameter
 /// - `it`: The character feeder.
 ///
 /// # Return
-/// The gsubs.
+/// The gsubs, and the parameters of them.
 ///
 /// # Related EBNF
 /// ```EBNF
 /// gsubs = [ { "~", space_one_nl_opt, sep, { pat }, sep2, [ { pat } ], sep2, [ gsub_limit ], space_opt } ] ; (* 'sep2' is the same character of 'sep'. *)
 /// sep = ? [^ \t\n{] ? ; (* '{' may be the beginning of the comment block. *)
 /// ```
-fn parse_gsubs<S: Substitutor, I: Iterator<Item = char>>(it: &mut CharFeeder<I>) -> ParseResult<S> {
+fn parse_gsubs<S: Substitutor, I: Iterator<Item = char>>(
+    it: &mut CharFeeder<I>,
+) -> ParseResult<(S, Vec<GsubParam>)> {
     let mut gsubs = S::new();
+    let mut gsub_params = Vec::new();
     while it.c() == '~' {
         it.next();
         skip_space_one_nl(it)?;
@@ -812,14 +997,15 @@ fn parse_gsubs<S: Substitutor, I: Iterator<Item = char>>(it: &mut CharFeeder<I>)
         let pattern = parse_pattern(it, sep, false)?;
         let repl = parse_pattern(it, sep, true)?;
         let limit = parse_gsub_limit(it)?;
-        if let Err(subst_err) = gsubs.add(&pattern, repl, limit) {
+        if let Err(subst_err) = gsubs.add(&pattern, repl.clone(), limit) {
             let mut err_msg = "Gsub error: ".to_string();
-            err_msg += &subst_err.error_message();
+            err_msg += subst_err.error_message();
             return parse_error(it, &err_msg);
         }
+        gsub_params.push(GsubParam::new(pattern, repl, limit));
         skip_space(it)?;
     }
-    return Ok(gsubs);
+    return Ok((gsubs, gsub_params));
 }
 
 /// Parse a gsub limit.
@@ -925,4 +1111,22 @@ mod tests {
         it.next();
         assert_eq!(it.is_end(), true);
     }
+
+    #[test]
+    fn test_char_feeder_recording() {
+        let v = "abcde";
+        let mut chars = v.chars();
+        let mut it = CharFeeder::new(&mut chars);
+        it.next();
+        let outer = it.start_recording();
+        it.next();
+        let inner = it.start_recording();
+        it.next();
+        it.next();
+        assert_eq!(it.stop_recording(inner), "cd");
+        it.next();
+        assert_eq!(it.stop_recording(outer), "bcde");
+        let start = it.start_recording();
+        assert_eq!(it.stop_recording(start), "");
+    }
 }
@@ -18,17 +18,61 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 
+use crate::list_weights;
 use crate::select_and_generate_text;
 use crate::CompileError;
-use crate::ExtContext;
+use crate::Context;
+use crate::ContextValue;
+use crate::Environment;
+use crate::ExtLengthBounds;
+use crate::Filter;
+use crate::FormatSpec;
+use crate::FunctionRegistry;
+use crate::KeyedRng;
+use crate::LengthRange;
+use crate::LengthUnit;
+use crate::Locale;
+use crate::Phrases;
 use crate::RandomNumberGenerator;
+use crate::RngSelector;
+use crate::SeedableRandomNumberGenerator;
+use crate::Selector;
 use crate::Substitutor;
 use crate::TextGenerator;
+use crate::WeightError;
+use crate::WeightExpr;
 
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::rc::Rc;
-type Assignments<S> = HashMap<String, Rc<RefCell<ProductionRule<S>>>>;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+mod banned;
+mod binary;
+mod length;
+
+pub(crate) use self::banned::BannedFinder;
+pub(crate) use self::length::{choose, LengthSampler, RangeAnalyzer};
+
+type Assignments<S> = HashMap<String, Arc<RwLock<ProductionRule<S>>>>;
+
+/// The last binding epoch, shared by all the instances of [`Syntax`].
+static LAST_BINDING_EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+/// A new binding epoch.
+///
+/// # Return
+/// The epoch that isn't used by any instances of [`Syntax`], except 0 that means the unbound state. (It isn't unique only if the counter wraps around.)
+fn next_binding_epoch() -> usize {
+    loop {
+        let epoch = LAST_BINDING_EPOCH
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1);
+        if epoch != 0 {
+            return epoch;
+        }
+    }
+}
 
 /// A part of the text.
 #[derive(Debug)]
@@ -36,9 +80,15 @@ enum Part<S: Substitutor> {
     /// The part is a literal text.
     Literal(String),
     /// The part is an expansion. [`Option`] is [`None`] when the [`Part`] doesn't bind to a [`Syntax`].
-    Expansion(String, Option<Rc<RefCell<ProductionRule<S>>>>),
+    Expansion(String, Option<Arc<RwLock<ProductionRule<S>>>>),
     /// The part is an anonymous rule.
-    AnonymousRule(Rc<RefCell<ProductionRule<S>>>),
+    AnonymousRule(Arc<RwLock<ProductionRule<S>>>),
+    /// The part is an expansion or an anonymous rule whose result is formatted by the spec.
+    Formatted(Box<Part<S>>, FormatSpec),
+    /// The part is an expansion, an anonymous rule, or a formatted part whose result is changed by the filters in order.
+    Filtered(Box<Part<S>>, Vec<Filter>),
+    /// The part is a call of the function that has the name and the arguments.
+    Call(String, Vec<String>),
 }
 impl<S: Substitutor> Clone for Part<S> {
     /// # Note
@@ -48,10 +98,194 @@ impl<S: Substitutor> Clone for Part<S> {
             Part::Literal(s) => Part::Literal(s.clone()),
             Part::Expansion(s, _) => Part::Expansion(s.clone(), None),
             Part::AnonymousRule(r) => {
-                Part::AnonymousRule(Rc::new(RefCell::new(r.borrow().clone())))
+                Part::AnonymousRule(Arc::new(RwLock::new(r.read().unwrap().clone())))
+            }
+            Part::Formatted(p, spec) => Part::Formatted(Box::new((**p).clone()), spec.clone()),
+            Part::Filtered(p, filters) => Part::Filtered(Box::new((**p).clone()), filters.clone()),
+            Part::Call(name, args) => Part::Call(name.clone(), args.clone()),
+        }
+    }
+}
+impl<S: Substitutor> Part<S> {
+    /// The production rule that the part expands.
+    ///
+    /// # Return
+    /// The rule, or [`None`] if the part doesn't bind to a rule.
+    fn rule(self: &Self) -> Option<&Arc<RwLock<ProductionRule<S>>>> {
+        match self {
+            Part::AnonymousRule(r) => Some(r),
+            Part::Expansion(_, Some(r)) => Some(r),
+            Part::Formatted(p, _) | Part::Filtered(p, _) => p.rule(),
+            _ => None,
+        }
+    }
+
+    /// Bind the part on a syntax.
+    ///
+    /// # Parameter
+    /// - `assingments`: The assignments in the [`Syntax`] to be bound on.
+    /// - `epoch`: The current binding epoch.
+    /// - `err_msg`: The error messages are added if some errors are detected.
+    ///
+    /// # Errors
+    /// An error message is added to `err_msg` if this instance detects a recursive expansion.
+    fn bind_syntax(
+        self: &mut Self,
+        assignments: &Assignments<S>,
+        epoch: usize,
+        err_msg: &mut Vec<String>,
+    ) {
+        match self {
+            Part::AnonymousRule(r) => {
+                r.write().unwrap().bind_syntax(assignments, epoch, err_msg);
+            }
+            Part::Expansion(s, _) => {
+                if let Some(r) = assignments.get(s) {
+                    match r.try_write() {
+                        Ok(mut rule) => {
+                            rule.bind_syntax(assignments, epoch, err_msg);
+                            *self = Part::Expansion(s.clone(), Some(Arc::clone(r)));
+                        }
+                        Err(_) => {
+                            let mut msg = "Recursive expansion of \"".to_string();
+                            msg += s;
+                            msg += "\" is detected.";
+                            err_msg.push(msg);
+                        }
+                    }
+                }
+            }
+            Part::Formatted(p, _) | Part::Filtered(p, _) => {
+                p.bind_syntax(assignments, epoch, err_msg)
+            }
+            Part::Literal(_) | Part::Call(_, _) => (),
+        };
+    }
+
+    /// Fix the reference to the local nonterminal.
+    ///
+    /// # Parameter
+    /// - `syntax`: The syntax to be fixed.
+    /// - `err_msg`: The error messages are added if some errors are detected.
+    ///
+    /// # Errors
+    /// An error is caused if the local nonterminal that is referred by the part doesn't exists.
+    fn fix_local_nonterminal(self: &mut Self, syntax: &Syntax<S>, err_msg: &mut Vec<String>) {
+        match self {
+            Part::Expansion(s, _) if Syntax::<S>::is_local_nonterminal(s) => {
+                if let Some(r) = syntax.production_rule(s) {
+                    *self = Part::AnonymousRule(r);
+                } else {
+                    let mut msg = "The local nonterminal \"".to_string();
+                    msg += s;
+                    msg += "\" is not found.";
+                    err_msg.push(msg);
+                }
+            }
+            Part::Formatted(p, _) | Part::Filtered(p, _) => {
+                p.fix_local_nonterminal(syntax, err_msg)
+            }
+            _ => (),
+        }
+    }
+
+    /// Generate a text from the part except a literal.
+    ///
+    /// # Parameter
+    /// - `spec`: The format spec applied to the text, or [`None`].
+    /// - `env`: The environment that has the external context and the locale.
+    /// - `selector`: The selector that selects the options.
+    ///
+    /// # Return
+    /// A text.
+    ///
+    /// # Note
+    /// The unsolved nonterminal is expanded into its name without the format.
+    fn generate<Sel: Selector>(
+        self: &Self,
+        spec: Option<&FormatSpec>,
+        env: &Environment<'_>,
+        selector: &mut Sel,
+    ) -> String {
+        let format_text = |s: String| match spec {
+            Some(spec) => spec.format_text(&s),
+            None => s,
+        };
+        match self {
+            Part::Literal(s) => s.clone(),
+            Part::Expansion(s, Some(e)) => {
+                format_text(e.read().unwrap().generate_rule(Some(s), env, selector))
+            }
+            Part::Expansion(s, None) => match env.ext_context().value(s) {
+                Some(ContextValue::List(items)) => {
+                    selector.use_context(s);
+                    format_text(generate_from_list(s, &items, selector))
+                }
+                Some(value) => {
+                    selector.use_context(s);
+                    let plain = FormatSpec::default();
+                    spec.unwrap_or(&plain)
+                        .format_value(&value, env.locale())
+                        .unwrap_or_default()
+                }
+                None => s.clone(),
+            },
+            Part::AnonymousRule(e) => {
+                format_text(e.read().unwrap().generate_rule(None, env, selector))
+            }
+            Part::Formatted(p, spec) => p.generate(Some(spec), env, selector),
+            Part::Filtered(p, filters) => {
+                Filter::apply_all(filters, p.generate(spec, env, selector))
+            }
+            Part::Call(name, args) => {
+                let source = call_source(name, args);
+                match env.functions().get(name) {
+                    Some(f) => {
+                        let mut rng = match selector.seed() {
+                            Some(seed) => KeyedRng::from_seed(seed),
+                            None => KeyedRng::from_key(source.as_bytes()),
+                        };
+                        let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+                        f(&args, env.ext_context(), &mut rng)
+                    }
+                    None => source,
+                }
             }
         }
     }
+
+    /// Collect the names of the functions called by the part.
+    ///
+    /// # Parameter
+    /// - `names`: The names are inserted.
+    ///
+    /// # Note
+    /// The production rules referred by the expansions aren't visited.
+    fn function_names(self: &Self, names: &mut BTreeSet<String>) {
+        match self {
+            Part::Call(name, _) => {
+                names.insert(name.clone());
+            }
+            Part::AnonymousRule(r) => r.read().unwrap().function_names(names),
+            Part::Formatted(p, _) | Part::Filtered(p, _) => p.function_names(names),
+            Part::Literal(_) | Part::Expansion(_, _) => (),
+        }
+    }
+}
+
+/// The source text of a function call, e.g. "@ordinal N".
+///
+/// # Parameter
+/// - `name`: The name of the function.
+/// - `args`: The arguments.
+pub(super) fn call_source(name: &str, args: &[String]) -> String {
+    let mut r = "@".to_string();
+    r += name;
+    for a in args.iter() {
+        r += " ";
+        r += a;
+    }
+    return r;
 }
 
 /// The data structure representing the text.
@@ -59,58 +293,76 @@ impl<S: Substitutor> Clone for Part<S> {
 pub(super) struct Text<S: Substitutor> {
     /// The parts of the text.
     parts: Vec<Part<S>>,
-    /// The number of the combination.
-    comb: usize,
+    /// The label of the text, which is its source text.
+    label: String,
+    /// The number of the combination, or [`None`] if it overflows.
+    comb: Option<u128>,
     /// The weight of the text.
     weight: f64,
     /// Was the weight manually set?
     weight_by_user: bool,
+    /// The expression of the weight referring to the external context, or [`None`] if the weight doesn't depend on the context.
+    weight_expr: Option<WeightExpr>,
 }
 impl<S: Substitutor> Clone for Text<S> {
     fn clone(self: &Self) -> Self {
         Self {
             parts: self.parts.clone(),
+            label: self.label.clone(),
             comb: self.comb,
             weight: self.weight,
             weight_by_user: self.weight_by_user,
+            weight_expr: self.weight_expr.clone(),
         }
     }
 }
 impl<S: Substitutor> TextGenerator for Text<S> {
-    fn generate<R: RandomNumberGenerator>(
-        self: &Self,
-        ext_context: &ExtContext,
-        rng: &mut R,
-    ) -> String {
+    fn generate<Sel: Selector>(self: &Self, env: &Environment<'_>, selector: &mut Sel) -> String {
         let mut r = "".to_string();
         for p in self.parts.iter() {
             match p {
-                Part::Literal(s) => r += &s,
-                Part::Expansion(s, e_opt) => {
-                    if let Some(e) = e_opt {
-                        r += &e.borrow().generate(ext_context, rng);
-                    } else if let Some(ext_str) = ext_context.get(s) {
-                        r += ext_str;
-                    } else {
-                        r += &s;
-                    }
-                }
-                Part::AnonymousRule(e) => {
-                    r += &e.borrow().generate(ext_context, rng);
-                }
+                Part::Literal(s) => r += s,
+                _ => r += &p.generate(None, env, selector),
             };
         }
         return r;
     }
 }
+
+/// Generate a text by selecting an item of a list in the external context, as if the list is a production rule.
+///
+/// # Parameter
+/// - `name`: The nonterminal that has the list.
+/// - `items`: The texts and their weights.
+/// - `selector`: The selector that selects the item.
+///
+/// # Return
+/// The selected text, or "nil" if the list is empty.
+fn generate_from_list<Sel: Selector>(
+    name: &str,
+    items: &[(String, f64)],
+    selector: &mut Sel,
+) -> String {
+    if items.is_empty() {
+        return "nil".to_string();
+    }
+    selector.enter_rule(Some(name));
+    let i = selector.select(&list_weights(items), false);
+    let r = items[i].0.clone();
+    selector.leave_rule(&r, &r);
+    return r;
+}
+
 impl<S: Substitutor> Text<S> {
     /// Create an empty [`Text`].
     pub(super) fn new() -> Self {
         Self {
             parts: Vec::new(),
-            comb: 1,
+            label: String::new(),
+            comb: Some(1),
             weight: 1.0,
             weight_by_user: false,
+            weight_expr: None,
         }
     }
 
@@ -130,22 +382,63 @@ impl<S: Substitutor> Text<S> {
         self.parts.push(Part::Expansion(s, None))
     }
 
+    /// Add an expansion name whose result is formatted and filtered.
+    ///
+    /// # Parameter
+    /// - `name`: The expansion name.
+    /// - `spec`: The format spec, or [`None`].
+    /// - `filters`: The filters applied after the format spec.
+    pub(super) fn add_formatted_expansion(
+        self: &mut Self,
+        s: String,
+        spec: Option<FormatSpec>,
+        filters: Vec<Filter>,
+    ) {
+        let mut part = Part::Expansion(s, None);
+        if let Some(spec) = spec {
+            part = Part::Formatted(Box::new(part), spec);
+        }
+        if !filters.is_empty() {
+            part = Part::Filtered(Box::new(part), filters);
+        }
+        self.parts.push(part);
+    }
+
+    /// Add a function call that is a part of the text.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the function.
+    /// - `args`: The arguments.
+    pub(super) fn add_call(self: &mut Self, name: String, args: Vec<String>) {
+        self.parts.push(Part::Call(name, args));
+    }
+
     /// Add an anonymous rule that is a part of the text.
     ///
     /// # Parameter
     /// - `r`: The anonymous rule.
-    pub(super) fn add_anonymous_rule(self: &mut Self, r: Rc<RefCell<ProductionRule<S>>>) {
+    pub(super) fn add_anonymous_rule(self: &mut Self, r: Arc<RwLock<ProductionRule<S>>>) {
         self.parts.push(Part::AnonymousRule(r));
     }
 
+    /// Set the label of the text.
+    ///
+    /// # Parameter
+    /// - `label`: The label, which is the source text of the text.
+    pub(super) fn set_label(self: &mut Self, label: String) {
+        self.label = label;
+    }
+
     /// Set the weight of the text manually.
     ///
     /// # Parameter
     /// - `w`: The weight. The default value is used if weight is [`None`].
     ///
     /// # Note
-    /// It disable the automatic calculation of the weight if `w` is not [`None`].
+    /// - It disable the automatic calculation of the weight if `w` is not [`None`].
+    /// - The weight referring to the external context is removed.
     pub(super) fn set_weight(self: &mut Self, w: Option<f64>) {
+        self.weight_expr = None;
         if let Some(x) = w {
             self.weight = x;
             self.weight_by_user = true;
@@ -154,6 +447,35 @@ impl<S: Substitutor> Text<S> {
         }
     }
 
+    /// Set the expression of the weight referring to the external context.
+    ///
+    /// # Parameter
+    /// - `expr`: The expression.
+    ///
+    /// # Note
+    /// The weight calculated automatically is used where the external context isn't available, e.g. the weight propagated to the higher layers.
+    pub(super) fn set_weight_expr(self: &mut Self, expr: WeightExpr) {
+        self.weight_expr = Some(expr);
+        self.weight_by_user = false;
+    }
+
+    /// The weight of the text in an external context.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context.
+    ///
+    /// # Return
+    /// The weight in the external context if the weight refers to it, or the weight of the text.
+    ///
+    /// # Note
+    /// See [`WeightExpr::eval()`].
+    fn weight_in_context(self: &Self, ext_context: &dyn Context) -> f64 {
+        match &self.weight_expr {
+            Some(expr) => expr.eval(ext_context),
+            None => self.weight,
+        }
+    }
+
     /// Bind the instance on a syntax.
     ///
     /// # Parameter
@@ -169,53 +491,33 @@ impl<S: Substitutor> Text<S> {
         epoch: usize,
         err_msg: &mut Vec<String>,
     ) {
-        let mut tmp_weight: f64 = 1.0;
-        self.comb = 1;
         for p in self.parts.iter_mut() {
-            match p {
-                Part::AnonymousRule(r) => {
-                    r.borrow_mut().bind_syntax(assignments, epoch, err_msg);
-                }
-                Part::Expansion(s, _) => {
-                    if let Some(r) = assignments.get(s) {
-                        match r.try_borrow_mut() {
-                            Ok(mut rule) => {
-                                rule.bind_syntax(assignments, epoch, err_msg);
-                                *p = Part::Expansion(s.clone(), Some(Rc::clone(r)));
-                            }
-                            Err(_) => {
-                                let mut msg = "Recursive expansion of \"".to_string();
-                                msg += &s;
-                                msg += "\" is detected.";
-                                err_msg.push(msg);
-                            }
-                        }
-                    }
-                }
-                _ => (),
-            };
-
-            let mut tmp_comb = self.comb;
-            let mut update_wc = |r: &Rc<RefCell<ProductionRule<S>>>| {
-                tmp_comb *= r.borrow().combination_number();
-                tmp_weight *= r.borrow().weight();
-            };
-            match p {
-                Part::AnonymousRule(r) => update_wc(r),
-                Part::Expansion(_, r_opt) => {
-                    if let Some(r) = r_opt {
-                        update_wc(r);
-                    }
-                }
-                _ => (),
-            };
-            self.comb = tmp_comb;
+            p.bind_syntax(assignments, epoch, err_msg);
         }
+        let (comb, weight) = self.propagated_values();
+        self.comb = comb;
         if !self.weight_by_user {
-            self.weight = tmp_weight;
+            self.weight = weight;
         }
     }
 
+    /// The number of the combination and the weight propagated from the production rules that the parts refer.
+    ///
+    /// # Return
+    /// The number of the combination, or [`None`] if it overflows, and the weight.
+    fn propagated_values(self: &Self) -> (Option<u128>, f64) {
+        let mut comb: Option<u128> = Some(1);
+        let mut weight: f64 = 1.0;
+        for p in self.parts.iter() {
+            if let Some(r) = p.rule() {
+                let r = r.read().unwrap();
+                comb = comb.and_then(|c| c.checked_mul(r.combination_number()?));
+                weight *= r.weight();
+            }
+        }
+        return (comb, weight);
+    }
+
     /// Fix the reference to the local nonterminal.
     ///
     /// # Parameter
@@ -226,18 +528,14 @@ impl<S: Substitutor> Text<S> {
     /// An error is caused if the local nonterminal that is referred by a production rule doesn't exists.
     fn fix_local_nonterminal(self: &mut Self, syntax: &Syntax<S>, err_msg: &mut Vec<String>) {
         for p in self.parts.iter_mut() {
-            if let Part::Expansion(s, _) = p {
-                if Syntax::<S>::is_local_nonterminal(s) {
-                    if let Some(r) = syntax.production_rule(s) {
-                        *p = Part::AnonymousRule(r);
-                    } else {
-                        let mut msg = "The local nonterminal \"".to_string();
-                        msg += &s;
-                        msg += "\" is not found.";
-                        err_msg.push(msg);
-                    }
-                }
-            }
+            p.fix_local_nonterminal(syntax, err_msg);
+        }
+    }
+
+    /// Collect the names of the functions called by the text.
+    fn function_names(self: &Self, names: &mut BTreeSet<String>) {
+        for p in self.parts.iter() {
+            p.function_names(names);
         }
     }
 
@@ -255,10 +553,66 @@ impl<S: Substitutor> Text<S> {
     /// The number of the possible texts generated by the instance.
     ///
     /// # Return
-    /// The the number of the possible texts generated by the instance.
-    fn combination_number(self: &Self) -> usize {
+    /// The the number of the possible texts generated by the instance, or [`None`] if it overflows.
+    fn combination_number(self: &Self) -> Option<u128> {
         self.comb
     }
+
+    /// The production rules that select the options, in the order of generation.
+    fn rules(self: &Self) -> impl Iterator<Item = &Arc<RwLock<ProductionRule<S>>>> {
+        self.parts.iter().filter_map(|p| p.rule())
+    }
+
+    /// Push the indices of the options to generate the k-th text.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the text, which must be less than [`combination_number()`].
+    /// - `indices`: The indices are pushed in the order of generation.
+    ///
+    /// # Note
+    /// - The index is the mixed radix number whose digits are the indices of the texts generated by the production rules, and the first production rule is the most significant.
+    /// - The number of the combination that overflows is regarded as infinity.
+    ///
+    /// [`combination_number()`]: #method.combination_number
+    fn unrank(self: &Self, k: u128, indices: &mut Vec<usize>) {
+        let rules: Vec<_> = self.rules().collect();
+        let mut digits = vec![0; rules.len()];
+        let mut k = k;
+        for (r, d) in rules.iter().zip(digits.iter_mut()).rev() {
+            match r.read().unwrap().combination_number() {
+                Some(c) => {
+                    *d = k % c;
+                    k /= c;
+                }
+                None => {
+                    *d = k;
+                    k = 0;
+                }
+            }
+        }
+        for (r, d) in rules.iter().zip(digits.iter()) {
+            r.read().unwrap().unrank(*d, indices);
+        }
+    }
+
+    /// The index of the text generated by the indices of the options.
+    ///
+    /// # Parameter
+    /// - `indices`: The indices of the options in the order of generation.
+    /// - `pos`: The position of the next index in `indices`, which is advanced.
+    ///
+    /// # Return
+    /// The index, or [`None`] if `indices` doesn't match the instance or the index overflows.
+    fn rank(self: &Self, indices: &[usize], pos: &mut usize) -> Option<u128> {
+        let mut k: u128 = 0;
+        for r in self.rules() {
+            let r = r.read().unwrap();
+            k = k
+                .checked_mul(r.combination_number()?)?
+                .checked_add(r.rank(indices, pos)?)?;
+        }
+        return Some(k);
+    }
 }
 
 /// The data structure representing the set of the text options.
@@ -281,17 +635,13 @@ impl<S: Substitutor> Clone for TextOptions<S> {
     }
 }
 impl<S: Substitutor> TextGenerator for TextOptions<S> {
-    fn generate<R: RandomNumberGenerator>(
-        self: &Self,
-        ext_context: &ExtContext,
-        rng: &mut R,
-    ) -> String {
+    fn generate<Sel: Selector>(self: &Self, env: &Environment<'_>, selector: &mut Sel) -> String {
         select_and_generate_text(
             &self.texts,
-            &self.weights,
+            &self.weights_in_context(env.ext_context()),
             self.equalized_chance,
-            &ext_context,
-            rng,
+            env,
+            selector,
         )
     }
 }
@@ -323,13 +673,73 @@ impl<S: Substitutor> TextOptions<S> {
     /// The number of the possible texts generated by the instance.
     ///
     /// # Return
-    /// The the number of the possible texts generated by the instance.
-    fn combination_number(self: &Self) -> usize {
-        let mut sum: usize = 0;
+    /// The the number of the possible texts generated by the instance, or [`None`] if it overflows.
+    fn combination_number(self: &Self) -> Option<u128> {
+        let mut sum: u128 = 0;
         for t in self.texts.iter() {
-            sum += t.combination_number();
+            sum = sum.checked_add(t.combination_number()?)?;
+        }
+        return Some(sum);
+    }
+
+    /// Push the indices of the options to generate the k-th text.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the text, which must be less than [`combination_number()`].
+    /// - `indices`: The indices are pushed in the order of generation.
+    ///
+    /// [`combination_number()`]: #method.combination_number
+    fn unrank(self: &Self, k: u128, indices: &mut Vec<usize>) {
+        let mut k = k;
+        for (i, t) in self.texts.iter().enumerate() {
+            match t.combination_number() {
+                Some(c) if k >= c => k -= c,
+                _ => {
+                    indices.push(i);
+                    t.unrank(k, indices);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// The index of the text generated by the indices of the options.
+    ///
+    /// # Parameter
+    /// - `indices`: The indices of the options in the order of generation.
+    /// - `pos`: The position of the next index in `indices`, which is advanced.
+    ///
+    /// # Return
+    /// The index, or [`None`] if `indices` doesn't match the instance or the index overflows.
+    fn rank(self: &Self, indices: &[usize], pos: &mut usize) -> Option<u128> {
+        let i = *indices.get(*pos)?;
+        let t = self.texts.get(i)?;
+        *pos += 1;
+        let mut offset: u128 = 0;
+        for x in self.texts[..i].iter() {
+            offset = offset.checked_add(x.combination_number()?)?;
         }
-        return sum;
+        return offset.checked_add(t.rank(indices, pos)?);
+    }
+
+    /// The weights to select the texts in an external context.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context.
+    ///
+    /// # Return
+    /// `weights[i]` is the sum of `weights[i-1]` and the weight to select `texts[i]` in the external context.
+    fn weights_in_context(self: &Self, ext_context: &dyn Context) -> Cow<'_, [f64]> {
+        if self.texts.iter().all(|t| t.weight_expr.is_none()) {
+            return Cow::Borrowed(&self.weights);
+        }
+        let mut sum: f64 = 0.0;
+        let mut weights = Vec::with_capacity(self.texts.len());
+        for t in self.texts.iter() {
+            sum += t.weight_in_context(ext_context);
+            weights.push(sum);
+        }
+        return Cow::Owned(weights);
     }
 
     /// Add a text.
@@ -385,6 +795,39 @@ impl<S: Substitutor> TextOptions<S> {
             t.fix_local_nonterminal(syntax, err_msg);
         }
     }
+
+    /// Collect the names of the functions called by the texts.
+    fn function_names(self: &Self, names: &mut BTreeSet<String>) {
+        for t in self.texts.iter() {
+            t.function_names(names);
+        }
+    }
+}
+
+/// The parameters of a gsub, which are kept to restore the substitutor.
+#[derive(Clone, Debug)]
+pub(super) struct GsubParam {
+    /// The pattern.
+    pattern: String,
+    /// The replacement.
+    repl: String,
+    /// The limit of the substitution. 0 means no limit.
+    limit: usize,
+}
+impl GsubParam {
+    /// Create a new [`GsubParam`].
+    ///
+    /// # Parameter
+    /// - `pattern`: The pattern.
+    /// - `repl`: The replacement.
+    /// - `limit`: The limit of the substitution.
+    pub(super) fn new(pattern: String, repl: String, limit: usize) -> Self {
+        Self {
+            pattern,
+            repl,
+            limit,
+        }
+    }
 }
 
 /// The data structure representing the production rule.
@@ -393,7 +836,9 @@ pub(super) struct ProductionRule<S: Substitutor> {
     /// The options in the production rule.
     options: TextOptions<S>,
     /// The gsubs in the production rule.
-    gsubs: Rc<S>,
+    gsubs: Arc<S>,
+    /// The parameters of the gsubs.
+    gsub_params: Arc<Vec<GsubParam>>,
     /// The binding epoch.
     binding_epoch: usize,
     /// The weight specified by the phrase syntax.
@@ -403,29 +848,42 @@ impl<S: Substitutor> Clone for ProductionRule<S> {
     fn clone(self: &Self) -> Self {
         Self {
             options: self.options.clone(),
-            gsubs: Rc::clone(&self.gsubs),
+            gsubs: Arc::clone(&self.gsubs),
+            gsub_params: Arc::clone(&self.gsub_params),
             binding_epoch: 0,
-            weight: None,
+            weight: self.weight,
         }
     }
 }
-impl<S: Substitutor> TextGenerator for ProductionRule<S> {
-    fn generate<R: RandomNumberGenerator>(
+impl<S: Substitutor> ProductionRule<S> {
+    /// Generate a text.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal assigned to this rule, or [`None`] if it's an anonymous rule.
+    /// - `env`: The environment that has the external context and the locale.
+    /// - `selector`: The selector that selects the options.
+    ///
+    /// # Return
+    /// A text.
+    pub(crate) fn generate_rule<Sel: Selector>(
         self: &Self,
-        ext_context: &ExtContext,
-        rng: &mut R,
+        nonterminal: Option<&str>,
+        env: &Environment<'_>,
+        selector: &mut Sel,
     ) -> String {
-        self.gsubs
-            .gsub(&self.options.generate(ext_context, rng))
-            .to_string()
+        selector.enter_rule(nonterminal);
+        let pre_gsub = self.options.generate(env, selector);
+        let post_gsub = self.gsubs.gsub(&pre_gsub).to_string();
+        selector.leave_rule(&pre_gsub, &post_gsub);
+        return post_gsub;
     }
-}
-impl<S: Substitutor> ProductionRule<S> {
+
     /// Create an empty [`ProductionRule`].
-    pub(super) fn new(options: TextOptions<S>, gsubs: S) -> Self {
+    pub(super) fn new(options: TextOptions<S>, gsubs: S, gsub_params: Vec<GsubParam>) -> Self {
         Self {
             options,
-            gsubs: Rc::new(gsubs),
+            gsubs: Arc::new(gsubs),
+            gsub_params: Arc::new(gsub_params),
             binding_epoch: 0,
             weight: None,
         }
@@ -448,11 +906,34 @@ impl<S: Substitutor> ProductionRule<S> {
     /// The number of the possible texts generated by the instance.
     ///
     /// # Return
-    /// The the number of the possible texts generated by the instance.
-    fn combination_number(self: &Self) -> usize {
+    /// The the number of the possible texts generated by the instance, or [`None`] if it overflows.
+    fn combination_number(self: &Self) -> Option<u128> {
         self.options.combination_number()
     }
 
+    /// Push the indices of the options to generate the k-th text.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the text, which must be less than [`combination_number()`].
+    /// - `indices`: The indices are pushed in the order of generation.
+    ///
+    /// [`combination_number()`]: #method.combination_number
+    fn unrank(self: &Self, k: u128, indices: &mut Vec<usize>) {
+        self.options.unrank(k, indices);
+    }
+
+    /// The index of the text generated by the indices of the options.
+    ///
+    /// # Parameter
+    /// - `indices`: The indices of the options in the order of generation.
+    /// - `pos`: The position of the next index in `indices`, which is advanced.
+    ///
+    /// # Return
+    /// The index, or [`None`] if `indices` doesn't match the instance or the index overflows.
+    fn rank(self: &Self, indices: &[usize], pos: &mut usize) -> Option<u128> {
+        self.options.rank(indices, pos)
+    }
+
     /// Set the weight of the production rule.
     ///
     /// # Parameter
@@ -484,7 +965,7 @@ impl<S: Substitutor> ProductionRule<S> {
         epoch: usize,
         err_msg: &mut Vec<String>,
     ) {
-        // No need to check the recursion because RefCell detects it.
+        // No need to check the recursion because RwLock::try_write() detects it.
         if self.binding_epoch == epoch {
             // Already bound
             return;
@@ -506,6 +987,11 @@ impl<S: Substitutor> ProductionRule<S> {
         self.options.fix_local_nonterminal(syntax, err_msg);
     }
 
+    /// Collect the names of the functions called by the production rule, except the ones called by the nonterminals that it refers.
+    fn function_names(self: &Self, names: &mut BTreeSet<String>) {
+        self.options.function_names(names);
+    }
+
     /// Reset the binding epoch.
     fn reset_binding_epoch(self: &mut Self) {
         self.binding_epoch = 0;
@@ -557,6 +1043,15 @@ impl<S: Substitutor> ProductionRule<S> {
 /// # }
 /// ```
 ///
+/// The phrase syntax is [`Send`] and [`Sync`] with the default substitutor, so it can be stored in a `static`.
+/// ```rust
+/// static SYNTAX: std::sync::OnceLock<tphrase::Syntax> = std::sync::OnceLock::new();
+/// let syntax = SYNTAX.get_or_init(|| "main = Hello, World!".parse().unwrap());
+/// let mut ph: tphrase::Generator = tphrase::Generator::new();
+/// let _ = ph.add(syntax.clone());
+/// assert_eq!(ph.generate(), "Hello, World!");
+/// ```
+///
 /// [`Err`] in the result holds some human readable error messages.
 /// ```rust
 /// let syntax_result: Result<tphrase::Syntax, _> = r#"
@@ -589,7 +1084,7 @@ pub struct Syntax<
     /// The assignments in the syntax.
     assignments: Assignments<S>,
     /// The reference to the start condition.
-    start_rule: Option<Rc<RefCell<ProductionRule<S>>>>,
+    start_rule: Option<Arc<RwLock<ProductionRule<S>>>>,
     /// The name of the start condition.
     start_condition: String,
     /// The binding epoch.
@@ -605,7 +1100,7 @@ impl<S: Substitutor> Clone for Syntax<S> {
         };
         for (k, v) in self.assignments.iter() {
             a.assignments
-                .insert(k.clone(), Rc::new(RefCell::new(v.borrow().clone())));
+                .insert(k.clone(), Arc::new(RwLock::new(v.read().unwrap().clone())));
         }
         if self.start_rule.is_some() {
             let _ = a.bind_syntax(&self.start_condition); // It should not generate any errors.
@@ -619,17 +1114,16 @@ impl<S: Substitutor> Default for Syntax<S> {
     }
 }
 impl<S: Substitutor> TextGenerator for Syntax<S> {
-    fn generate<R: RandomNumberGenerator>(
-        self: &Self,
-        ext_context: &ExtContext,
-        rng: &mut R,
-    ) -> String {
+    fn generate<Sel: Selector>(self: &Self, env: &Environment<'_>, selector: &mut Sel) -> String {
         if self.is_generatable() {
-            self.start_rule
+            let s = self
+                .start_rule
                 .as_ref()
                 .unwrap()
-                .borrow()
-                .generate(ext_context, rng)
+                .read()
+                .unwrap()
+                .generate_rule(Some(&self.start_condition), env, selector);
+            Filter::apply_all(env.post_filters(), s)
         } else {
             "nil".to_string()
         }
@@ -655,6 +1149,175 @@ impl<S: Substitutor> Syntax<S> {
         }
     }
 
+    /// Generate a phrase using an external context and a random number generator.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `rng`: The random number generator.
+    ///
+    /// # Return
+    /// A phrase.
+    ///
+    /// # Note
+    /// - The instance must be bound by [`bind_syntax()`], or it creates "nil".
+    /// - `self` isn't mut, so an instance can be shared with the threads that have their own random number generators.
+    /// - The locale is [`Locale::default()`], and no functions are registered, so a function call "{@name args}" is expanded into "@name args".
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut syntax: tphrase::Syntax = "main = Hello, {WORLD}!".parse()?;
+    /// syntax.bind_syntax("main")?;
+    /// let mut rng: tphrase::FastrandRng = tphrase::RandomNumberGenerator::new();
+    /// let context = tphrase::ExtContext::from([
+    ///     ("WORLD".to_string(), "World".to_string()),
+    /// ]);
+    /// assert_eq!(syntax.generate_with_rng(&context, &mut rng), "Hello, World!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`bind_syntax()`]: #method.bind_syntax
+    /// [`Locale::default()`]: struct.Locale.html#impl-Default-for-Locale
+    pub fn generate_with_rng<R: RandomNumberGenerator>(
+        self: &Self,
+        ext_context: &dyn Context,
+        rng: &mut R,
+    ) -> String {
+        self.generate(
+            &Environment::new(
+                ext_context,
+                &Locale::default(),
+                &[],
+                &FunctionRegistry::new(),
+            ),
+            &mut RngSelector::new(rng),
+        )
+    }
+
+    /// Iterate over all the phrases that the instance can generate.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    ///
+    /// # Return
+    /// The lazy iterator over the phrases in a deterministic order.
+    ///
+    /// # Note
+    /// - The phrases are generated from the start condition that the instance is bound on, so the instance must be bound by [`bind_syntax()`], or the iterator is empty.
+    /// - Some phrases may be duplicated if the syntax is ambiguous. Use [`Phrases::distinct()`] to skip them.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = {GREETING}, {WORLD}!
+    ///     GREETING = Hello | Hi
+    ///     greeting = {GREETING}
+    /// "#.parse()?;
+    /// let context = tphrase::ExtContext::from([
+    ///     ("WORLD".to_string(), "World".to_string()),
+    /// ]);
+    /// syntax.bind_syntax("main")?;
+    /// let all: Vec<_> = syntax.enumerate(&context).collect();
+    /// assert_eq!(all, ["Hello, World!", "Hi, World!"]);
+    /// syntax.bind_syntax("greeting")?;
+    /// let all: Vec<_> = syntax.enumerate(&context).collect();
+    /// assert_eq!(all, ["Hello", "Hi"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`bind_syntax()`]: #method.bind_syntax
+    /// [`Phrases::distinct()`]: ../struct.Phrases.html#method.distinct
+    pub fn enumerate<'a>(self: &'a Self, ext_context: &'a dyn Context) -> Phrases<'a, S> {
+        Phrases::new(
+            std::slice::from_ref(self),
+            ext_context,
+            Locale::default(),
+            Vec::new(),
+            FunctionRegistry::new(),
+        )
+    }
+
+    /// The range of the length of the phrases generated by the instance.
+    ///
+    /// # Parameter
+    /// - `unit`: The unit of the length.
+    /// - `ext_bounds`: The bounds of the length of the values in the external context. The nonterminal that isn't in the syntax nor in the bounds is measured as its name, as the generated phrase has it.
+    ///
+    /// # Return
+    /// The range, or [`None`] if the instance isn't bound by [`bind_syntax()`].
+    ///
+    /// # Note
+    /// - The length of a text is estimated as the sum of the length of its parts, and the gsubs are assumed not to change the length. [`LengthRange::is_approximate()`] is `true` if the estimation may be wrong.
+    /// - The options that are never selected because their weight is 0 are ignored.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = Hello, {NAME}! | Hi!
+    /// "#.parse()?;
+    /// let bounds = tphrase::ExtLengthBounds::from([
+    ///     ("NAME".to_string(), tphrase::LengthRange::new(1, 20)),
+    /// ]);
+    /// syntax.bind_syntax("main")?;
+    /// let range = syntax.length_range(tphrase::LengthUnit::Chars, &bounds).unwrap();
+    /// assert_eq!((range.min(), range.max()), (3, 28));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`bind_syntax()`]: #method.bind_syntax
+    /// [`LengthRange::is_approximate()`]: ../struct.LengthRange.html#method.is_approximate
+    pub fn length_range(
+        self: &Self,
+        unit: LengthUnit,
+        ext_bounds: &ExtLengthBounds,
+    ) -> Option<LengthRange> {
+        RangeAnalyzer::new(unit, ext_bounds).syntax_range(self)
+    }
+
+    /// The ranges of the length of the texts generated by the nonterminals in the instance.
+    ///
+    /// # Parameter
+    /// - `unit`: The unit of the length.
+    /// - `ext_bounds`: The bounds of the length of the values in the external context.
+    ///
+    /// # Return
+    /// The ranges of the nonterminals referred directly or indirectly by the start condition, which is bound by [`bind_syntax()`].
+    ///
+    /// # Note
+    /// See [`length_range()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = {size} apple
+    ///     size = small | big | enormous
+    ///     unused = not analyzed
+    /// "#.parse()?;
+    /// syntax.bind_syntax("main")?;
+    /// let ranges = syntax.length_ranges(tphrase::LengthUnit::Chars, &tphrase::ExtLengthBounds::new());
+    /// assert_eq!(ranges.len(), 2);
+    /// assert_eq!(ranges["main"].to_string(), "9..=14");
+    /// assert_eq!(ranges["size"].to_string(), "3..=8");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`bind_syntax()`]: #method.bind_syntax
+    /// [`length_range()`]: #method.length_range
+    pub fn length_ranges(
+        self: &Self,
+        unit: LengthUnit,
+        ext_bounds: &ExtLengthBounds,
+    ) -> BTreeMap<String, LengthRange> {
+        RangeAnalyzer::new(unit, ext_bounds).nonterminal_ranges(self)
+    }
+
     /// The sum of the weight of the texts.
     ///
     /// # Return
@@ -666,7 +1329,7 @@ impl<S: Substitutor> Syntax<S> {
     /// [`is_generatable()`]: #method.is_generatable
     pub(crate) fn weight(self: &Self) -> f64 {
         if self.is_generatable() {
-            self.start_rule.as_ref().unwrap().borrow().weight()
+            self.start_rule.as_ref().unwrap().read().unwrap().weight()
         } else {
             0.0
         }
@@ -675,24 +1338,50 @@ impl<S: Substitutor> Syntax<S> {
     /// The number of the possible texts generated by the instance.
     ///
     /// # Return
-    /// The the number of the possible texts generated by the instance.
+    /// The the number of the possible texts generated by the instance, or [`None`] if it overflows.
     ///
     /// # Note
-    /// The return value is meaningless if [`is_generatable()`] is `false`. (The return type isn't [`Option`] because the function isn't public for the crate user.)
+    /// The return value is 0 if [`is_generatable()`] is `false`.
     ///
     /// [`is_generatable()`]: #method.is_generatable
-    pub(crate) fn combination_number(self: &Self) -> usize {
+    pub(crate) fn combination_number(self: &Self) -> Option<u128> {
         if self.is_generatable() {
             self.start_rule
                 .as_ref()
                 .unwrap()
-                .borrow()
+                .read()
+                .unwrap()
                 .combination_number()
         } else {
-            0
+            Some(0)
         }
     }
 
+    /// Push the indices of the options to generate the k-th phrase.
+    ///
+    /// # Parameter
+    /// - `k`: The index of the phrase, which must be less than [`combination_number()`].
+    /// - `indices`: The indices are pushed in the order of generation.
+    ///
+    /// [`combination_number()`]: #method.combination_number
+    pub(crate) fn unrank(self: &Self, k: u128, indices: &mut Vec<usize>) {
+        if let Some(r) = &self.start_rule {
+            r.read().unwrap().unrank(k, indices);
+        }
+    }
+
+    /// The index of the phrase generated by the indices of the options.
+    ///
+    /// # Parameter
+    /// - `indices`: The indices of the options in the order of generation.
+    /// - `pos`: The position of the next index in `indices`, which is advanced.
+    ///
+    /// # Return
+    /// The index, or [`None`] if `indices` doesn't match the instance or the index overflows.
+    pub(crate) fn rank(self: &Self, indices: &[usize], pos: &mut usize) -> Option<u128> {
+        self.start_rule.as_ref()?.read().unwrap().rank(indices, pos)
+    }
+
     /// Is is a local nonterminal?
     ///
     /// # Parameter
@@ -711,7 +1400,7 @@ impl<S: Substitutor> Syntax<S> {
     ///
     /// # Return
     /// The production rule.
-    fn production_rule(self: &Self, nonterminal: &str) -> Option<Rc<RefCell<ProductionRule<S>>>> {
+    fn production_rule(self: &Self, nonterminal: &str) -> Option<Arc<RwLock<ProductionRule<S>>>> {
         self.assignments.get(nonterminal).cloned()
     }
 
@@ -726,6 +1415,29 @@ impl<S: Substitutor> Syntax<S> {
         self.start_rule.is_some()
     }
 
+    /// Is the instance bound on the start condition?
+    ///
+    /// # Parameter
+    /// - `start_condition`: The nonterminal where is the start condition.
+    ///
+    /// # Return
+    /// The instance is bound on `start_condition`.
+    pub(crate) fn is_bound(self: &Self, start_condition: &str) -> bool {
+        self.is_generatable() && self.start_condition == start_condition
+    }
+
+    /// The names of the functions called in the syntax.
+    ///
+    /// # Return
+    /// The names in all the production rules, including the ones not referred by the start condition.
+    pub(crate) fn function_names(self: &Self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for r in self.assignments.values() {
+            r.read().unwrap().function_names(&mut names);
+        }
+        return names;
+    }
+
     /// Add a pair of a nonterminal and a production rule.
     ///
     /// # Return
@@ -746,7 +1458,7 @@ impl<S: Substitutor> Syntax<S> {
         let it = self.assignments.get(nonterminal);
         if it.is_none() {
             self.assignments
-                .insert(nonterminal.to_string(), Rc::new(RefCell::new(rule)));
+                .insert(nonterminal.to_string(), Arc::new(RwLock::new(rule)));
             Ok(())
         } else {
             let mut err = "The nonterminal \"".to_string();
@@ -766,12 +1478,15 @@ impl<S: Substitutor> Syntax<S> {
     ///
     /// # Note
     /// - If syntax has the nonterminal that this already contains, then: (1) the nonterminal in syntax OVERWRITES it, (2) [`Err`] is returned.
-    /// - It has a side effect to make the instance the unbound state (although the crate user has no occasion to use the bound [`Syntax`] directly.)
+    /// - It has a side effect to make the instance and the assignments in `syntax` the unbound state, so [`bind_syntax()`] is needed before generating a phrase.
+    ///
+    /// [`bind_syntax()`]: #method.bind_syntax
     pub fn add(self: &mut Self, mut syntax: Syntax<S>) -> Result<(), CompileError> {
         self.disable_generating();
         let mut err_msg = Vec::new();
         for (k, v) in syntax.assignments.drain() {
-            if self.assignments.get(&k).is_some() {
+            v.write().unwrap().reset_binding_epoch();
+            if self.assignments.contains_key(&k) {
                 let mut err = "The nonterminal \"".to_string();
                 err += &k;
                 err += "\" is already defined. Overwrited by newer.";
@@ -806,9 +1521,14 @@ impl<S: Substitutor> Syntax<S> {
     /// - Only the nonterminals that are directly or indirectly referred by the start condition are tried binding.
     /// - An error is caused if the recursive reference to a nonterminal exists.
     /// - An error is cause if the nonterminal start_condition doesn't exist.
-    pub(crate) fn bind_syntax(self: &mut Self, start_condition: &str) -> Result<(), CompileError> {
+    /// - [`Generator::add()`] binds the instance, so you don't need to call it unless you need the bound instance itself, such as [`generate_with_rng()`] and [`to_bytes()`].
+    ///
+    /// [`Generator::add()`]: ../../struct.Generator.html#method.add
+    /// [`generate_with_rng()`]: #method.generate_with_rng
+    /// [`to_bytes()`]: #method.to_bytes
+    pub fn bind_syntax(self: &mut Self, start_condition: &str) -> Result<(), CompileError> {
         self.disable_generating();
-        if self.assignments.get(start_condition).is_none() {
+        if !self.assignments.contains_key(start_condition) {
             let mut err = "The nonterminal \"".to_string();
             err += start_condition;
             err += "\" doesn't exist.";
@@ -817,19 +1537,13 @@ impl<S: Substitutor> Syntax<S> {
             return Err(compile_error);
         }
 
-        self.binding_epoch += 1;
-        // It's generally 0 or 1 because the functions of struct Syntax and Generator don't call bind_syntax() to the syntax that already bound. (The three variations (initial, current, not current) are enough to distinguish the binding epoch unless start_condition is changed.)
-        if self.binding_epoch == std::usize::MAX {
-            for (_, v) in self.assignments.iter() {
-                v.borrow_mut().reset_binding_epoch();
-            }
-            self.binding_epoch = 1;
-        }
+        // The epoch is unique in the process, so the rules moved from another instance by add() are never regarded as bound.
+        self.binding_epoch = next_binding_epoch();
 
         let mut err_msg = Vec::new();
-        let start_rule = Rc::clone(self.assignments.get(start_condition).as_ref().unwrap());
-        start_rule.borrow_mut().bind_syntax(
-            &mut self.assignments,
+        let start_rule = Arc::clone(self.assignments.get(start_condition).as_ref().unwrap());
+        start_rule.write().unwrap().bind_syntax(
+            &self.assignments,
             self.binding_epoch,
             &mut err_msg,
         );
@@ -844,6 +1558,193 @@ impl<S: Substitutor> Syntax<S> {
         }
     }
 
+    /// Override the weight of a nonterminal without parsing the syntax again.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal.
+    /// - `weight`: The weight of the nonterminal, which is propagated to the texts that refer to it. The default value, calculated from the options, is used if `weight` is [`None`].
+    ///
+    /// # Return
+    /// [`Ok`] if the weight is overridden. [`Err`] if the nonterminal doesn't exist or the weight isn't a finite non-negative number.
+    ///
+    /// # Note
+    /// The instance is bound again if it's bound, so the weights propagated to the other nonterminals are updated.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = {A} | {B}
+    ///     A = a1 | a2
+    ///     B = b
+    /// "#.parse()?;
+    /// syntax.bind_syntax("main")?;
+    /// syntax.set_weight("A", Some(0.0))?;
+    /// let mut rng: tphrase::FastrandRng = tphrase::RandomNumberGenerator::new();
+    /// assert_eq!(syntax.generate_with_rng(&tphrase::ExtContext::new(), &mut rng), "b");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_weight(
+        self: &mut Self,
+        nonterminal: &str,
+        weight: Option<f64>,
+    ) -> Result<(), WeightError> {
+        check_weight(weight)?;
+        self.rule(nonterminal)?.write().unwrap().set_weight(weight);
+        self.rebind();
+        return Ok(());
+    }
+
+    /// Override the weight of an option of a nonterminal without parsing the syntax again.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal.
+    /// - `index`: The index of the option, starting from 0 in the order in the syntax.
+    /// - `weight`: The weight of the option. The default value, calculated from the parts, is used if `weight` is [`None`].
+    ///
+    /// # Return
+    /// [`Ok`] if the weight is overridden. [`Err`] if the nonterminal or the option doesn't exist, or the weight isn't a finite non-negative number.
+    ///
+    /// # Note
+    /// - The instance is bound again if it's bound, so the weights propagated to the other nonterminals are updated.
+    /// - See [`set_option_weight_by_label()`] to specify the option by its label.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = x | y | z
+    /// "#.parse()?;
+    /// syntax.bind_syntax("main")?;
+    /// syntax.set_option_weight("main", 0, Some(0.0))?;
+    /// syntax.set_option_weight("main", 2, Some(0.0))?;
+    /// let mut rng: tphrase::FastrandRng = tphrase::RandomNumberGenerator::new();
+    /// assert_eq!(syntax.generate_with_rng(&tphrase::ExtContext::new(), &mut rng), "y");
+    /// assert!(syntax.set_option_weight("main", 3, Some(1.0)).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_option_weight_by_label()`]: #method.set_option_weight_by_label
+    pub fn set_option_weight(
+        self: &mut Self,
+        nonterminal: &str,
+        index: usize,
+        weight: Option<f64>,
+    ) -> Result<(), WeightError> {
+        check_weight(weight)?;
+        {
+            let rule = self.rule(nonterminal)?;
+            let mut rule = rule.write().unwrap();
+            match rule.options.texts.get_mut(index) {
+                Some(t) => t.set_weight(weight),
+                None => {
+                    let mut msg = "The option index ".to_string();
+                    msg += &index.to_string();
+                    msg += " of \"";
+                    msg += nonterminal;
+                    msg += "\" is out of range.";
+                    return Err(WeightError::new(msg));
+                }
+            }
+        }
+        self.rebind();
+        return Ok(());
+    }
+
+    /// Override the weight of the options of a nonterminal that have a label without parsing the syntax again.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal.
+    /// - `label`: The label of the options.
+    /// - `weight`: The weight of the options. The default value, calculated from the parts, is used if `weight` is [`None`].
+    ///
+    /// # Return
+    /// [`Ok`] if the weight is overridden. [`Err`] if the nonterminal or the option doesn't exist, or the weight isn't a finite non-negative number.
+    ///
+    /// # Note
+    /// - The label of an option is its source text in the syntax, without the quotes, the weight, and the trailing spaces, e.g. `{A} apple` for `"{A} apple" 2`. The comments and the escapes, e.g. `{(}`, are left as they are.
+    /// - All the options that have the label are overridden.
+    /// - See [`set_option_weight()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = {A} apple | "{A} banana" 2 | cherry
+    ///     A = red | green
+    /// "#.parse()?;
+    /// syntax.bind_syntax("main")?;
+    /// syntax.set_option_weight_by_label("main", "{A} apple", Some(0.0))?;
+    /// syntax.set_option_weight_by_label("main", "{A} banana", Some(0.0))?;
+    /// let mut rng: tphrase::FastrandRng = tphrase::RandomNumberGenerator::new();
+    /// assert_eq!(syntax.generate_with_rng(&tphrase::ExtContext::new(), &mut rng), "cherry");
+    /// assert!(syntax.set_option_weight_by_label("main", "apple", Some(1.0)).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_option_weight()`]: #method.set_option_weight
+    pub fn set_option_weight_by_label(
+        self: &mut Self,
+        nonterminal: &str,
+        label: &str,
+        weight: Option<f64>,
+    ) -> Result<(), WeightError> {
+        check_weight(weight)?;
+        {
+            let rule = self.rule(nonterminal)?;
+            let mut rule = rule.write().unwrap();
+            let mut found = false;
+            for t in rule.options.texts.iter_mut().filter(|t| t.label == label) {
+                t.set_weight(weight);
+                found = true;
+            }
+            if !found {
+                let mut msg = "The option labeled \"".to_string();
+                msg += label;
+                msg += "\" of \"";
+                msg += nonterminal;
+                msg += "\" doesn't exist.";
+                return Err(WeightError::new(msg));
+            }
+        }
+        self.rebind();
+        return Ok(());
+    }
+
+    /// The production rule of a nonterminal.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal.
+    ///
+    /// # Return
+    /// The production rule, or [`Err`] if the nonterminal doesn't exist.
+    fn rule(
+        self: &Self,
+        nonterminal: &str,
+    ) -> Result<&Arc<RwLock<ProductionRule<S>>>, WeightError> {
+        match self.assignments.get(nonterminal) {
+            Some(r) => Ok(r),
+            None => {
+                let mut msg = "The nonterminal \"".to_string();
+                msg += nonterminal;
+                msg += "\" doesn't exist.";
+                Err(WeightError::new(msg))
+            }
+        }
+    }
+
+    /// Bind the instance again if it's bound, to update the weights.
+    fn rebind(self: &mut Self) {
+        if self.start_rule.is_some() {
+            let start_condition = self.start_condition.clone();
+            // It never fails because the structure isn't changed since it's bound.
+            let _ = self.bind_syntax(&start_condition);
+        }
+    }
+
     /// Fix the reference to the local nonterminal.
     ///
     /// # Parameter
@@ -852,14 +1753,33 @@ impl<S: Substitutor> Syntax<S> {
     /// # Errors
     /// An error is caused if the local nonterminal that is referred by a production rule doesn't exists.
     pub(super) fn fix_local_nonterminal(self: &mut Self, err_msg: &mut Vec<String>) {
-        let mut rc_v: Vec<Rc<RefCell<ProductionRule<S>>>> = Vec::new();
+        let mut rc_v: Vec<Arc<RwLock<ProductionRule<S>>>> = Vec::new();
         for (_, v) in self.assignments.iter() {
-            rc_v.push(Rc::clone(v));
+            rc_v.push(Arc::clone(v));
         }
         for v in rc_v.iter() {
-            v.borrow_mut().fix_local_nonterminal(self, err_msg);
+            v.write().unwrap().fix_local_nonterminal(self, err_msg);
         }
         self.assignments
             .retain(|k, _| !Self::is_local_nonterminal(k));
     }
 }
+
+/// Check a weight given by the user.
+///
+/// # Parameter
+/// - `weight`: The weight.
+///
+/// # Return
+/// [`Err`] if the weight isn't a finite non-negative number.
+fn check_weight(weight: Option<f64>) -> Result<(), WeightError> {
+    match weight {
+        Some(w) if !(w.is_finite() && w >= 0.0) => {
+            let mut msg = "The weight ".to_string();
+            msg += &w.to_string();
+            msg += " is not a finite non-negative number.";
+            Err(WeightError::new(msg))
+        }
+        _ => Ok(()),
+    }
+}
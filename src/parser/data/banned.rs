@@ -0,0 +1,227 @@
+//! Detection of the banned words in the phrases generated by a syntax
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use super::{Part, ProductionRule, Syntax, Text};
+use crate::selection_probability;
+use crate::Context;
+use crate::Substitutor;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+type RuleRef<S> = Arc<RwLock<ProductionRule<S>>>;
+
+/// A class of the texts that behave the same when they're concatenated with other texts.
+#[derive(Clone, Debug)]
+struct Fragment {
+    /// The first `margin` characters of the texts, or the whole text if it's short.
+    head: String,
+    /// The last `margin` characters of the texts, or the whole text if it's short.
+    tail: String,
+    /// Is the text not longer than `margin` characters?
+    short: bool,
+    /// The index of the banned word found in the texts.
+    hit: Option<usize>,
+    /// The indices of the options to generate one of the texts.
+    indices: Vec<usize>,
+}
+
+/// The finder of the banned words in the phrases generated by a syntax.
+///
+/// The texts are summarized into the fragments that have the first and the last characters of them, so the banned words across the parts are found without generating all the phrases.
+///
+/// # Note
+/// The gsubs, the format specs, and the filters are ignored, and the function calls are regarded as the empty texts.
+pub(crate) struct BannedFinder<'a> {
+    /// The banned words.
+    words: &'a [&'a str],
+    /// The external context.
+    ext_context: &'a dyn Context,
+    /// The number of the characters kept at the ends of a text, which is less than the length of the longest banned word.
+    margin: usize,
+    /// `fragments[r]` are the fragments of the production rule `r`.
+    fragments: HashMap<usize, Vec<Fragment>>,
+}
+impl<'a> BannedFinder<'a> {
+    /// Create a finder.
+    ///
+    /// # Parameter
+    /// - `words`: The banned words. The empty words are ignored.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    pub(crate) fn new(words: &'a [&'a str], ext_context: &'a dyn Context) -> Self {
+        let margin = words
+            .iter()
+            .map(|w| w.chars().count())
+            .max()
+            .unwrap_or(0)
+            .saturating_sub(1);
+        Self {
+            words,
+            ext_context,
+            margin,
+            fragments: HashMap::new(),
+        }
+    }
+
+    /// Find the banned words in the phrases generated by a syntax.
+    ///
+    /// # Parameter
+    /// - `syntax`: The syntax.
+    ///
+    /// # Return
+    /// The pairs of the index of the banned word and the indices of the options to generate a phrase that has the word. The same word may be found in some derivations.
+    pub(crate) fn find<S: Substitutor>(
+        self: &mut Self,
+        syntax: &Syntax<S>,
+    ) -> Vec<(usize, Vec<usize>)> {
+        match &syntax.start_rule {
+            Some(r) => self
+                .rule_fragments(r)
+                .into_iter()
+                .filter_map(|f| f.hit.map(|h| (h, f.indices)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The fragments of a production rule.
+    fn rule_fragments<S: Substitutor>(self: &mut Self, rule: &RuleRef<S>) -> Vec<Fragment> {
+        let key = Arc::as_ptr(rule) as *const () as usize;
+        if let Some(f) = self.fragments.get(&key) {
+            return f.clone();
+        }
+        let r = rule.read().unwrap();
+        let options = &r.options;
+        let weights = options.weights_in_context(self.ext_context);
+        let mut fragments = Vec::new();
+        for (i, t) in options.texts.iter().enumerate() {
+            if selection_probability(&weights, options.equalized_chance, i) > 0.0 {
+                for mut f in self.text_fragments(t) {
+                    f.indices.insert(0, i);
+                    fragments.push(f);
+                }
+            }
+        }
+        let fragments = dedup(fragments);
+        self.fragments.insert(key, fragments.clone());
+        return fragments;
+    }
+
+    /// The fragments of a text.
+    fn text_fragments<S: Substitutor>(self: &mut Self, text: &Text<S>) -> Vec<Fragment> {
+        let mut fragments = vec![self.literal("")];
+        for p in text.parts.iter() {
+            let x = self.part_fragments(p);
+            let mut next = Vec::with_capacity(fragments.len() * x.len());
+            for a in fragments.iter() {
+                for b in x.iter() {
+                    next.push(self.concat(a, b));
+                }
+            }
+            fragments = dedup(next);
+        }
+        return fragments;
+    }
+
+    /// The fragments of a part.
+    fn part_fragments<S: Substitutor>(self: &mut Self, part: &Part<S>) -> Vec<Fragment> {
+        match part {
+            Part::Literal(s) => vec![self.literal(s)],
+            Part::Expansion(_, Some(r)) => self.rule_fragments(r),
+            Part::Expansion(s, None) => match self.ext_context.get(s) {
+                Some(x) => vec![self.literal(&x)],
+                None => vec![self.literal(s)],
+            },
+            Part::AnonymousRule(r) => self.rule_fragments(r),
+            Part::Formatted(p, _) | Part::Filtered(p, _) => self.part_fragments(p),
+            Part::Call(_, _) => vec![self.literal("")],
+        }
+    }
+
+    /// The fragment of a literal.
+    fn literal(self: &Self, s: &str) -> Fragment {
+        Fragment {
+            head: s.chars().take(self.margin).collect(),
+            tail: last_chars(s, self.margin).to_string(),
+            short: s.chars().count() <= self.margin,
+            hit: self.search(s),
+            indices: Vec::new(),
+        }
+    }
+
+    /// The fragment of the concatenated texts.
+    fn concat(self: &Self, a: &Fragment, b: &Fragment) -> Fragment {
+        let joint = a.tail.clone() + &b.head;
+        let hit = a.hit.or(b.hit).or_else(|| self.search(&joint));
+        let short = a.short && b.short && joint.chars().count() <= self.margin;
+        let head = if a.short {
+            (a.head.clone() + &b.head)
+                .chars()
+                .take(self.margin)
+                .collect()
+        } else {
+            a.head.clone()
+        };
+        let tail = if b.short {
+            last_chars(&(a.tail.clone() + &b.tail), self.margin).to_string()
+        } else {
+            b.tail.clone()
+        };
+        let mut indices = a.indices.clone();
+        indices.extend_from_slice(&b.indices);
+        Fragment {
+            head,
+            tail,
+            short,
+            hit,
+            indices,
+        }
+    }
+
+    /// Search the banned words in a string.
+    ///
+    /// # Return
+    /// The index of the first banned word found.
+    fn search(self: &Self, s: &str) -> Option<usize> {
+        self.words
+            .iter()
+            .position(|w| !w.is_empty() && s.contains(w))
+    }
+}
+
+/// The last characters of a string.
+fn last_chars(s: &str, n: usize) -> &str {
+    if n == 0 {
+        return "";
+    }
+    match s.char_indices().rev().nth(n - 1) {
+        Some((i, _)) => &s[i..],
+        None => s,
+    }
+}
+
+/// Remove the fragments that behave the same as a preceding one.
+fn dedup(fragments: Vec<Fragment>) -> Vec<Fragment> {
+    let mut seen = HashSet::new();
+    return fragments
+        .into_iter()
+        .filter(|f| seen.insert((f.head.clone(), f.tail.clone(), f.short, f.hit)))
+        .collect();
+}
@@ -0,0 +1,726 @@
+//! Binary encoding of Syntax
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use super::{
+    next_binding_epoch, Assignments, GsubParam, Part, ProductionRule, Syntax, Text, TextOptions,
+};
+use crate::binary_io::{ByteReader, ByteWriter};
+use crate::DecodeError;
+use crate::Filter;
+use crate::FormatSpec;
+use crate::Substitutor;
+use crate::WeightExpr;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// The magic number of the binary data of [`Syntax`].
+const MAGIC: &[u8; 4] = b"TPHS";
+/// The version of the binary format of [`Syntax`].
+const VERSION: u32 = 1;
+
+/// The tags of [`Part`] in the binary data.
+const TAG_LITERAL: u8 = 0;
+const TAG_EXPANSION: u8 = 1;
+const TAG_ANONYMOUS_RULE: u8 = 2;
+const TAG_FORMATTED: u8 = 3;
+const TAG_FILTERED: u8 = 4;
+const TAG_CALL: u8 = 5;
+
+/// The minimum size of a production rule in the binary data. (The flags of the weight and the equalized chance, the number of the gsubs, and the number of the texts.)
+const MIN_RULE_SIZE: usize = 1 + 1 + 4 + 4;
+
+/// The maximum depth of the production rules referring to each other, to avoid the stack overflow when they're bound or generate a text.
+const MAX_RULE_DEPTH: usize = 256;
+
+type RuleRef<S> = Arc<RwLock<ProductionRule<S>>>;
+
+/// The state to encode a [`Syntax`].
+struct Encoder<S: Substitutor> {
+    /// The string table.
+    strings: Vec<String>,
+    /// The index of the string in the string table.
+    string_index: HashMap<String, usize>,
+    /// The rule table.
+    rules: Vec<RuleRef<S>>,
+    /// The index of the rule in the rule table.
+    rule_index: HashMap<*const RwLock<ProductionRule<S>>, usize>,
+}
+impl<S: Substitutor> Encoder<S> {
+    /// Create an empty encoder.
+    fn new() -> Self {
+        Self {
+            strings: Vec::new(),
+            string_index: HashMap::new(),
+            rules: Vec::new(),
+            rule_index: HashMap::new(),
+        }
+    }
+
+    /// The index of a string. The string is added into the string table if it's new.
+    fn string(self: &mut Self, s: &str) -> usize {
+        if let Some(i) = self.string_index.get(s) {
+            return *i;
+        }
+        let i = self.strings.len();
+        self.strings.push(s.to_string());
+        self.string_index.insert(s.to_string(), i);
+        return i;
+    }
+
+    /// The index of a production rule. The rule is added into the rule table if it's new.
+    fn rule(self: &mut Self, r: &RuleRef<S>) -> usize {
+        let key = Arc::as_ptr(r);
+        if let Some(i) = self.rule_index.get(&key) {
+            return *i;
+        }
+        let i = self.rules.len();
+        self.rules.push(Arc::clone(r));
+        self.rule_index.insert(key, i);
+        return i;
+    }
+
+    /// Write a production rule.
+    fn write_rule(self: &mut Self, w: &mut ByteWriter, rule: &ProductionRule<S>) {
+        w.bool(rule.weight.is_some());
+        if let Some(weight) = rule.weight {
+            w.f64(weight);
+        }
+        w.bool(rule.options.equalized_chance);
+        w.len(rule.gsub_params.len());
+        for param in rule.gsub_params.iter() {
+            let pattern = self.string(&param.pattern);
+            w.len(pattern);
+            let repl = self.string(&param.repl);
+            w.len(repl);
+            w.u64(param.limit as u64);
+        }
+        w.len(rule.options.texts.len());
+        for (text, sum) in rule.options.texts.iter().zip(rule.options.weights.iter()) {
+            let label = self.string(&text.label);
+            w.len(label);
+            w.f64(*sum);
+            w.f64(text.weight);
+            w.bool(text.weight_by_user);
+            w.bool(text.weight_expr.is_some());
+            if let Some(expr) = &text.weight_expr {
+                let i = self.string(expr.source());
+                w.len(i);
+            }
+            w.bool(text.comb.is_some());
+            let comb = text.comb.unwrap_or(0);
+            w.u64(comb as u64);
+            w.u64((comb >> 64) as u64);
+            w.len(text.parts.len());
+            for part in text.parts.iter() {
+                self.write_part(w, part);
+            }
+        }
+    }
+
+    /// Write a part of a text.
+    fn write_part(self: &mut Self, w: &mut ByteWriter, part: &Part<S>) {
+        match part {
+            Part::Literal(s) => {
+                w.u8(TAG_LITERAL);
+                let i = self.string(s);
+                w.len(i);
+            }
+            Part::Expansion(s, r_opt) => {
+                w.u8(TAG_EXPANSION);
+                let i = self.string(s);
+                w.len(i);
+                w.bool(r_opt.is_some());
+                if let Some(r) = r_opt {
+                    let i = self.rule(r);
+                    w.len(i);
+                }
+            }
+            Part::AnonymousRule(r) => {
+                w.u8(TAG_ANONYMOUS_RULE);
+                let i = self.rule(r);
+                w.len(i);
+            }
+            Part::Formatted(p, spec) => {
+                w.u8(TAG_FORMATTED);
+                let i = self.string(spec.source());
+                w.len(i);
+                self.write_part(w, p);
+            }
+            Part::Filtered(p, filters) => {
+                w.u8(TAG_FILTERED);
+                w.len(filters.len());
+                for f in filters.iter() {
+                    let i = self.string(f.name());
+                    w.len(i);
+                }
+                self.write_part(w, p);
+            }
+            Part::Call(name, args) => {
+                w.u8(TAG_CALL);
+                let i = self.string(name);
+                w.len(i);
+                w.len(args.len());
+                for a in args.iter() {
+                    let i = self.string(a);
+                    w.len(i);
+                }
+            }
+        }
+    }
+}
+
+/// Check a weight specified by the user.
+///
+/// # Parameter
+/// - `weight`: The weight.
+///
+/// # Return
+/// The weight if it's a finite non-negative number.
+fn check_weight(weight: f64) -> Result<f64, DecodeError> {
+    if weight.is_finite() && weight >= 0.0 {
+        return Ok(weight);
+    }
+    return Err(DecodeError::new("A weight is broken.".to_string()));
+}
+
+/// Read a [`Text`].
+///
+/// # Parameter
+/// - `r`: The reader.
+/// - `strings`: The string table.
+/// - `rules`: The rule table.
+/// - `children`: The indices of the rules referred by the text are added.
+///
+/// # Return
+/// The text and the sum of the weights up to the text.
+///
+/// # Note
+/// The precomputed values are checked by [`check_bound_rule()`] if the text is bound, except the weight specified by the user.
+fn read_text<S: Substitutor>(
+    r: &mut ByteReader,
+    strings: &[String],
+    rules: &[RuleRef<S>],
+    children: &mut Vec<usize>,
+) -> Result<(Text<S>, f64), DecodeError> {
+    let mut text = Text::new();
+    text.label = strings[r.index(strings.len())?].clone();
+    let sum = r.f64()?;
+    let weight = r.f64()?;
+    text.weight_by_user = r.bool()?;
+    text.weight = if text.weight_by_user {
+        check_weight(weight)?
+    } else {
+        weight
+    };
+    if r.bool()? {
+        let source = &strings[r.index(strings.len())?];
+        let expr = WeightExpr::parse(source)
+            .ok_or_else(|| DecodeError::new("A context weight is broken.".to_string()))?;
+        text.weight_expr = Some(expr);
+    }
+    let has_comb = r.bool()?;
+    let comb = r.u64()? as u128 | ((r.u64()? as u128) << 64);
+    text.comb = if has_comb { Some(comb) } else { None };
+    let num_parts = r.len()?;
+    for _ in 0..num_parts {
+        let part = read_part(r, strings, rules, children)?;
+        text.parts.push(part);
+    }
+    return Ok((text, sum));
+}
+
+/// Read a part of a [`Text`].
+///
+/// # Parameter
+/// - `r`: The reader.
+/// - `strings`: The string table.
+/// - `rules`: The rule table.
+/// - `children`: The indices of the rules referred by the part are added.
+///
+/// # Return
+/// The part.
+fn read_part<S: Substitutor>(
+    r: &mut ByteReader,
+    strings: &[String],
+    rules: &[RuleRef<S>],
+    children: &mut Vec<usize>,
+) -> Result<Part<S>, DecodeError> {
+    match r.u8()? {
+        TAG_LITERAL => {
+            let s = &strings[r.index(strings.len())?];
+            return Ok(Part::Literal(s.clone()));
+        }
+        tag @ (TAG_EXPANSION | TAG_ANONYMOUS_RULE) => {
+            return read_rule_part(tag, r, strings, rules, children);
+        }
+        TAG_FORMATTED => {
+            return read_formatted_part(r, strings, rules, children);
+        }
+        TAG_FILTERED => {
+            let n = r.len()?;
+            let mut filters = Vec::new();
+            for _ in 0..n {
+                let name = &strings[r.index(strings.len())?];
+                let f = Filter::from_name(name)
+                    .ok_or_else(|| DecodeError::new("A filter is unknown.".to_string()))?;
+                filters.push(f);
+            }
+            // The inner tag is checked before reading the inner part, so the nesting is bounded.
+            let p = match r.u8()? {
+                tag @ (TAG_EXPANSION | TAG_ANONYMOUS_RULE) => {
+                    read_rule_part(tag, r, strings, rules, children)?
+                }
+                TAG_FORMATTED => read_formatted_part(r, strings, rules, children)?,
+                _ => {
+                    return Err(DecodeError::new(
+                        "A filter is applied to an unexpected part.".to_string(),
+                    ));
+                }
+            };
+            return Ok(Part::Filtered(Box::new(p), filters));
+        }
+        TAG_CALL => {
+            let name = &strings[r.index(strings.len())?];
+            let n = r.len()?;
+            let mut args = Vec::new();
+            for _ in 0..n {
+                args.push(strings[r.index(strings.len())?].clone());
+            }
+            return Ok(Part::Call(name.clone(), args));
+        }
+        _ => {
+            return Err(DecodeError::new("Unknown kind of a part.".to_string()));
+        }
+    }
+}
+
+/// Read an expansion or an anonymous rule whose tag has been read.
+///
+/// # Parameter
+/// - `tag`: The tag, [`TAG_EXPANSION`] or [`TAG_ANONYMOUS_RULE`].
+/// - `r`: The reader.
+/// - `strings`: The string table.
+/// - `rules`: The rule table.
+/// - `children`: The indices of the rules referred by the part are added.
+///
+/// # Return
+/// The part.
+fn read_rule_part<S: Substitutor>(
+    tag: u8,
+    r: &mut ByteReader,
+    strings: &[String],
+    rules: &[RuleRef<S>],
+    children: &mut Vec<usize>,
+) -> Result<Part<S>, DecodeError> {
+    if tag == TAG_EXPANSION {
+        let s = &strings[r.index(strings.len())?];
+        let rule = if r.bool()? {
+            let i = r.index(rules.len())?;
+            children.push(i);
+            Some(Arc::clone(&rules[i]))
+        } else {
+            None
+        };
+        return Ok(Part::Expansion(s.clone(), rule));
+    }
+    let i = r.index(rules.len())?;
+    children.push(i);
+    return Ok(Part::AnonymousRule(Arc::clone(&rules[i])));
+}
+
+/// Read a formatted part whose tag has been read.
+///
+/// # Parameter
+/// - `r`: The reader.
+/// - `strings`: The string table.
+/// - `rules`: The rule table.
+/// - `children`: The indices of the rules referred by the part are added.
+///
+/// # Return
+/// The part.
+fn read_formatted_part<S: Substitutor>(
+    r: &mut ByteReader,
+    strings: &[String],
+    rules: &[RuleRef<S>],
+    children: &mut Vec<usize>,
+) -> Result<Part<S>, DecodeError> {
+    let source = &strings[r.index(strings.len())?];
+    let spec = FormatSpec::parse(source)
+        .ok_or_else(|| DecodeError::new("A format spec is broken.".to_string()))?;
+    match r.u8()? {
+        tag @ (TAG_EXPANSION | TAG_ANONYMOUS_RULE) => {
+            let p = read_rule_part(tag, r, strings, rules, children)?;
+            return Ok(Part::Formatted(Box::new(p), spec));
+        }
+        _ => {
+            return Err(DecodeError::new(
+                "A format spec is applied to an unexpected part.".to_string(),
+            ));
+        }
+    }
+}
+
+/// Read a [`ProductionRule`].
+///
+/// # Parameter
+/// - `r`: The reader.
+/// - `strings`: The string table.
+/// - `rules`: The rule table.
+/// - `children`: The indices of the rules referred by the production rule are added.
+///
+/// # Return
+/// The production rule.
+fn read_rule<S: Substitutor>(
+    r: &mut ByteReader,
+    strings: &[String],
+    rules: &[RuleRef<S>],
+    children: &mut Vec<usize>,
+) -> Result<ProductionRule<S>, DecodeError> {
+    let has_weight = r.bool()?;
+    let weight = if has_weight {
+        Some(check_weight(r.f64()?)?)
+    } else {
+        None
+    };
+    let equalized_chance = r.bool()?;
+    let mut gsubs = S::new();
+    let mut gsub_params = Vec::new();
+    let num_gsubs = r.len()?;
+    for _ in 0..num_gsubs {
+        let pattern = strings[r.index(strings.len())?].clone();
+        let repl = strings[r.index(strings.len())?].clone();
+        let limit = r.u64()?;
+        if limit > usize::MAX as u64 {
+            return Err(DecodeError::new("A gsub limit is too big.".to_string()));
+        }
+        let limit = limit as usize;
+        if let Err(subst_err) = gsubs.add(&pattern, repl.clone(), limit) {
+            let mut err_msg = "Gsub error: ".to_string();
+            err_msg += subst_err.error_message();
+            return Err(DecodeError::new(err_msg));
+        }
+        gsub_params.push(GsubParam::new(pattern, repl, limit));
+    }
+    let mut options = TextOptions::new();
+    let num_texts = r.len()?;
+    for _ in 0..num_texts {
+        let (text, sum) = read_text(r, strings, rules, children)?;
+        options.texts.push(text);
+        options.weights.push(sum);
+    }
+    options.equalize_chance(equalized_chance);
+    let mut rule = ProductionRule::new(options, gsubs, gsub_params);
+    rule.set_weight(weight);
+    return Ok(rule);
+}
+
+/// Sort the production rules so that every rule follows the rules that it refers.
+///
+/// # Parameter
+/// - `children`: `children[i]` has the indices of the rules referred by the i-th rule.
+///
+/// # Return
+/// The indices of the rules, or the error if the references are recursive or nested deeper than [`MAX_RULE_DEPTH`].
+fn sort_rules(children: &[Vec<usize>]) -> Result<Vec<usize>, DecodeError> {
+    // 0: not visited, 1: visiting, 2: visited
+    let mut state = vec![0u8; children.len()];
+    let mut depth = vec![0usize; children.len()];
+    let mut order = Vec::with_capacity(children.len());
+    for root in 0..children.len() {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        state[root] = 1;
+        while let Some((i, next)) = stack.pop() {
+            if next < children[i].len() {
+                stack.push((i, next + 1));
+                let c = children[i][next];
+                if state[c] == 1 {
+                    return Err(DecodeError::new(
+                        "Recursive expansion is detected.".to_string(),
+                    ));
+                } else if state[c] == 0 {
+                    state[c] = 1;
+                    stack.push((c, 0));
+                }
+            } else {
+                state[i] = 2;
+                depth[i] = 1 + children[i].iter().map(|c| depth[*c]).max().unwrap_or(0);
+                if depth[i] > MAX_RULE_DEPTH {
+                    return Err(DecodeError::new(
+                        "The production rules are nested too deeply.".to_string(),
+                    ));
+                }
+                order.push(i);
+            }
+        }
+    }
+    return Ok(order);
+}
+
+/// Check the precomputed values of a bound production rule, assuming the rules that it refers have been checked.
+///
+/// # Parameter
+/// - `rule`: The production rule.
+/// - `assignments`: The assignments in the [`Syntax`].
+///
+/// # Return
+/// The error if the expansions don't refer to the assignments, or the precomputed values differ from the ones computed by binding.
+fn check_bound_rule<S: Substitutor>(
+    rule: &ProductionRule<S>,
+    assignments: &Assignments<S>,
+) -> Result<(), DecodeError> {
+    let mut sum: f64 = 0.0;
+    for (t, w) in rule.options.texts.iter().zip(rule.options.weights.iter()) {
+        for p in t.parts.iter() {
+            check_bound_part(p, assignments)?;
+        }
+        let (comb, weight) = t.propagated_values();
+        sum += t.weight;
+        if t.comb != comb || (!t.weight_by_user && t.weight != weight) || *w != sum {
+            return Err(DecodeError::new(
+                "A precomputed weight or number of the combination is broken.".to_string(),
+            ));
+        }
+    }
+    return Ok(());
+}
+
+/// Check that an expansion in a bound part refers to the production rule assigned to the nonterminal.
+///
+/// # Parameter
+/// - `part`: The part.
+/// - `assignments`: The assignments in the [`Syntax`].
+fn check_bound_part<S: Substitutor>(
+    part: &Part<S>,
+    assignments: &Assignments<S>,
+) -> Result<(), DecodeError> {
+    match part {
+        Part::Expansion(s, r_opt) => {
+            let ok = match (r_opt, assignments.get(s)) {
+                (Some(r), Some(a)) => Arc::ptr_eq(r, a),
+                (None, None) => true,
+                _ => false,
+            };
+            if !ok {
+                return Err(DecodeError::new(
+                    "An expansion refers to a wrong production rule.".to_string(),
+                ));
+            }
+        }
+        Part::Formatted(p, _) | Part::Filtered(p, _) => check_bound_part(p, assignments)?,
+        _ => (),
+    }
+    return Ok(());
+}
+
+/// Unbind the expansions in a part of the production rule that isn't bound, so the references in the data aren't trusted.
+fn unbind_part<S: Substitutor>(part: &mut Part<S>) {
+    match part {
+        Part::Expansion(_, r_opt) => *r_opt = None,
+        Part::Formatted(p, _) | Part::Filtered(p, _) => unbind_part(p),
+        _ => (),
+    }
+}
+
+impl<S: Substitutor> Syntax<S> {
+    /// Encode the instance into the binary data.
+    ///
+    /// # Return
+    /// The binary data, which [`from_bytes()`] can decode.
+    ///
+    /// # Note
+    /// - The binary data contains the precomputed weights and the numbers of the combination if the instance is bound by [`bind_syntax()`], so [`from_bytes()`] can restore the instance without parsing and binding it again.
+    /// - It panics if the instance has 2^32 or more strings, production rules, or texts in a production rule, or a string is 2^32 bytes or longer, which the binary format can't store.
+    /// - The binary data has the parameters of the gsubs but doesn't have the substitutor itself. The substitutor is restored by [`Substitutor::add()`] with the parameters.
+    /// - The binary data is the same if the instances are the same.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut syntax: tphrase::Syntax = r#"
+    ///     main = {HELLO}, {WORLD}! ~ /Hi/Hello/
+    ///     HELLO = Hi
+    ///     WORLD = world
+    /// "#.parse()?;
+    /// syntax.bind_syntax("main")?;
+    /// let bytes = syntax.to_bytes();
+    ///
+    /// let syntax2: tphrase::Syntax = tphrase::Syntax::from_bytes(&bytes)?;
+    /// let mut ph: tphrase::Generator = tphrase::Generator::new();
+    /// let _ = ph.add(syntax2)?;
+    /// assert_eq!(ph.generate(), "Hello, world!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`from_bytes()`]: #method.from_bytes
+    /// [`bind_syntax()`]: #method.bind_syntax
+    /// [`Substitutor::add()`]: ../../trait.Substitutor.html#tymethod.add
+    pub fn to_bytes(self: &Self) -> Vec<u8> {
+        let mut enc = Encoder::new();
+        let mut names: Vec<&String> = self.assignments.keys().collect();
+        names.sort();
+        for name in names.iter() {
+            enc.rule(&self.assignments[*name]);
+        }
+
+        let mut rules_w = ByteWriter::new();
+        let mut i = 0;
+        while i < enc.rules.len() {
+            let r = Arc::clone(&enc.rules[i]);
+            enc.write_rule(&mut rules_w, &r.read().unwrap());
+            i += 1;
+        }
+
+        let mut tail_w = ByteWriter::new();
+        tail_w.len(names.len());
+        for name in names.iter() {
+            let s = enc.string(name);
+            tail_w.len(s);
+            let r = enc.rule(&self.assignments[*name]);
+            tail_w.len(r);
+        }
+        tail_w.bool(self.is_generatable());
+        if self.is_generatable() {
+            let s = enc.string(&self.start_condition);
+            tail_w.len(s);
+        }
+
+        let mut w = ByteWriter::new();
+        w.len(enc.strings.len());
+        for s in enc.strings.iter() {
+            w.bytes(s.as_bytes());
+        }
+        w.len(enc.rules.len());
+        w.append(rules_w);
+        w.append(tail_w);
+        return w.finish(MAGIC, VERSION);
+    }
+
+    /// Decode the binary data created by [`to_bytes()`].
+    ///
+    /// # Parameter
+    /// - `data`: The binary data.
+    ///
+    /// # Return
+    /// The phrase syntax, or the error if the data is broken.
+    ///
+    /// # Note
+    /// - The integrity of the data is validated. (The checksum, the references between the production rules, the weights, the numbers of the combination, and the recursive references.)
+    /// - The instance is bound if the encoded instance was bound, and [`Generator::add()`] doesn't need to bind it again if the start condition is the same. The precomputed values are checked instead of binding it again.
+    /// - An error is returned if the production rules refer to each other deeper than 256 levels.
+    ///
+    /// [`to_bytes()`]: #method.to_bytes
+    /// [`Generator::add()`]: ../../struct.Generator.html#method.add
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = ByteReader::new(data, MAGIC, VERSION)?;
+        let num_strings = r.len()?;
+        let mut strings = Vec::new();
+        for _ in 0..num_strings {
+            strings.push(r.string()?);
+        }
+
+        let num_rules = r.len()?;
+        if num_rules > r.remaining() / MIN_RULE_SIZE {
+            return Err(DecodeError::new(
+                "The number of the production rules is too large.".to_string(),
+            ));
+        }
+        let mut rules: Vec<RuleRef<S>> = Vec::new();
+        for _ in 0..num_rules {
+            rules.push(Arc::new(RwLock::new(ProductionRule::new(
+                TextOptions::new(),
+                S::new(),
+                Vec::new(),
+            ))));
+        }
+        let mut children = Vec::new();
+        for i in 0..num_rules {
+            let mut c = Vec::new();
+            let rule = read_rule(&mut r, &strings, &rules, &mut c)?;
+            *rules[i].write().unwrap() = rule;
+            children.push(c);
+        }
+        let order = sort_rules(&children)?;
+
+        let mut syntax = Syntax::new();
+        let mut rule_indices = HashMap::new();
+        let num_assignments = r.len()?;
+        for _ in 0..num_assignments {
+            let name = strings[r.index(strings.len())?].clone();
+            let i = r.index(rules.len())?;
+            rule_indices.insert(name.clone(), i);
+            if syntax
+                .assignments
+                .insert(name, Arc::clone(&rules[i]))
+                .is_some()
+            {
+                return Err(DecodeError::new(
+                    "A nonterminal is defined twice.".to_string(),
+                ));
+            }
+        }
+        if r.bool()? {
+            let start_condition = &strings[r.index(strings.len())?];
+            match syntax.assignments.get(start_condition) {
+                Some(rule) => {
+                    syntax.start_rule = Some(Arc::clone(rule));
+                    syntax.start_condition = start_condition.clone();
+                }
+                None => {
+                    return Err(DecodeError::new(
+                        "The start condition doesn't exist.".to_string(),
+                    ));
+                }
+            }
+        }
+        r.finish()?;
+
+        // The rules referred by the start condition are bound. Their precomputed values are checked in the order that every rule follows the rules that it refers.
+        let mut bound = vec![false; rules.len()];
+        if syntax.start_rule.is_some() {
+            let mut stack = vec![rule_indices[&syntax.start_condition]];
+            while let Some(i) = stack.pop() {
+                if !bound[i] {
+                    bound[i] = true;
+                    stack.extend(children[i].iter());
+                }
+            }
+            syntax.binding_epoch = next_binding_epoch();
+        }
+        for i in order {
+            let mut rule = rules[i].write().unwrap();
+            if bound[i] {
+                check_bound_rule(&rule, &syntax.assignments)?;
+                rule.binding_epoch = syntax.binding_epoch;
+            } else {
+                for t in rule.options.texts.iter_mut() {
+                    for p in t.parts.iter_mut() {
+                        unbind_part(p);
+                    }
+                }
+            }
+        }
+        return Ok(syntax);
+    }
+}
@@ -0,0 +1,608 @@
+//! The length of the phrases generated by a syntax
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use super::{Part, ProductionRule, Syntax, Text, TextOptions};
+use crate::apply_temperature;
+use crate::list_weights;
+use crate::selection_probability;
+use crate::Context;
+use crate::ContextValue;
+use crate::ExtLengthBounds;
+use crate::FormatSpec;
+use crate::LengthRange;
+use crate::LengthUnit;
+use crate::Locale;
+use crate::RandomNumberGenerator;
+use crate::Substitutor;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+type RuleRef<S> = Arc<RwLock<ProductionRule<S>>>;
+
+/// The sampler of the derivations whose phrases don't exceed a length.
+///
+/// # Note
+/// - The length of a text is the sum of the length of its parts, so it's approximate unless the unit is [`LengthUnit::Chars`].
+/// - The gsubs and the filters are assumed not to change the length.
+/// - The function calls are assumed to be expanded into the empty texts.
+pub(crate) struct LengthSampler<'a> {
+    /// The unit of the length.
+    unit: LengthUnit,
+    /// The maximum length.
+    max_len: usize,
+    /// The external context.
+    ext_context: &'a dyn Context,
+    /// The locale to format the numbers.
+    locale: &'a Locale,
+    /// The temperature applied to the weights.
+    temperature: f64,
+    /// `dists[r][l]` is the probability that the production rule `r` generates the text of the length `l`.
+    dists: HashMap<usize, Vec<f64>>,
+    /// `text_dists[t][l]` is the probability that the text `t` generates the text of the length `l`.
+    text_dists: HashMap<usize, Vec<f64>>,
+    /// `bounds[r]` is the upper bound of the length of the texts generated by the production rule `r`.
+    bounds: HashMap<usize, usize>,
+}
+impl<'a> LengthSampler<'a> {
+    /// Create a sampler.
+    ///
+    /// # Parameter
+    /// - `unit`: The unit of the length.
+    /// - `max_len`: The maximum length.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `locale`: The locale to format the numbers.
+    /// - `temperature`: The temperature applied to the weights.
+    pub(crate) fn new(
+        unit: LengthUnit,
+        max_len: usize,
+        ext_context: &'a dyn Context,
+        locale: &'a Locale,
+        temperature: f64,
+    ) -> Self {
+        Self {
+            unit,
+            max_len,
+            ext_context,
+            locale,
+            temperature,
+            dists: HashMap::new(),
+            text_dists: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    /// The maximum length.
+    pub(crate) fn max_len(self: &Self) -> usize {
+        self.max_len
+    }
+
+    /// Decrease the maximum length to the upper bound of the length of the phrases generated by the syntaxes, so the distributions aren't longer than needed.
+    ///
+    /// # Parameter
+    /// - `syntaxes`: The syntaxes.
+    ///
+    /// # Note
+    /// It must be called before any distributions are computed.
+    pub(crate) fn fit_max_len<S: Substitutor>(self: &mut Self, syntaxes: &[Syntax<S>]) {
+        let mut bound = 0;
+        for syntax in syntaxes.iter() {
+            if let Some(r) = &syntax.start_rule {
+                bound = bound.max(self.rule_bound(r));
+                if bound >= self.max_len {
+                    return;
+                }
+            }
+        }
+        self.max_len = bound;
+    }
+
+    /// The distribution of the length of the phrases generated by a syntax.
+    ///
+    /// # Parameter
+    /// - `syntax`: The syntax.
+    ///
+    /// # Return
+    /// The probability of each length up to the maximum length. The sum is less than 1 if some phrases exceed the maximum length.
+    pub(crate) fn syntax_distribution<S: Substitutor>(
+        self: &mut Self,
+        syntax: &Syntax<S>,
+    ) -> Vec<f64> {
+        match &syntax.start_rule {
+            Some(r) => self.rule_distribution(r),
+            None => vec![0.0; self.max_len + 1],
+        }
+    }
+
+    /// Push the indices of the options to generate a phrase of a length from a syntax.
+    ///
+    /// # Parameter
+    /// - `syntax`: The syntax.
+    /// - `len`: The length, whose probability must be positive.
+    /// - `rng`: The random number generator.
+    /// - `indices`: The indices are pushed in the order of generation.
+    pub(crate) fn sample_syntax<S: Substitutor, R: RandomNumberGenerator>(
+        self: &mut Self,
+        syntax: &Syntax<S>,
+        len: usize,
+        rng: &mut R,
+        indices: &mut Vec<usize>,
+    ) {
+        if let Some(r) = &syntax.start_rule {
+            self.sample_rule(r, len, rng, indices);
+        }
+    }
+
+    /// The weights to select the options of a production rule.
+    fn option_weights<S: Substitutor>(self: &Self, options: &TextOptions<S>) -> Vec<f64> {
+        let weights = options.weights_in_context(self.ext_context);
+        if options.equalized_chance {
+            return weights.into_owned();
+        }
+        return apply_temperature(&weights, self.temperature).into_owned();
+    }
+
+    /// The distribution of the length of the texts generated by a production rule.
+    fn rule_distribution<S: Substitutor>(self: &mut Self, rule: &RuleRef<S>) -> Vec<f64> {
+        let key = Arc::as_ptr(rule) as *const () as usize;
+        if let Some(d) = self.dists.get(&key) {
+            return d.clone();
+        }
+        let r = rule.read().unwrap();
+        let options = &r.options;
+        let weights = self.option_weights(options);
+        let mut d = vec![0.0; self.max_len + 1];
+        for (i, t) in options.texts.iter().enumerate() {
+            let p = selection_probability(&weights, options.equalized_chance, i);
+            if p > 0.0 {
+                for (x, y) in d.iter_mut().zip(self.text_distribution(t).iter()) {
+                    *x += p * *y;
+                }
+            }
+        }
+        self.dists.insert(key, d.clone());
+        return d;
+    }
+
+    /// The distribution of the length of the texts generated by a text.
+    fn text_distribution<S: Substitutor>(self: &mut Self, text: &Text<S>) -> Vec<f64> {
+        let key = text as *const Text<S> as usize;
+        if let Some(d) = self.text_dists.get(&key) {
+            return d.clone();
+        }
+        let mut d = self.delta(0);
+        for p in text.parts.iter() {
+            let x = self.part_distribution(p);
+            d = self.convolve(&d, &x);
+        }
+        self.text_dists.insert(key, d.clone());
+        return d;
+    }
+
+    /// The distribution of the length of the texts generated by a part.
+    fn part_distribution<S: Substitutor>(self: &mut Self, part: &Part<S>) -> Vec<f64> {
+        self.formatted_distribution(part, None)
+    }
+
+    /// The distribution of the length of the texts generated by a part and formatted by a spec.
+    ///
+    /// # Note
+    /// The texts longer than the maximum length are ignored even if the precision of the spec truncates them.
+    fn formatted_distribution<S: Substitutor>(
+        self: &mut Self,
+        part: &Part<S>,
+        spec: Option<&FormatSpec>,
+    ) -> Vec<f64> {
+        match part {
+            Part::Literal(s) => self.delta(self.unit.measure(s)),
+            Part::Expansion(_, Some(r)) | Part::AnonymousRule(r) => {
+                let d = self.rule_distribution(r);
+                match spec {
+                    Some(spec) => {
+                        let mut mapped = vec![0.0; self.max_len + 1];
+                        for (len, p) in d.iter().enumerate() {
+                            let len = spec.text_length(len);
+                            if len <= self.max_len {
+                                mapped[len] += p;
+                            }
+                        }
+                        mapped
+                    }
+                    None => d,
+                }
+            }
+            Part::Expansion(s, None) => match self.ext_context.value(s) {
+                Some(ContextValue::List(items)) => {
+                    let mut d = vec![0.0; self.max_len + 1];
+                    for (p, len) in self.list_probabilities(&items, spec) {
+                        if len <= self.max_len {
+                            d[len] += p;
+                        }
+                    }
+                    d
+                }
+                Some(value) => self.delta(self.value_length(&value, spec)),
+                None => self.delta(self.unit.measure(s)),
+            },
+            Part::Formatted(p, spec) => self.formatted_distribution(p, Some(spec)),
+            Part::Filtered(p, _) => self.formatted_distribution(p, spec),
+            Part::Call(_, _) => self.delta(0),
+        }
+    }
+
+    /// The upper bound of the length of the texts generated by a production rule.
+    fn rule_bound<S: Substitutor>(self: &mut Self, rule: &RuleRef<S>) -> usize {
+        let key = Arc::as_ptr(rule) as *const () as usize;
+        if let Some(b) = self.bounds.get(&key) {
+            return *b;
+        }
+        let r = rule.read().unwrap();
+        let mut bound = 0;
+        for t in r.options.texts.iter() {
+            let mut len: usize = 0;
+            for p in t.parts.iter() {
+                len = len.saturating_add(self.part_bound(p, None));
+            }
+            bound = bound.max(len);
+        }
+        self.bounds.insert(key, bound);
+        return bound;
+    }
+
+    /// The upper bound of the length of the texts generated by a part and formatted by a spec, measured in the same way as [`formatted_distribution()`].
+    ///
+    /// [`formatted_distribution()`]: #method.formatted_distribution
+    fn part_bound<S: Substitutor>(
+        self: &mut Self,
+        part: &Part<S>,
+        spec: Option<&FormatSpec>,
+    ) -> usize {
+        match part {
+            Part::Literal(s) => self.unit.measure(s),
+            Part::Expansion(_, Some(r)) | Part::AnonymousRule(r) => {
+                let len = self.rule_bound(r);
+                match spec {
+                    Some(spec) => spec.text_length(len),
+                    None => len,
+                }
+            }
+            Part::Expansion(s, None) => match self.ext_context.value(s) {
+                Some(ContextValue::List(items)) => self
+                    .list_probabilities(&items, spec)
+                    .iter()
+                    .map(|(_, len)| *len)
+                    .max()
+                    .unwrap_or(0),
+                Some(value) => self.value_length(&value, spec),
+                None => self.unit.measure(s),
+            },
+            Part::Formatted(p, spec) => self.part_bound(p, Some(spec)),
+            Part::Filtered(p, _) => self.part_bound(p, spec),
+            Part::Call(_, _) => 0,
+        }
+    }
+
+    /// The length of a value in the external context formatted by a spec.
+    fn value_length(self: &Self, value: &ContextValue, spec: Option<&FormatSpec>) -> usize {
+        let plain = FormatSpec::default();
+        let s = spec
+            .unwrap_or(&plain)
+            .format_value(value, self.locale)
+            .unwrap_or_default();
+        return self.unit.measure(&s);
+    }
+
+    /// The probability and the length of each item of a list in the external context.
+    ///
+    /// # Parameter
+    /// - `items`: The texts and their weights.
+    /// - `spec`: The format spec applied to the item, or [`None`].
+    ///
+    /// # Return
+    /// The probability and the length. An empty list is treated as "nil", as it's generated.
+    fn list_probabilities(
+        self: &Self,
+        items: &[(String, f64)],
+        spec: Option<&FormatSpec>,
+    ) -> Vec<(f64, usize)> {
+        let measure = |t: &str| match spec {
+            Some(spec) => self.unit.measure(&spec.format_text(t)),
+            None => self.unit.measure(t),
+        };
+        if items.is_empty() {
+            return vec![(1.0, measure("nil"))];
+        }
+        let weights = apply_temperature(&list_weights(items), self.temperature).into_owned();
+        return items
+            .iter()
+            .enumerate()
+            .map(|(i, (t, _))| (selection_probability(&weights, false, i), measure(t)))
+            .collect();
+    }
+
+    /// The distribution where the length is always `len`.
+    fn delta(self: &Self, len: usize) -> Vec<f64> {
+        let mut d = vec![0.0; self.max_len + 1];
+        if len <= self.max_len {
+            d[len] = 1.0;
+        }
+        return d;
+    }
+
+    /// The distribution of the sum of the lengths, up to the maximum length.
+    fn convolve(self: &Self, a: &[f64], b: &[f64]) -> Vec<f64> {
+        let mut d = vec![0.0; self.max_len + 1];
+        for (i, x) in a.iter().enumerate() {
+            if *x == 0.0 {
+                continue;
+            }
+            for (j, y) in b[..=(self.max_len - i)].iter().enumerate() {
+                d[i + j] += x * y;
+            }
+        }
+        return d;
+    }
+
+    /// Push the indices of the options to generate a text of a length from a production rule.
+    fn sample_rule<S: Substitutor, R: RandomNumberGenerator>(
+        self: &mut Self,
+        rule: &RuleRef<S>,
+        len: usize,
+        rng: &mut R,
+        indices: &mut Vec<usize>,
+    ) {
+        let r = rule.read().unwrap();
+        let options = &r.options;
+        let option_weights = self.option_weights(options);
+        let mut weights = Vec::with_capacity(options.texts.len());
+        for (i, t) in options.texts.iter().enumerate() {
+            let p = selection_probability(&option_weights, options.equalized_chance, i);
+            weights.push(if p > 0.0 {
+                p * self.text_distribution(t)[len]
+            } else {
+                0.0
+            });
+        }
+        let i = choose(&weights, rng).unwrap_or(0);
+        indices.push(i);
+        if let Some(t) = options.texts.get(i) {
+            self.sample_text(t, len, rng, indices);
+        }
+    }
+
+    /// Push the indices of the options to generate a text of a length from a text.
+    fn sample_text<S: Substitutor, R: RandomNumberGenerator>(
+        self: &mut Self,
+        text: &Text<S>,
+        len: usize,
+        rng: &mut R,
+        indices: &mut Vec<usize>,
+    ) {
+        let parts: Vec<Vec<f64>> = text
+            .parts
+            .iter()
+            .map(|p| self.part_distribution(p))
+            .collect();
+        // suffixes[j] is the distribution of the sum of the lengths of parts[j..].
+        let mut suffixes = vec![self.delta(0)];
+        for d in parts.iter().rev() {
+            let s = self.convolve(d, suffixes.last().unwrap());
+            suffixes.push(s);
+        }
+        suffixes.reverse();
+        let mut rest = len;
+        for (j, p) in text.parts.iter().enumerate() {
+            let weights: Vec<f64> = (0..=rest)
+                .map(|l| parts[j][l] * suffixes[j + 1][rest - l])
+                .collect();
+            let l = choose(&weights, rng).unwrap_or(0);
+            self.sample_part(p, None, l, rng, indices);
+            rest -= l;
+        }
+    }
+
+    /// Push the indices of the options to generate a text of a length from a part formatted by a spec.
+    fn sample_part<S: Substitutor, R: RandomNumberGenerator>(
+        self: &mut Self,
+        part: &Part<S>,
+        spec: Option<&FormatSpec>,
+        len: usize,
+        rng: &mut R,
+        indices: &mut Vec<usize>,
+    ) {
+        match part {
+            Part::Expansion(_, Some(r)) | Part::AnonymousRule(r) => match spec {
+                Some(spec) => {
+                    // Choose the length before the text is formatted.
+                    let weights: Vec<f64> = self
+                        .rule_distribution(r)
+                        .iter()
+                        .enumerate()
+                        .map(|(l, p)| if spec.text_length(l) == len { *p } else { 0.0 })
+                        .collect();
+                    let l = choose(&weights, rng).unwrap_or(0);
+                    self.sample_rule(r, l, rng, indices);
+                }
+                None => self.sample_rule(r, len, rng, indices),
+            },
+            Part::Expansion(s, None) => {
+                if let Some(ContextValue::List(items)) = self.ext_context.value(s) {
+                    if !items.is_empty() {
+                        let weights: Vec<f64> = self
+                            .list_probabilities(&items, spec)
+                            .iter()
+                            .map(|(p, l)| if *l == len { *p } else { 0.0 })
+                            .collect();
+                        indices.push(choose(&weights, rng).unwrap_or(0));
+                    }
+                }
+            }
+            Part::Formatted(p, spec) => self.sample_part(p, Some(spec), len, rng, indices),
+            Part::Filtered(p, _) => self.sample_part(p, spec, len, rng, indices),
+            Part::Literal(_) | Part::Call(_, _) => (),
+        }
+    }
+}
+
+/// Choose an index in proportion to the weights.
+///
+/// # Parameter
+/// - `weights`: The weights.
+/// - `rng`: The random number generator.
+///
+/// # Return
+/// The index, or [`None`] if the sum of the weights isn't positive.
+pub(crate) fn choose<R: RandomNumberGenerator>(weights: &[f64], rng: &mut R) -> Option<usize> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut r = rng.next() * total;
+    let mut last = None;
+    for (i, w) in weights.iter().enumerate() {
+        if *w > 0.0 {
+            if r < *w {
+                return Some(i);
+            }
+            r -= w;
+            last = Some(i);
+        }
+    }
+    return last;
+}
+
+/// The analyzer of the range of the length of the texts generated by a syntax.
+///
+/// # Note
+/// - The gsubs and the filters are assumed not to change the length, and the range is marked approximate.
+/// - The function call "{@name args}" is measured by the bounds of "@name" in the external context, or it's assumed to be the empty text and the range is marked approximate.
+/// - The length of a text is the sum of the length of its parts, and the range is marked approximate if the unit isn't [`LengthUnit::Chars`] and the text has multiple parts.
+pub(crate) struct RangeAnalyzer<'a> {
+    /// The unit of the length.
+    unit: LengthUnit,
+    /// The bounds of the external context.
+    ext_bounds: &'a ExtLengthBounds,
+    /// `ranges[r]` is the range of the production rule `r`.
+    ranges: HashMap<usize, LengthRange>,
+}
+impl<'a> RangeAnalyzer<'a> {
+    /// Create an analyzer.
+    ///
+    /// # Parameter
+    /// - `unit`: The unit of the length.
+    /// - `ext_bounds`: The bounds of the length of the values in the external context.
+    pub(crate) fn new(unit: LengthUnit, ext_bounds: &'a ExtLengthBounds) -> Self {
+        Self {
+            unit,
+            ext_bounds,
+            ranges: HashMap::new(),
+        }
+    }
+
+    /// The range of the phrases generated by a syntax.
+    ///
+    /// # Return
+    /// The range, or [`None`] if the syntax isn't bound.
+    pub(crate) fn syntax_range<S: Substitutor>(
+        self: &mut Self,
+        syntax: &Syntax<S>,
+    ) -> Option<LengthRange> {
+        return syntax.start_rule.as_ref().map(|r| self.rule_range(r));
+    }
+
+    /// The ranges of the texts generated by the nonterminals in a syntax.
+    ///
+    /// # Return
+    /// The ranges of the nonterminals that are bound, i.e. referred directly or indirectly by the start condition.
+    pub(crate) fn nonterminal_ranges<S: Substitutor>(
+        self: &mut Self,
+        syntax: &Syntax<S>,
+    ) -> BTreeMap<String, LengthRange> {
+        let mut ranges = BTreeMap::new();
+        if syntax.start_rule.is_none() {
+            return ranges;
+        }
+        for (name, r) in syntax.assignments.iter() {
+            if r.read().unwrap().binding_epoch == syntax.binding_epoch {
+                let x = self.rule_range(r);
+                ranges.insert(name.clone(), x);
+            }
+        }
+        return ranges;
+    }
+
+    /// The range of the texts generated by a production rule.
+    fn rule_range<S: Substitutor>(self: &mut Self, rule: &RuleRef<S>) -> LengthRange {
+        let key = Arc::as_ptr(rule) as *const () as usize;
+        if let Some(r) = self.ranges.get(&key) {
+            return *r;
+        }
+        let r = rule.read().unwrap();
+        let options = &r.options;
+        let mut range: Option<LengthRange> = None;
+        for (i, t) in options.texts.iter().enumerate() {
+            if selection_probability(&options.weights, options.equalized_chance, i) > 0.0 {
+                let x = self.text_range(t);
+                range = Some(range.map_or(x, |y| y.union(x)));
+            }
+        }
+        let mut range = range.unwrap_or(LengthRange::exact(0));
+        if !r.gsub_params.is_empty() {
+            range = range.with_approximate(true);
+        }
+        self.ranges.insert(key, range);
+        return range;
+    }
+
+    /// The range of the texts generated by a text.
+    fn text_range<S: Substitutor>(self: &mut Self, text: &Text<S>) -> LengthRange {
+        let mut range = LengthRange::exact(0);
+        for p in text.parts.iter() {
+            let x = self.part_range(p);
+            range = range.concat(x);
+        }
+        if self.unit != LengthUnit::Chars && text.parts.len() > 1 {
+            range = range.with_approximate(true);
+        }
+        return range;
+    }
+
+    /// The range of the texts generated by a part.
+    fn part_range<S: Substitutor>(self: &mut Self, part: &Part<S>) -> LengthRange {
+        match part {
+            Part::Literal(s) => LengthRange::exact(self.unit.measure(s)),
+            Part::Expansion(_, Some(r)) => self.rule_range(r),
+            Part::Expansion(s, None) => match self.ext_bounds.get(s) {
+                Some(x) => *x,
+                None => LengthRange::exact(self.unit.measure(s)),
+            },
+            Part::AnonymousRule(r) => self.rule_range(r),
+            Part::Formatted(p, spec) => match &**p {
+                Part::Expansion(s, None) if !self.ext_bounds.contains_key(s) => self.part_range(p),
+                _ => self.part_range(p).map(|len| spec.text_length(len)),
+            },
+            Part::Filtered(p, _) => self.part_range(p).with_approximate(true),
+            Part::Call(name, _) => match self.ext_bounds.get(&("@".to_string() + name)) {
+                Some(x) => *x,
+                None => LengthRange::exact(0).with_approximate(true),
+            },
+        }
+    }
+}
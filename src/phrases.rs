@@ -0,0 +1,182 @@
+//! The iterator over all the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Context;
+use crate::Derivation;
+use crate::Environment;
+use crate::Filter;
+use crate::FixedContext;
+use crate::FunctionRegistry;
+use crate::Locale;
+use crate::ReplaySelector;
+use crate::Substitutor;
+use crate::Syntax;
+use crate::TextGenerator;
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// The lazy iterator over all the phrases that the syntaxes can generate.
+///
+/// It's created by [`Generator::iter_all()`] or [`Syntax::enumerate()`]. The phrases are generated in the order of [`Generator::nth()`], one by one, so the whole set isn't stored in memory.
+///
+/// [`Generator::iter_all()`]: struct.Generator.html#method.iter_all
+/// [`Generator::nth()`]: struct.Generator.html#method.nth
+/// [`Syntax::enumerate()`]: struct.Syntax.html#method.enumerate
+pub struct Phrases<'a, S: Substitutor> {
+    /// The syntaxes.
+    syntaxes: &'a [Syntax<S>],
+    /// The external context.
+    ext_context: &'a dyn Context,
+    /// The locale to format the numbers.
+    locale: Locale,
+    /// The filters applied to the whole phrase.
+    post_filters: Vec<Filter>,
+    /// The functions called in the texts.
+    functions: FunctionRegistry,
+    /// The index of the current syntax.
+    syntax_index: usize,
+    /// The index of the next phrase in the current syntax.
+    k: u128,
+    /// The phrases already yielded, if only the distinct phrases are yielded.
+    yielded: Option<HashSet<String>>,
+}
+impl<'a, S: Substitutor> Phrases<'a, S> {
+    /// Create an iterator.
+    ///
+    /// # Parameter
+    /// - `syntaxes`: The syntaxes.
+    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
+    /// - `locale`: The locale to format the numbers.
+    /// - `post_filters`: The filters applied to the whole phrase.
+    /// - `functions`: The functions called in the texts.
+    pub(crate) fn new(
+        syntaxes: &'a [Syntax<S>],
+        ext_context: &'a dyn Context,
+        locale: Locale,
+        post_filters: Vec<Filter>,
+        functions: FunctionRegistry,
+    ) -> Self {
+        Self {
+            syntaxes,
+            ext_context,
+            locale,
+            post_filters,
+            functions,
+            syntax_index: 0,
+            k: 0,
+            yielded: None,
+        }
+    }
+
+    /// Yield only the distinct phrases, i.e. skip the phrases that have been yielded.
+    ///
+    /// # Return
+    /// The iterator.
+    ///
+    /// # Note
+    /// - The distinct phrases after gsubs are stored in memory to detect the duplications.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), tphrase::CompileError> {
+    /// let ph: tphrase::Generator = "main = {A}{A}\nA = a | aa".parse()?;
+    /// let no_context = tphrase::ExtContext::new();
+    /// let all: Vec<_> = ph.iter_all(&no_context).collect();
+    /// assert_eq!(all, ["aa", "aaa", "aaa", "aaaa"]);
+    /// let distinct: Vec<_> = ph.iter_all(&no_context).distinct().collect();
+    /// assert_eq!(distinct, ["aa", "aaa", "aaaa"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn distinct(self: Self) -> Self {
+        Self {
+            yielded: Some(HashSet::new()),
+            ..self
+        }
+    }
+
+    /// Generate the next phrase regardless of the duplication.
+    fn next_phrase(self: &mut Self) -> Option<String> {
+        while let Some(syntax) = self.syntaxes.get(self.syntax_index) {
+            if syntax.combination_number().is_none_or(|c| self.k < c) {
+                let mut indices = Vec::new();
+                syntax.unrank(self.k, &mut indices);
+                self.k += 1;
+                let derivation = Derivation::new(indices);
+                let mut selector = ReplaySelector::new(&derivation);
+                let ext_context = FixedContext::new(self.ext_context);
+                let env = Environment::new(
+                    &ext_context,
+                    &self.locale,
+                    &self.post_filters,
+                    &self.functions,
+                );
+                return Some(syntax.generate(&env, &mut selector));
+            }
+            self.syntax_index += 1;
+            self.k = 0;
+        }
+        return None;
+    }
+
+    /// The number of the phrases not generated yet, including the duplicated ones.
+    ///
+    /// # Note
+    /// The number saturates at `usize::MAX`.
+    fn remaining(self: &Self) -> usize {
+        let mut n: u128 = 0;
+        for (i, syntax) in self.syntaxes.iter().enumerate().skip(self.syntax_index) {
+            let c = syntax.combination_number().unwrap_or(u128::MAX);
+            n = n.saturating_add(if i == self.syntax_index {
+                c.saturating_sub(self.k)
+            } else {
+                c
+            });
+        }
+        return usize::try_from(n).unwrap_or(usize::MAX);
+    }
+}
+impl<S: Substitutor> Iterator for Phrases<'_, S> {
+    type Item = String;
+
+    fn next(self: &mut Self) -> Option<Self::Item> {
+        while let Some(phrase) = self.next_phrase() {
+            match &mut self.yielded {
+                None => return Some(phrase),
+                Some(yielded) => {
+                    if yielded.insert(phrase.clone()) {
+                        return Some(phrase);
+                    }
+                }
+            }
+        }
+        return None;
+    }
+
+    fn size_hint(self: &Self) -> (usize, Option<usize>) {
+        let n = self.remaining();
+        if self.yielded.is_some() {
+            (0, Some(n))
+        } else {
+            (n, Some(n))
+        }
+    }
+}
@@ -19,7 +19,7 @@
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 
 /// The random number generator used when selecting the text options in [`Generator`].
-/// You can replace the default into your version. [`Generator`] doesn't have [`Clone`] and [`Debug`] traits if your instance of [`RandomNumberGenerator`] doesn't have [`Clone`] and [`Debug`] traits.
+/// You can replace the default into your version. [`Generator`] doesn't have [`Clone`] and [`Debug`] traits if your instance of [`RandomNumberGenerator`] doesn't have [`Clone`] and [`Debug`] traits. Likewise, [`Generator`] is [`Send`] and [`Sync`] only if your instance is [`Send`] and [`Sync`].
 ///
 /// [`Generator`]: struct.Generator.html
 pub trait RandomNumberGenerator {
@@ -32,3 +32,32 @@ pub trait RandomNumberGenerator {
     /// [`Generator::generate()`]: struct.Generator.html#method.generate
     fn next(self: &mut Self) -> f64;
 }
+
+/// The random number generator that can be seeded, and whose state can be saved and restored.
+///
+/// It's necessary to reproduce the generated phrases, such as in a bug report and a snapshot test.
+///
+/// # Example
+/// ```rust
+/// use tphrase::{RandomNumberGenerator, SeedableRandomNumberGenerator};
+/// let mut rng1 = tphrase::FastrandRng::from_seed(42);
+/// let mut rng2 = tphrase::FastrandRng::from_seed(42);
+/// assert_eq!(rng1.next(), rng2.next());
+///
+/// let state = rng1.state();
+/// let a = rng1.next();
+/// rng1.set_state(state);
+/// assert_eq!(rng1.next(), a);
+/// ```
+pub trait SeedableRandomNumberGenerator: RandomNumberGenerator {
+    /// The type of the internal state.
+    type State: Clone;
+    /// Create a random number generator with a seed. Used in [`Generator::with_seed()`].
+    ///
+    /// [`Generator::with_seed()`]: struct.Generator.html#method.with_seed
+    fn from_seed(seed: u64) -> Self;
+    /// The current internal state.
+    fn state(self: &Self) -> Self::State;
+    /// Restore the internal state.
+    fn set_state(self: &mut Self, state: Self::State);
+}
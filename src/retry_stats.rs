@@ -0,0 +1,101 @@
+//! The statistics of the retries
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+/// The statistics of the retries in [`Generator::generate_where()`].
+///
+/// A low acceptance rate means the predicate rejects most of the phrases, i.e. it's starving the distribution.
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let mut ph: tphrase::Generator = "main = apple | banana | cherry".parse()?;
+/// for _ in 0..10 {
+///     assert_eq!(ph.generate_where(|s| s.starts_with('b')), Some("banana".to_string()));
+/// }
+/// let stats = ph.retry_stats();
+/// assert_eq!(stats.calls(), 10);
+/// assert_eq!(stats.failures(), 0);
+/// assert_eq!(stats.attempts(), stats.rejections() + 10);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Generator::generate_where()`]: struct.Generator.html#method.generate_where
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetryStats {
+    calls: u64,
+    attempts: u64,
+    rejections: u64,
+    failures: u64,
+}
+impl RetryStats {
+    /// Create an instance that has no record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of the calls.
+    pub fn calls(self: &Self) -> u64 {
+        self.calls
+    }
+
+    /// The number of the generated phrases, including the rejected ones.
+    pub fn attempts(self: &Self) -> u64 {
+        self.attempts
+    }
+
+    /// The number of the phrases rejected by the predicate.
+    pub fn rejections(self: &Self) -> u64 {
+        self.rejections
+    }
+
+    /// The number of the calls that gave up because all the attempts were rejected.
+    pub fn failures(self: &Self) -> u64 {
+        self.failures
+    }
+
+    /// The rate of the phrases accepted by the predicate.
+    ///
+    /// # Return
+    /// The rate, or [`None`] if no phrase is generated.
+    pub fn acceptance_rate(self: &Self) -> Option<f64> {
+        if self.attempts == 0 {
+            None
+        } else {
+            Some((self.attempts - self.rejections) as f64 / self.attempts as f64)
+        }
+    }
+
+    /// Record a call.
+    ///
+    /// # Parameter
+    /// - `attempts`: The number of the generated phrases.
+    /// - `accepted`: Is the last phrase accepted?
+    pub(crate) fn record(self: &mut Self, attempts: u64, accepted: bool) {
+        self.calls += 1;
+        self.attempts += attempts;
+        if accepted {
+            self.rejections += attempts - 1;
+        } else {
+            self.rejections += attempts;
+            self.failures += 1;
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! The shuffle bag to avoid the repetition of the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::RandomNumberGenerator;
+
+/// The pseudo-random permutation of the integers in the range of `0..n`.
+///
+/// It's a Feistel network over the smallest power of 4 not less than `n`, and the values out of range are skipped by cycle walking, so no table is needed even if `n` is huge.
+#[derive(Clone, Debug)]
+pub(crate) struct Permutation {
+    /// The number of the integers.
+    n: usize,
+    /// The key to select the permutation.
+    key: u64,
+    /// The number of the bits of a half block.
+    half_bits: u32,
+}
+impl Permutation {
+    /// The number of the rounds of the Feistel network.
+    const ROUNDS: u64 = 4;
+
+    /// Create a permutation.
+    ///
+    /// # Parameter
+    /// - `n`: The number of the integers.
+    /// - `key`: The key to select the permutation.
+    pub(crate) fn new(n: usize, key: u64) -> Self {
+        let bits = if n <= 1 {
+            0
+        } else {
+            usize::BITS - (n - 1).leading_zeros()
+        };
+        Self {
+            n,
+            key,
+            half_bits: bits.div_ceil(2),
+        }
+    }
+
+    /// Create a permutation with a key drawn from a random number generator.
+    ///
+    /// # Parameter
+    /// - `n`: The number of the integers.
+    /// - `rng`: The random number generator.
+    pub(crate) fn with_rng<R: RandomNumberGenerator>(n: usize, rng: &mut R) -> Self {
+        let key = (rng.next() * 9007199254740992.0) as u64;
+        Self::new(n, key)
+    }
+
+    /// The number of the integers.
+    pub(crate) fn len(self: &Self) -> usize {
+        self.n
+    }
+
+    /// The i-th integer in the permutation.
+    ///
+    /// # Parameter
+    /// - `i`: The index, which must be less than [`len()`].
+    ///
+    /// [`len()`]: #method.len
+    pub(crate) fn get(self: &Self, i: usize) -> usize {
+        let mut x = i as u64;
+        loop {
+            x = self.encrypt(x);
+            if x < self.n as u64 {
+                return x as usize;
+            }
+        }
+    }
+
+    /// Apply the Feistel network to a block.
+    fn encrypt(self: &Self, x: u64) -> u64 {
+        if self.half_bits == 0 {
+            return x;
+        }
+        let mask: u64 = (1u64 << self.half_bits) - 1;
+        let mut left = x >> self.half_bits;
+        let mut right = x & mask;
+        for round in 0..Self::ROUNDS {
+            let f = mix(self.key ^ round.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ right) & mask;
+            let t = left ^ f;
+            left = right;
+            right = t;
+        }
+        return (left << self.half_bits) | right;
+    }
+}
+
+/// Mix the bits. (The finalizer of SplitMix64.)
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return z ^ (z >> 31);
+}
+
+/// The shuffle bag that yields the indices of the phrases without repetition.
+#[derive(Clone, Debug)]
+pub(crate) struct ShuffleBag {
+    /// The fraction of the indices to be yielded before reshuffling.
+    fraction: f64,
+    /// The current permutation.
+    permutation: Option<Permutation>,
+    /// The number of the indices yielded from the current permutation.
+    pos: usize,
+}
+impl ShuffleBag {
+    /// Create an empty bag.
+    ///
+    /// # Parameter
+    /// - `fraction`: The fraction of the indices to be yielded before reshuffling, in the range of (0.0, 1.0].
+    pub(crate) fn new(fraction: f64) -> Self {
+        Self {
+            fraction,
+            permutation: None,
+            pos: 0,
+        }
+    }
+
+    /// Discard the current permutation, so the next index is yielded from a new permutation.
+    pub(crate) fn reset(self: &mut Self) {
+        self.permutation = None;
+    }
+
+    /// Yield the next index.
+    ///
+    /// # Parameter
+    /// - `n`: The number of the indices.
+    /// - `rng`: The random number generator to shuffle the indices.
+    ///
+    /// # Return
+    /// The index in the range of `0..n`, or [`None`] if `n` is 0.
+    pub(crate) fn next<R: RandomNumberGenerator>(
+        self: &mut Self,
+        n: usize,
+        rng: &mut R,
+    ) -> Option<usize> {
+        if n == 0 {
+            return None;
+        }
+        let limit = ((n as f64 * self.fraction).ceil() as usize).clamp(1, n);
+        let need_shuffle = match &self.permutation {
+            Some(p) => p.len() != n || self.pos >= limit,
+            None => true,
+        };
+        if need_shuffle {
+            self.permutation = Some(Permutation::with_rng(n, rng));
+            self.pos = 0;
+        }
+        let i = self.permutation.as_ref().unwrap().get(self.pos);
+        self.pos += 1;
+        return Some(i);
+    }
+}
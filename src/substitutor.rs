@@ -19,7 +19,7 @@
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 
 /// The substitutor implementing the gsub function in tphrase.
-/// You can replace the default into your version. [`Generator`] doesn't have [`Clone`] and [`Debug`] traits if your instance of [`Substitutor`] doesn't have [`Clone`] and [`Debug`] traits.
+/// You can replace the default into your version. [`Generator`] doesn't have [`Clone`] and [`Debug`] traits if your instance of [`Substitutor`] doesn't have [`Clone`] and [`Debug`] traits. Likewise, [`Generator`] is [`Send`] and [`Sync`] only if your instance is [`Send`] and [`Sync`].
 ///
 /// [`Generator`]: struct.Generator.html
 pub trait Substitutor {
@@ -0,0 +1,119 @@
+//! The temperature to sharpen or flatten the distribution of the options
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Selector;
+
+use std::borrow::Cow;
+
+/// Apply a temperature to the weights.
+///
+/// # Parameter
+/// - `weights`: `weights[i]` is the sum of `weights[i-1]` and the weight of the i-th option.
+/// - `temperature`: The temperature.
+///
+/// # Return
+/// The cumulative weights where each weight `w` is replaced with `(w / max) ^ (1 / temperature)`. `max` is the maximum weight, so the largest weight is 1. The weights are borrowed if the temperature is 1 or all the weights are 0.
+///
+/// # Note
+/// - The weight 0 stays 0 at any temperature.
+/// - The temperature 0 selects only the options that have the maximum weight, and the infinite temperature equalizes the chance of the options that have a positive weight.
+pub(crate) fn apply_temperature(weights: &[f64], temperature: f64) -> Cow<'_, [f64]> {
+    if temperature == 1.0 {
+        return Cow::Borrowed(weights);
+    }
+    let mut prev: f64 = 0.0;
+    let mut individual = Vec::with_capacity(weights.len());
+    for w in weights.iter() {
+        individual.push(w - prev);
+        prev = *w;
+    }
+    let max = individual.iter().cloned().fold(0.0, f64::max);
+    if max <= 0.0 {
+        return Cow::Borrowed(weights);
+    }
+    let exponent = 1.0 / temperature;
+    let mut sum: f64 = 0.0;
+    let mut tempered = Vec::with_capacity(weights.len());
+    for w in individual.iter() {
+        if *w > 0.0 {
+            sum += (w / max).powf(exponent);
+        }
+        tempered.push(sum);
+    }
+    return Cow::Owned(tempered);
+}
+
+/// The selector that applies a temperature to the weights, delegating the selection to another selector.
+///
+/// # Note
+/// The equalized chance isn't affected by the temperature.
+pub(crate) struct TemperatureSelector<Sel: Selector> {
+    /// The selector that actually selects the options.
+    inner: Sel,
+    /// The temperature.
+    temperature: f64,
+}
+impl<Sel: Selector> TemperatureSelector<Sel> {
+    /// Create a selector.
+    ///
+    /// # Parameter
+    /// - `inner`: The selector that actually selects the options.
+    /// - `temperature`: The temperature.
+    pub(crate) fn new(inner: Sel, temperature: f64) -> Self {
+        Self { inner, temperature }
+    }
+
+    /// The selector that actually selects the options.
+    pub(crate) fn inner(self: &Self) -> &Sel {
+        &self.inner
+    }
+
+    /// The selector that actually selects the options.
+    pub(crate) fn into_inner(self: Self) -> Sel {
+        self.inner
+    }
+}
+impl<Sel: Selector> Selector for TemperatureSelector<Sel> {
+    fn select(self: &mut Self, weights: &[f64], equalized_chance: bool) -> usize {
+        if equalized_chance {
+            return self.inner.select(weights, equalized_chance);
+        }
+        self.inner.select(
+            &apply_temperature(weights, self.temperature),
+            equalized_chance,
+        )
+    }
+
+    fn enter_rule(self: &mut Self, nonterminal: Option<&str>) {
+        self.inner.enter_rule(nonterminal);
+    }
+
+    fn leave_rule(self: &mut Self, pre_gsub: &str, post_gsub: &str) {
+        self.inner.leave_rule(pre_gsub, post_gsub);
+    }
+
+    fn use_context(self: &mut Self, name: &str) {
+        self.inner.use_context(name);
+    }
+
+    fn seed(self: &mut Self) -> Option<u64> {
+        self.inner.seed()
+    }
+}
@@ -0,0 +1,275 @@
+//! The derivation trace
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Derivation;
+use crate::Selector;
+use crate::SyntaxId;
+
+/// The derivation trace of a generated phrase.
+///
+/// It's created by [`Generator::generate_traced()`].
+///
+/// # Example
+/// ```rust
+/// # fn main() -> Result<(), tphrase::CompileError> {
+/// let mut ph: tphrase::Generator = tphrase::Generator::new();
+/// let id = ph.add(r#"
+///     main = {GREETING}, {NAME}!
+///     GREETING = Hello
+///     NAME = world ~ /w/W/
+/// "#.parse()?)?;
+/// let (phrase, trace) = ph.generate_traced();
+/// assert_eq!(phrase, "Hello, World!");
+/// assert_eq!(trace.syntax_id(), Some(id));
+/// let root = trace.root().unwrap();
+/// assert_eq!(root.nonterminal(), Some("main"));
+/// assert_eq!(root.children()[1].nonterminal(), Some("NAME"));
+/// assert_eq!(root.children()[1].pre_gsub(), "world");
+/// assert_eq!(root.children()[1].post_gsub(), "World");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Generator::generate_traced()`]: struct.Generator.html#method.generate_traced
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Trace {
+    syntax_id: Option<SyntaxId>,
+    syntax_index: usize,
+    root: Option<TraceNode>,
+}
+impl Trace {
+    /// The ID of the syntax that generated the phrase.
+    ///
+    /// # Return
+    /// The ID, or [`None`] if the generator is empty.
+    pub fn syntax_id(self: &Self) -> Option<SyntaxId> {
+        self.syntax_id
+    }
+
+    /// The node of the start rule.
+    ///
+    /// # Return
+    /// The root node, or [`None`] if the generator is empty.
+    pub fn root(self: &Self) -> Option<&TraceNode> {
+        self.root.as_ref()
+    }
+
+    /// The derivation that reproduces the phrase.
+    ///
+    /// # Return
+    /// The derivation, which is empty if the generator is empty.
+    pub fn derivation(self: &Self) -> Derivation {
+        let mut indices = Vec::new();
+        if let Some(root) = &self.root {
+            indices.push(self.syntax_index);
+            root.push_indices(&mut indices);
+        }
+        return Derivation::new(indices);
+    }
+}
+impl std::fmt::Display for Trace {
+    /// Write the trace as an indented tree, one line per rule.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.syntax_id, &self.root) {
+            (Some(id), Some(root)) => {
+                writeln!(f, "syntax {}", id)?;
+                root.fmt_indented(f, 1)
+            }
+            _ => writeln!(f, "nil"),
+        }
+    }
+}
+
+/// A production rule visited during the generation.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct TraceNode {
+    nonterminal: Option<String>,
+    option_index: usize,
+    pre_gsub: String,
+    post_gsub: String,
+    context_keys: Vec<String>,
+    children: Vec<TraceNode>,
+}
+impl TraceNode {
+    /// The nonterminal assigned to the rule.
+    ///
+    /// # Return
+    /// The nonterminal, or [`None`] if the rule is anonymous.
+    ///
+    /// # Note
+    /// - A local nonterminal is treated as an anonymous rule.
+    pub fn nonterminal(self: &Self) -> Option<&str> {
+        self.nonterminal.as_deref()
+    }
+
+    /// The index of the option chosen in the rule.
+    ///
+    /// # Return
+    /// The index counted from 0 in the order of the options in the source.
+    pub fn option_index(self: &Self) -> usize {
+        self.option_index
+    }
+
+    /// The text before the gsubs of the rule are applied.
+    pub fn pre_gsub(self: &Self) -> &str {
+        &self.pre_gsub
+    }
+
+    /// The text after the gsubs of the rule are applied.
+    pub fn post_gsub(self: &Self) -> &str {
+        &self.post_gsub
+    }
+
+    /// The keys of the external context used by the chosen option, in order of use.
+    pub fn context_keys(self: &Self) -> &[String] {
+        &self.context_keys
+    }
+
+    /// The rules expanded by the chosen option, in order of expansion.
+    pub fn children(self: &Self) -> &[TraceNode] {
+        &self.children
+    }
+
+    /// Push the option indices of the node and the descendants in preorder, which is the order of the selections.
+    fn push_indices(self: &Self, indices: &mut Vec<usize>) {
+        indices.push(self.option_index);
+        for c in self.children.iter() {
+            c.push_indices(indices);
+        }
+    }
+
+    /// Write the node and the descendants with indentation.
+    fn fmt_indented(
+        self: &Self,
+        f: &mut std::fmt::Formatter<'_>,
+        depth: usize,
+    ) -> std::fmt::Result {
+        let name = self.nonterminal.as_deref().unwrap_or("(anonymous)");
+        write!(
+            f,
+            "{:indent$}{} #{} {:?}",
+            "",
+            name,
+            self.option_index,
+            self.post_gsub,
+            indent = depth * 2
+        )?;
+        if self.pre_gsub != self.post_gsub {
+            write!(f, " <- {:?}", self.pre_gsub)?;
+        }
+        if !self.context_keys.is_empty() {
+            write!(f, " [{}]", self.context_keys.join(", "))?;
+        }
+        writeln!(f)?;
+        for c in self.children.iter() {
+            c.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The selector that records the derivation tree, delegating the selection to another selector.
+pub(crate) struct TraceSelector<Sel: Selector> {
+    /// The selector that actually selects the options.
+    inner: Sel,
+    /// The index of the syntax selected by the generator.
+    syntax_index: Option<usize>,
+    /// The nodes being generated.
+    stack: Vec<TraceNode>,
+    /// The finished node of the start rule.
+    root: Option<TraceNode>,
+}
+impl<Sel: Selector> TraceSelector<Sel> {
+    /// Create a selector.
+    ///
+    /// # Parameter
+    /// - `inner`: The selector that actually selects the options.
+    pub(crate) fn new(inner: Sel) -> Self {
+        Self {
+            inner,
+            syntax_index: None,
+            stack: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// The selector that actually selects the options.
+    pub(crate) fn inner(self: &Self) -> &Sel {
+        &self.inner
+    }
+
+    /// Finish the recording.
+    ///
+    /// # Parameter
+    /// - `ids`: The IDs of the syntaxes in the generator.
+    ///
+    /// # Return
+    /// The trace.
+    pub(crate) fn into_trace(self: Self, ids: &[SyntaxId]) -> Trace {
+        Trace {
+            syntax_id: self.syntax_index.and_then(|i| ids.get(i).copied()),
+            syntax_index: self.syntax_index.unwrap_or(0),
+            root: self.root,
+        }
+    }
+}
+impl<Sel: Selector> Selector for TraceSelector<Sel> {
+    fn select(self: &mut Self, weights: &[f64], equalized_chance: bool) -> usize {
+        let i = self.inner.select(weights, equalized_chance);
+        if let Some(node) = self.stack.last_mut() {
+            node.option_index = i;
+        } else {
+            self.syntax_index = Some(i);
+        }
+        return i;
+    }
+
+    fn enter_rule(self: &mut Self, nonterminal: Option<&str>) {
+        self.inner.enter_rule(nonterminal);
+        self.stack.push(TraceNode {
+            nonterminal: nonterminal.map(|s| s.to_string()),
+            ..TraceNode::default()
+        });
+    }
+
+    fn leave_rule(self: &mut Self, pre_gsub: &str, post_gsub: &str) {
+        self.inner.leave_rule(pre_gsub, post_gsub);
+        if let Some(mut node) = self.stack.pop() {
+            node.pre_gsub = pre_gsub.to_string();
+            node.post_gsub = post_gsub.to_string();
+            if let Some(parent) = self.stack.last_mut() {
+                parent.children.push(node);
+            } else {
+                self.root = Some(node);
+            }
+        }
+    }
+
+    fn use_context(self: &mut Self, name: &str) {
+        self.inner.use_context(name);
+        if let Some(node) = self.stack.last_mut() {
+            node.context_keys.push(name.to_string());
+        }
+    }
+
+    fn seed(self: &mut Self) -> Option<u64> {
+        self.inner.seed()
+    }
+}
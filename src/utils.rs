@@ -23,18 +23,142 @@ pub(crate) trait TextGenerator {
     /// Generate a text.
     ///
     /// # Parameter
-    /// - `ext_context`: The external context that has some nonterminals and the substitutions.
-    /// - `rng`: The random number generator.
+    /// - `env`: The environment that has the external context and the locale.
+    /// - `selector`: The selector that selects the options.
     ///
     /// # Return
     /// A text.
-    fn generate<R: crate::RandomNumberGenerator>(
+    fn generate<Sel: Selector>(
         self: &Self,
-        ext_context: &crate::ExtContext,
-        rng: &mut R,
+        env: &crate::Environment<'_>,
+        selector: &mut Sel,
     ) -> String;
 }
 
+/// A type of instances that select an option while generating a text, and can observe the generation.
+///
+/// The observing functions do nothing by default, so they cost nothing unless the implementation uses them.
+pub(crate) trait Selector {
+    /// Select an option.
+    ///
+    /// # Parameter
+    /// - `weights`: `weights[i]` is the sum of `weights[i-1]` and the weight to select the i-th option. The length is the number of the options.
+    /// - `equalized_chance`: Equalize the chance to select the options.
+    ///
+    /// # Return
+    /// The index of the selected option. It must be less than `weights.len()`.
+    fn select(self: &mut Self, weights: &[f64], equalized_chance: bool) -> usize;
+
+    /// Called when a production rule begins generating a text.
+    ///
+    /// # Parameter
+    /// - `nonterminal`: The nonterminal assigned to the production rule, or [`None`] if it's an anonymous rule.
+    fn enter_rule(self: &mut Self, _nonterminal: Option<&str>) {}
+
+    /// Called when a production rule ends generating a text.
+    ///
+    /// # Parameter
+    /// - `pre_gsub`: The text before the gsubs are applied.
+    /// - `post_gsub`: The text after the gsubs are applied.
+    fn leave_rule(self: &mut Self, _pre_gsub: &str, _post_gsub: &str) {}
+
+    /// Called when an expansion is expanded by the external context.
+    ///
+    /// # Parameter
+    /// - `name`: The name of the expansion.
+    fn use_context(self: &mut Self, _name: &str) {}
+
+    /// Create a seed of the random number generator given to a function called in a phrase.
+    ///
+    /// # Return
+    /// The seed, or [`None`] if the selector doesn't use the random numbers.
+    fn seed(self: &mut Self) -> Option<u64> {
+        None
+    }
+}
+
+/// The selector using a random number generator.
+pub(crate) struct RngSelector<'a, R: crate::RandomNumberGenerator> {
+    /// The random number generator that generates random numbers in the range of [0.0, 1.0).
+    rng: &'a mut R,
+}
+impl<'a, R: crate::RandomNumberGenerator> RngSelector<'a, R> {
+    /// Create a selector.
+    ///
+    /// # Parameter
+    /// - `rng`: The random number generator.
+    pub(crate) fn new(rng: &'a mut R) -> Self {
+        Self { rng }
+    }
+}
+impl<R: crate::RandomNumberGenerator> Selector for RngSelector<'_, R> {
+    /// # Note
+    /// The algorithm is described in "Selection Algorithm" in the crate document. Increment [`SELECTION_ALGORITHM_VERSION`] if you change the result for the same random numbers.
+    ///
+    /// [`SELECTION_ALGORITHM_VERSION`]: ../constant.SELECTION_ALGORITHM_VERSION.html
+    fn select(self: &mut Self, weights: &[f64], equalized_chance: bool) -> usize {
+        if weights.len() <= 1 {
+            return 0;
+        }
+        let mut r: f64 = self.rng.next();
+        let mut i: usize = 0;
+        if equalized_chance {
+            i = (r * weights.len() as f64).floor().min(usize::MAX as f64) as usize;
+        } else {
+            if let Some(x) = weights.last() {
+                r *= *x;
+                i = weights.partition_point(|&x| x < r);
+            }
+        }
+        if i >= weights.len() {
+            i = 0;
+        }
+        return i;
+    }
+
+    /// # Note
+    /// It consumes a random number as described in "Selection Algorithm" in the crate document.
+    fn seed(self: &mut Self) -> Option<u64> {
+        Some((self.rng.next() * (1u64 << 53) as f64) as u64)
+    }
+}
+
+/// The probability that [`RngSelector`] selects an option.
+///
+/// # Parameter
+/// - `weights`: `weights[i]` is the sum of `weights[i-1]` and the weight to select the i-th option.
+/// - `equalized_chance`: Equalize the chance to select the options.
+/// - `i`: The index of the option.
+///
+/// # Return
+/// The probability.
+///
+/// # Note
+/// It must be consistent with [`RngSelector::select()`].
+pub(crate) fn selection_probability(weights: &[f64], equalized_chance: bool, i: usize) -> f64 {
+    let n = weights.len();
+    if i >= n {
+        return 0.0;
+    }
+    if n == 1 {
+        return 1.0;
+    }
+    if equalized_chance {
+        return 1.0 / n as f64;
+    }
+    let total = weights[n - 1];
+    if total > 0.0 {
+        let w = if i == 0 {
+            weights[0]
+        } else {
+            weights[i] - weights[i - 1]
+        };
+        return w / total;
+    } else {
+        return if i == 0 { 1.0 } else { 0.0 };
+    }
+}
+
 /// Select an item, and a string is generated by it.
 ///
 /// # Generic type
@@ -44,37 +168,23 @@ pub(crate) trait TextGenerator {
 /// - `target`: A set from which an item is selected.
 /// - `weights`: `weights[i]` is the sum of `weights[i-1]` and the weight to select `target[i]`.
 /// - `equalized_chance`: Equalize the chance to select the items.
-/// - `ext_context`: The external context that has some nonterminals and the substitutions.
-/// - `rng`: The random number generator that generates random numbers in the range of [0.0, 1.0).
+/// - `env`: The environment that has the external context and the locale.
+/// - `selector`: The selector that selects the options.
 ///
 /// # Return
 /// The generated string.
-pub(crate) fn select_and_generate_text<T: crate::TextGenerator, R: crate::RandomNumberGenerator>(
-    targets: &Vec<T>,
-    weights: &Vec<f64>,
+pub(crate) fn select_and_generate_text<T: crate::TextGenerator, Sel: Selector>(
+    targets: &[T],
+    weights: &[f64],
     equalized_chance: bool,
-    ext_context: &crate::ExtContext,
-    rng: &mut R,
+    env: &crate::Environment<'_>,
+    selector: &mut Sel,
 ) -> String {
     if targets.is_empty() {
         return "nil".to_string();
-    } else if targets.len() == 1 {
-        return targets[0].generate(ext_context, rng);
     } else {
-        let mut r: f64 = rng.next();
-        let mut i: usize = 0;
-        if equalized_chance {
-            i = (r * targets.len() as f64).floor().min(usize::MAX as f64) as usize;
-        } else {
-            if let Some(x) = weights.last() {
-                r *= *x;
-                i = weights.partition_point(|&x| x < r);
-            }
-        }
-        if i >= targets.len() {
-            i = 0;
-        }
-        return targets[i].generate(ext_context, rng);
+        let i = selector.select(weights, equalized_chance);
+        return targets[i].generate(env, selector);
     }
 }
 
@@ -0,0 +1,204 @@
+//! The expression of the weight referring to the external context
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+use crate::Context;
+
+/// A node of the expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// A number.
+    Number(f64),
+    /// The value of a nonterminal in the external context.
+    Nonterminal(String),
+    /// The negation.
+    Neg(Box<Node>),
+    /// The binary operation that has the operator, "+", "-", "*", or "/".
+    Binary(char, Box<Node>, Box<Node>),
+}
+impl Node {
+    /// Evaluate the node.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context.
+    ///
+    /// # Return
+    /// The value, or [`None`] if the external context doesn't have a nonterminal or its value isn't a number.
+    fn eval(self: &Self, ext_context: &dyn Context) -> Option<f64> {
+        return match self {
+            Node::Number(x) => Some(*x),
+            Node::Nonterminal(name) => ext_context.value(name)?.as_f64(),
+            Node::Neg(x) => Some(-x.eval(ext_context)?),
+            Node::Binary(op, x, y) => {
+                let x = x.eval(ext_context)?;
+                let y = y.eval(ext_context)?;
+                match op {
+                    '+' => Some(x + y),
+                    '-' => Some(x - y),
+                    '*' => Some(x * y),
+                    _ => Some(x / y),
+                }
+            }
+        };
+    }
+}
+
+/// The expression that follows "w:" in a weight, e.g. "RAIN_LEVEL * 2" in "{w:RAIN_LEVEL * 2}".
+///
+/// # Note
+/// The syntax is:
+/// ```EBNF
+/// weight_expr = term, [ { ( "+" | "-" ), term } ] ;
+/// term = factor, [ { ( "*" | "/" ), factor } ] ;
+/// factor = number | nonterminal | "-", factor | "(", weight_expr, ")" ;
+/// number = ( ( { ? [0-9] ? }, [ "." ] ) | ( ".", ? [0-9] ? ) ), [ { ? [0-9] ? } ] ;
+/// nonterminal = { ? [A-Za-z0-9_.] ? } ; (* It isn't a number. *)
+/// ```
+/// The spaces are allowed between the tokens. The expression is up to 256 characters, which limits the nesting.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct WeightExpr {
+    /// The source of the expression.
+    source: String,
+    /// The root node.
+    root: Node,
+}
+impl WeightExpr {
+    /// The maximum number of the characters of the expression.
+    const MAX_LEN: usize = 256;
+
+    /// Parse an expression.
+    ///
+    /// # Parameter
+    /// - `expr`: The expression.
+    ///
+    /// # Return
+    /// The expression, or [`None`] if `expr` is empty, too long, or invalid.
+    pub(crate) fn parse(expr: &str) -> Option<Self> {
+        let chars: Vec<char> = expr.chars().collect();
+        if chars.len() > Self::MAX_LEN {
+            return None;
+        }
+        let mut i = 0;
+        let root = parse_sum(&chars, &mut i)?;
+        skip_spaces(&chars, &mut i);
+        if i != chars.len() {
+            return None;
+        }
+        return Some(Self {
+            source: expr.to_string(),
+            root,
+        });
+    }
+
+    /// The source of the expression.
+    pub(crate) fn source(self: &Self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the expression.
+    ///
+    /// # Parameter
+    /// - `ext_context`: The external context.
+    ///
+    /// # Return
+    /// The weight. It's 0 if the external context doesn't have a nonterminal in the expression, the value of a nonterminal isn't a number, or the result isn't a finite non-negative number.
+    pub(crate) fn eval(self: &Self, ext_context: &dyn Context) -> f64 {
+        return self
+            .root
+            .eval(ext_context)
+            .filter(|w| w.is_finite() && *w >= 0.0)
+            .unwrap_or(0.0);
+    }
+}
+
+/// Skip the spaces.
+fn skip_spaces(chars: &[char], i: &mut usize) {
+    while *i < chars.len() && (chars[*i] == ' ' || chars[*i] == '\t') {
+        *i += 1;
+    }
+}
+
+/// Parse "weight_expr".
+fn parse_sum(chars: &[char], i: &mut usize) -> Option<Node> {
+    let mut r = parse_product(chars, i)?;
+    loop {
+        skip_spaces(chars, i);
+        match chars.get(*i) {
+            Some(op @ ('+' | '-')) => {
+                *i += 1;
+                r = Node::Binary(*op, Box::new(r), Box::new(parse_product(chars, i)?));
+            }
+            _ => return Some(r),
+        }
+    }
+}
+
+/// Parse "term".
+fn parse_product(chars: &[char], i: &mut usize) -> Option<Node> {
+    let mut r = parse_factor(chars, i)?;
+    loop {
+        skip_spaces(chars, i);
+        match chars.get(*i) {
+            Some(op @ ('*' | '/')) => {
+                *i += 1;
+                r = Node::Binary(*op, Box::new(r), Box::new(parse_factor(chars, i)?));
+            }
+            _ => return Some(r),
+        }
+    }
+}
+
+/// Parse "factor".
+fn parse_factor(chars: &[char], i: &mut usize) -> Option<Node> {
+    skip_spaces(chars, i);
+    match chars.get(*i) {
+        Some('-') => {
+            *i += 1;
+            return Some(Node::Neg(Box::new(parse_factor(chars, i)?)));
+        }
+        Some('(') => {
+            *i += 1;
+            let r = parse_sum(chars, i)?;
+            skip_spaces(chars, i);
+            if chars.get(*i) != Some(&')') {
+                return None;
+            }
+            *i += 1;
+            return Some(r);
+        }
+        _ => {}
+    }
+    let start = *i;
+    while *i < chars.len()
+        && (chars[*i].is_ascii_alphanumeric() || chars[*i] == '_' || chars[*i] == '.')
+    {
+        *i += 1;
+    }
+    if start == *i {
+        return None;
+    }
+    let token: String = chars[start..*i].iter().collect();
+    let is_number = token.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && token.chars().filter(|c| *c == '.').count() <= 1
+        && token.chars().any(|c| c.is_ascii_digit());
+    if is_number {
+        return Some(Node::Number(token.parse().ok()?));
+    }
+    return Some(Node::Nonterminal(token));
+}
@@ -0,0 +1,115 @@
+//! Test for the distinct phrases and the ambiguities
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+#[test]
+fn test_distinct_phrase_count() {
+    let no_context = ExtContext::new();
+    let ph: Generator = "main = {A}{B}\nA = a | b\nB = c | d".parse().unwrap();
+    assert_eq!(ph.distinct_phrase_count(&no_context).unwrap(), 4);
+    assert!(ph.ambiguities(&no_context).unwrap().is_empty());
+
+    // The same options in the different rules.
+    let ph: Generator = "main = {A} | {B}\nA = x | y\nB = y | z".parse().unwrap();
+    assert_eq!(ph.combination_number(), 4);
+    assert_eq!(ph.distinct_phrase_count(&no_context).unwrap(), 3);
+
+    // The gsubs collapse the differences.
+    let ph: Generator = "main = a | b | c ~ /[ab]/x/".parse().unwrap();
+    assert_eq!(ph.distinct_phrase_count(&no_context).unwrap(), 2);
+
+    let ph: Generator = Generator::new();
+    assert_eq!(ph.distinct_phrase_count(&no_context).unwrap(), 0);
+}
+
+#[test]
+fn test_distinct_phrase_count_context() {
+    let ph: Generator = "main = {X} | {Y}".parse().unwrap();
+    let same = ExtContext::from([
+        ("X".to_string(), "v".to_string()),
+        ("Y".to_string(), "v".to_string()),
+    ]);
+    assert_eq!(ph.distinct_phrase_count(&same).unwrap(), 1);
+    let different = ExtContext::from([
+        ("X".to_string(), "v".to_string()),
+        ("Y".to_string(), "w".to_string()),
+    ]);
+    assert_eq!(ph.distinct_phrase_count(&different).unwrap(), 2);
+}
+
+#[test]
+fn test_ambiguities() {
+    let no_context = ExtContext::new();
+    let mut ph: Generator = Generator::new();
+    ph.add(
+        "main = {A} | {B}\nA = x | y\nB = y | z | x"
+            .parse()
+            .unwrap(),
+    )
+    .unwrap();
+    ph.add("main = z".parse().unwrap()).unwrap();
+    let ambiguities = ph.ambiguities(&no_context).unwrap();
+    let phrases: Vec<_> = ambiguities.iter().map(|x| x.phrase()).collect();
+    assert_eq!(phrases, ["x", "y", "z"]);
+
+    let x = &ambiguities[0];
+    assert_eq!(
+        x.derivations(),
+        [
+            Derivation::new(vec![0, 0, 0]),
+            Derivation::new(vec![0, 1, 2])
+        ]
+    );
+    // The weights of the syntaxes are 5 and 1.
+    assert!((x.probability() - 5.0 / 6.0 * 2.0 / 5.0).abs() < 1e-12);
+    assert!((x.probability() - ph.probability_of("x", &no_context).unwrap()).abs() < 1e-12);
+
+    let z = &ambiguities[2];
+    assert_eq!(
+        z.derivations(),
+        [Derivation::new(vec![0, 1, 1]), Derivation::new(vec![1, 0])]
+    );
+    for a in ambiguities.iter() {
+        for d in a.derivations().iter() {
+            assert_eq!(
+                ph.generate_from_derivation(d, &no_context).unwrap(),
+                a.phrase()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_ambiguity_limit() {
+    let ph: Generator = r#"
+        main = {A}{A}{A}{A}{B}
+        A = {C}{C}{C}
+        B = 0 | 1
+        C = 0 | 1 | 2 | 3
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    assert!(ph.combination_number_exact().unwrap() > ENUMERATION_LIMIT);
+    assert_eq!(ph.distinct_phrase_count(&no_context), None);
+    assert!(ph.ambiguities(&no_context).is_none());
+}
@@ -0,0 +1,182 @@
+//! Test for Generator::generate_where() and Generator::find_banned()
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+#[test]
+fn test_generate_where() {
+    let mut ph: Generator = "main = {A}{A}\nA = 0 | 1 | 2 | 3".parse().unwrap();
+    for _ in 0..100 {
+        let s = ph.generate_where(|s| s.starts_with('3')).unwrap();
+        assert!(s.starts_with('3'));
+    }
+    let stats = ph.retry_stats();
+    assert_eq!(stats.calls(), 100);
+    assert_eq!(stats.failures(), 0);
+    assert_eq!(stats.attempts() - stats.rejections(), 100);
+    // The acceptance rate is 1/4.
+    let rate = stats.acceptance_rate().unwrap();
+    assert!(rate > 0.15 && rate < 0.4);
+}
+
+#[test]
+fn test_generate_where_failure() {
+    let mut ph: Generator = "main = a | b".parse().unwrap();
+    ph.set_max_attempts(5);
+    assert_eq!(ph.max_attempts(), 5);
+    assert_eq!(ph.generate_where(|s| s == "c"), None);
+    let stats = ph.retry_stats();
+    assert_eq!(stats.calls(), 1);
+    assert_eq!(stats.attempts(), 5);
+    assert_eq!(stats.rejections(), 5);
+    assert_eq!(stats.failures(), 1);
+    assert_eq!(stats.acceptance_rate(), Some(0.0));
+
+    ph.reset_retry_stats();
+    assert_eq!(ph.retry_stats(), RetryStats::new());
+    assert_eq!(ph.retry_stats().acceptance_rate(), None);
+
+    ph.set_max_attempts(0);
+    assert_eq!(ph.generate_where(|_| true), None);
+    assert_eq!(ph.retry_stats().failures(), 1);
+    assert_eq!(ph.retry_stats().attempts(), 0);
+}
+
+#[test]
+fn test_generate_where_with_context() {
+    let mut ph: Generator = "main = {X}a | {X}b".parse().unwrap();
+    let ctx = ExtContext::from([("X".to_string(), "x".to_string())]);
+    assert_eq!(
+        ph.generate_where_with_context(&ctx, |s| s.ends_with('b')),
+        Some("xb".to_string())
+    );
+}
+
+#[test]
+fn test_find_banned_literal() {
+    let ph: Generator = "main = good | bad word | other".parse().unwrap();
+    let found = ph.find_banned(&["bad"], &ExtContext::new());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].word(), "bad");
+    assert_eq!(found[0].phrase(), "bad word");
+    assert_eq!(found[0].derivation(), &Derivation::new(vec![0, 1]));
+    assert!(ph.find_banned(&["worst"], &ExtContext::new()).is_empty());
+    assert!(ph.find_banned(&[], &ExtContext::new()).is_empty());
+    assert!(ph.find_banned(&[""], &ExtContext::new()).is_empty());
+}
+
+#[test]
+fn test_find_banned_across_parts() {
+    let ph: Generator = r#"
+        main = {A}{B}{C}
+        A = xs | xc
+        B = h | {= a }
+        C = it | ot | t
+    "#
+    .parse()
+    .unwrap();
+    let found = ph.find_banned(&["shit"], &ExtContext::new());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].phrase(), "xshit");
+    assert_eq!(
+        ph.generate_from_derivation(found[0].derivation(), &ExtContext::new())
+            .unwrap(),
+        "xshit"
+    );
+    let found = ph.find_banned(&["cat", "hot"], &ExtContext::new());
+    let mut words: Vec<&str> = found.iter().map(|m| m.word()).collect();
+    words.sort();
+    words.dedup();
+    assert_eq!(words, ["cat", "hot"]);
+    for m in found.iter() {
+        assert!(m.phrase().contains(m.word()));
+    }
+}
+
+#[test]
+fn test_find_banned_short_parts() {
+    // The banned word spans more than two parts.
+    let ph: Generator = r#"
+        main = {A}{B}{C}{D}
+        A = x | w
+        B = o | r
+        C = r | o
+        D = d | k
+    "#
+    .parse()
+    .unwrap();
+    let found = ph.find_banned(&["word", "work"], &ExtContext::new());
+    let mut phrases: Vec<&str> = found.iter().map(|m| m.phrase()).collect();
+    phrases.sort();
+    assert_eq!(phrases, ["word", "work"]);
+}
+
+#[test]
+fn test_find_banned_matches_enumeration() {
+    let ph: Generator = r#"
+        main = {A} {B} | {B}{A}
+        A = ab | ba | {= c | cab }
+        B = a | b | bc
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    let words = ["abc", "bca", "b b"];
+    for (i, w) in words.iter().enumerate() {
+        let expected = ph.iter_all(&no_context).any(|s| s.contains(w));
+        let found = ph.find_banned(&words[i..=i], &no_context);
+        assert_eq!(!found.is_empty(), expected, "{}", w);
+    }
+}
+
+#[test]
+fn test_find_banned_context() {
+    let ph: Generator = "main = Hello, {NAME}!".parse().unwrap();
+    let ctx = ExtContext::from([("NAME".to_string(), "Brand".to_string())]);
+    assert_eq!(ph.find_banned(&["Brand"], &ctx).len(), 1);
+    assert!(ph.find_banned(&["Brand"], &ExtContext::new()).is_empty());
+    // Without the context, the nonterminal is output as it is.
+    assert_eq!(ph.find_banned(&["NAME"], &ExtContext::new()).len(), 1);
+}
+
+#[test]
+fn test_find_banned_gsub() {
+    let ph: Generator = r#"
+        main = {A} | {B}
+        A = bad ~ /bad/good/
+        B = bat ~ /t/d/
+    "#
+    .parse()
+    .unwrap();
+    // "bad" removed by the gsub isn't reported, and "bad" made by the gsub isn't found.
+    assert!(ph.find_banned(&["bad"], &ExtContext::new()).is_empty());
+}
+
+#[test]
+fn test_find_banned_multiple_syntaxes() {
+    let mut ph: Generator = "main = fine".parse().unwrap();
+    let _ = ph.add("main = {A}\nA = evil | nice".parse().unwrap());
+    let found = ph.find_banned(&["evil"], &ExtContext::new());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].derivation().indices(), [1, 0, 0]);
+    let empty: Generator = Generator::new();
+    assert!(empty.find_banned(&["nil"], &ExtContext::new()).is_empty());
+}
@@ -0,0 +1,419 @@
+//! Test for the binary encoding of Syntax
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+mod utils;
+use utils::*;
+
+fn round_trip<S: Substitutor>(syntax: &Syntax<S>) -> Syntax<S> {
+    Syntax::from_bytes(&syntax.to_bytes()).unwrap()
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let mut syntax: Syntax = r#"
+        main = {A} | {B} | "{C}" 3
+        A = a1 | a2 ~ /a/A/
+        B 5 := b1 | {_L} | "{_L}{_L}" 10
+        C = c
+        _L = l1 | l2
+    "#
+    .parse()
+    .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let syntax2 = round_trip(&syntax);
+
+    let mut ph1: Generator<LinearNG6> = Generator::new();
+    let _ = ph1.add(syntax).unwrap();
+    let mut ph2: Generator<LinearNG6> = Generator::new();
+    let _ = ph2.add(syntax2).unwrap();
+    assert_eq!(ph1.combination_number(), ph2.combination_number());
+    assert_eq!(ph1.weight(), ph2.weight());
+    for _ in 0..6 {
+        assert_eq!(ph1.generate(), ph2.generate());
+    }
+}
+
+#[test]
+fn test_binary_round_trip_overflow() {
+    let mut syntax: Syntax = r#"
+        main = {A}{A}{A}{A}{A} | {A}
+        A = {B}{B}{B}{B}
+        B = {C}{C}{C}{C}
+        C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+    .parse()
+    .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let syntax2 = round_trip(&syntax);
+
+    let mut ph1: Generator = Generator::new();
+    let _ = ph1.add(syntax).unwrap();
+    let mut ph2: Generator = Generator::new();
+    let _ = ph2.add(syntax2).unwrap();
+    assert_eq!(ph1.combination_number_exact(), None);
+    assert_eq!(ph2.combination_number_exact(), None);
+    let d = ph1.nth_derivation(usize::MAX).unwrap();
+    assert_eq!(ph2.rank(&d), Some(usize::MAX));
+}
+
+#[test]
+fn test_binary_round_trip_distribution() {
+    let mut syntax: Syntax = r#"
+        main = {A} | {B}
+        A = a1 | a2
+        B := b1 | {C}
+        C = c1 | c2 | c3
+    "#
+    .parse()
+    .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(round_trip(&syntax)).unwrap();
+    assert_eq!(ph.combination_number(), 6);
+    assert_eq!(ph.weight(), 6.0);
+
+    let dist = TextDistribution::from([
+        ("a1".to_string(), 1.0 / 6.0),
+        ("a2".to_string(), 1.0 / 6.0),
+        ("b1".to_string(), 2.0 / 6.0),
+        ("c1".to_string(), 2.0 / 18.0),
+        ("c2".to_string(), 2.0 / 18.0),
+        ("c3".to_string(), 2.0 / 18.0),
+    ]);
+    assert!(check_distribution(&mut ph, 100000, &dist, 0.01));
+}
+
+#[test]
+fn test_binary_unbound_syntax() {
+    let syntax: Syntax = r#"
+        main = {HELLO}, {WORLD}!
+        HELLO = Hello
+    "#
+    .parse()
+    .unwrap();
+    let mut syntax2 = round_trip(&syntax);
+    let world: Syntax = "WORLD = World".parse().unwrap();
+    syntax2.add(world).unwrap();
+
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(syntax2).unwrap();
+    assert_eq!(ph.generate(), "Hello, World!");
+}
+
+#[test]
+fn test_binary_start_condition() {
+    let mut syntax: Syntax = r#"
+        main = main
+        start = start
+    "#
+    .parse()
+    .unwrap();
+    syntax.bind_syntax("start").unwrap();
+    let bytes = syntax.to_bytes();
+
+    let mut ph: Generator = Generator::new();
+    let _ = ph
+        .add_with_start_condition(Syntax::from_bytes(&bytes).unwrap(), "start")
+        .unwrap();
+    let _ = ph.add(Syntax::from_bytes(&bytes).unwrap()).unwrap();
+    ph.equalize_chance(true);
+    let dist = TextDistribution::from([("main".to_string(), 0.5), ("start".to_string(), 0.5)]);
+    assert!(check_distribution(&mut ph, 10000, &dist, 0.02));
+}
+
+#[test]
+fn test_binary_deterministic() {
+    let mut syntax: Syntax = r#"
+        main = {A}{B}{C}{D}
+        A = a
+        B = b
+        C = c
+        D = d
+    "#
+    .parse()
+    .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let bytes = syntax.to_bytes();
+    assert_eq!(bytes, syntax.to_bytes());
+    assert_eq!(bytes, round_trip(&syntax).to_bytes());
+}
+
+#[test]
+fn test_binary_alternative_substitutor() {
+    let mut syntax: Syntax<PlainSubst> = parse_str(
+        r#"
+        main = "A.B.C" ~ /./-/g
+    "#,
+    )
+    .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let mut ph: Generator<ZeroNG, PlainSubst> = Generator::new();
+    let _ = ph.add(round_trip(&syntax)).unwrap();
+    assert_eq!(ph.generate(), "A-B-C");
+
+    // PlainSubst doesn't accept the limit except for "g".
+    let regex_syntax: Syntax = "main = A ~ /A/B/".parse().unwrap();
+    let result: Result<Syntax<PlainSubst>, _> = Syntax::from_bytes(&regex_syntax.to_bytes());
+    match result {
+        Ok(_) => assert!(result.is_err()),
+        Err(err) => assert_eq!(err.error_message(), "Gsub error: limit must be 0 or g."),
+    }
+}
+
+#[test]
+fn test_binary_broken_data() {
+    let mut syntax: Syntax = "main = Hello, World!".parse().unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let bytes = syntax.to_bytes();
+
+    let result: Result<Syntax, _> = Syntax::from_bytes(&bytes[1..]);
+    assert_eq!(result.unwrap_err().error_message(), "Unknown data format.");
+
+    let result: Result<Syntax, _> = Syntax::from_bytes(&bytes[..bytes.len() - 1]);
+    assert_eq!(
+        result.unwrap_err().error_message(),
+        "The data size is wrong."
+    );
+
+    let mut version = bytes.clone();
+    version[4] = 0xFF;
+    let result: Result<Syntax, _> = Syntax::from_bytes(&version);
+    assert_eq!(
+        result.unwrap_err().error_message(),
+        "Unsupported format version 255."
+    );
+
+    let mut corrupted = bytes.clone();
+    corrupted[20] ^= 0x01;
+    let result: Result<Syntax, _> = Syntax::from_bytes(&corrupted);
+    assert_eq!(
+        result.unwrap_err().error_message(),
+        "The checksum is wrong."
+    );
+}
+
+#[test]
+fn test_binary_unreferred_rules() {
+    let mut syntax: Syntax = r#"
+        main = {A}
+        A = a
+        U = unreferred
+    "#
+    .parse()
+    .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(round_trip(&syntax)).unwrap();
+    let ranges = ph.length_ranges(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(ranges.len(), 2);
+    assert!(!ranges.contains_key("U"));
+}
+
+/// Wrap a payload with the header and the checksum.
+fn wrap_payload(payload: &[u8]) -> Vec<u8> {
+    let mut data = b"TPHS".to_vec();
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    data.extend_from_slice(payload);
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in payload.iter() {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    data.extend_from_slice(&h.to_le_bytes());
+    return data;
+}
+
+/// The bytes of a production rule that has a text.
+fn rule_bytes(
+    rule_weight: Option<f64>,
+    sum: f64,
+    text_weight: f64,
+    weight_by_user: bool,
+    comb: Option<u128>,
+    part: &[u8],
+) -> Vec<u8> {
+    let mut p = Vec::new();
+    p.push(rule_weight.is_some() as u8);
+    if let Some(w) = rule_weight {
+        p.extend_from_slice(&w.to_le_bytes());
+    }
+    p.push(0);
+    p.extend_from_slice(&0u32.to_le_bytes());
+    p.extend_from_slice(&1u32.to_le_bytes());
+    p.extend_from_slice(&1u32.to_le_bytes());
+    p.extend_from_slice(&sum.to_le_bytes());
+    p.extend_from_slice(&text_weight.to_le_bytes());
+    p.push(weight_by_user as u8);
+    p.push(0);
+    p.push(comb.is_some() as u8);
+    p.extend_from_slice(&comb.unwrap_or(0).to_le_bytes());
+    p.extend_from_slice(&1u32.to_le_bytes());
+    p.extend_from_slice(part);
+    return p;
+}
+
+/// The payload of the syntax that has the strings "main" and "5", and the production rules, the first of which is assigned to "main" and is the start condition.
+fn syntax_payload(rules: &[Vec<u8>]) -> Vec<u8> {
+    let mut p = Vec::new();
+    p.extend_from_slice(&2u32.to_le_bytes());
+    p.extend_from_slice(&4u32.to_le_bytes());
+    p.extend_from_slice(b"main");
+    p.extend_from_slice(&1u32.to_le_bytes());
+    p.extend_from_slice(b"5");
+    p.extend_from_slice(&(rules.len() as u32).to_le_bytes());
+    for r in rules.iter() {
+        p.extend_from_slice(r);
+    }
+    p.extend_from_slice(&1u32.to_le_bytes());
+    p.extend_from_slice(&0u32.to_le_bytes());
+    p.extend_from_slice(&0u32.to_le_bytes());
+    p.push(1);
+    p.extend_from_slice(&0u32.to_le_bytes());
+    return p;
+}
+
+/// Decode a syntax that has a production rule, and get the error message.
+fn decode_error(
+    rule_weight: Option<f64>,
+    sum: f64,
+    text_weight: f64,
+    weight_by_user: bool,
+    comb: Option<u128>,
+    part: &[u8],
+) -> String {
+    let rule = rule_bytes(rule_weight, sum, text_weight, weight_by_user, comb, part);
+    let result: Result<Syntax, _> = Syntax::from_bytes(&wrap_payload(&syntax_payload(&[rule])));
+    return result.unwrap_err().error_message().to_string();
+}
+
+const LITERAL: [u8; 5] = [0, 1, 0, 0, 0];
+
+#[test]
+fn test_binary_crafted_data() {
+    let rule = rule_bytes(Some(2.0), 3.0, 3.0, true, Some(1), &LITERAL);
+    let data = wrap_payload(&syntax_payload(&[rule]));
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(Syntax::from_bytes(&data).unwrap()).unwrap();
+    assert_eq!(ph.generate(), "5");
+    assert_eq!(ph.combination_number(), 1);
+    assert_eq!(ph.weight(), 2.0);
+}
+
+#[test]
+fn test_binary_broken_weights() {
+    for w in [f64::NAN, -1.0, f64::INFINITY] {
+        assert_eq!(
+            decode_error(Some(w), 1.0, 1.0, false, Some(1), &LITERAL),
+            "A weight is broken."
+        );
+        assert_eq!(
+            decode_error(None, w, w, true, Some(1), &LITERAL),
+            "A weight is broken."
+        );
+    }
+}
+
+#[test]
+fn test_binary_broken_precomputed_values() {
+    let msg = "A precomputed weight or number of the combination is broken.";
+    assert_eq!(decode_error(None, 1.0, 1.0, false, Some(0), &LITERAL), msg);
+    assert_eq!(decode_error(None, 1.0, 1.0, false, None, &LITERAL), msg);
+    assert_eq!(decode_error(None, 2.0, 2.0, false, Some(1), &LITERAL), msg);
+    assert_eq!(
+        decode_error(None, f64::NAN, 1.0, false, Some(1), &LITERAL),
+        msg
+    );
+    assert_eq!(decode_error(None, 0.5, 1.0, false, Some(1), &LITERAL), msg);
+
+    // The expansion refers to the production rule that contains it.
+    let expansion = [1u8, 1, 0, 0, 0, 1, 0, 0, 0, 0];
+    assert_eq!(
+        decode_error(None, 1.0, 1.0, false, Some(1), &expansion),
+        "Recursive expansion is detected."
+    );
+
+    // The expansion "5" refers to a production rule that isn't assigned to "5".
+    let expansion = [1u8, 1, 0, 0, 0, 1, 1, 0, 0, 0];
+    let rules = [
+        rule_bytes(None, 1.0, 1.0, false, Some(1), &expansion),
+        rule_bytes(None, 1.0, 1.0, false, Some(1), &LITERAL),
+    ];
+    let result: Result<Syntax, _> = Syntax::from_bytes(&wrap_payload(&syntax_payload(&rules)));
+    assert_eq!(
+        result.unwrap_err().error_message(),
+        "An expansion refers to a wrong production rule."
+    );
+}
+
+#[test]
+fn test_binary_broken_nesting() {
+    // The nested filters without any filters.
+    let mut nested = Vec::new();
+    for _ in 0..100000 {
+        nested.extend_from_slice(&[4u8, 0, 0, 0, 0]);
+    }
+    nested.extend_from_slice(&LITERAL);
+    assert_eq!(
+        decode_error(None, 1.0, 1.0, false, Some(1), &nested),
+        "A filter is applied to an unexpected part."
+    );
+
+    // The formatted part in the formatted part.
+    let formatted = [3u8, 1, 0, 0, 0, 3, 1, 0, 0, 0, 1, 0, 0, 0, 0];
+    assert_eq!(
+        decode_error(None, 1.0, 1.0, false, Some(1), &formatted),
+        "A format spec is applied to an unexpected part."
+    );
+
+    // The chain of the anonymous rules.
+    let chain = |n: u32| -> Vec<Vec<u8>> {
+        let mut rules = Vec::new();
+        for i in 0..n {
+            let mut part = vec![2u8];
+            part.extend_from_slice(&(i + 1).to_le_bytes());
+            rules.push(rule_bytes(None, 1.0, 1.0, false, Some(1), &part));
+        }
+        rules.push(rule_bytes(None, 1.0, 1.0, false, Some(1), &LITERAL));
+        rules
+    };
+    let data = wrap_payload(&syntax_payload(&chain(255)));
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(Syntax::from_bytes(&data).unwrap()).unwrap();
+    assert_eq!(ph.generate(), "5");
+    let data = wrap_payload(&syntax_payload(&chain(256)));
+    let result: Result<Syntax, _> = Syntax::from_bytes(&data);
+    assert_eq!(
+        result.unwrap_err().error_message(),
+        "The production rules are nested too deeply."
+    );
+
+    let mut p = Vec::new();
+    p.extend_from_slice(&0u32.to_le_bytes());
+    p.extend_from_slice(&u32::MAX.to_le_bytes());
+    let result: Result<Syntax, _> = Syntax::from_bytes(&wrap_payload(&p));
+    assert_eq!(
+        result.unwrap_err().error_message(),
+        "The number of the production rules is too large."
+    );
+}
@@ -0,0 +1,133 @@
+//! Tests for Context and ChainedContext
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use tphrase::*;
+
+#[test]
+fn test_context_hash_map() {
+    let mut ph: Generator = "main = {A}-{B}".parse().unwrap();
+    let mut context = HashMap::new();
+    context.insert("A".to_string(), "x".to_string());
+    assert_eq!(ph.generate_with_context(&context), "x-B");
+}
+
+#[test]
+fn test_context_btree_map() {
+    let mut ph: Generator = "main = {A}-{B}".parse().unwrap();
+    let mut context = BTreeMap::new();
+    context.insert("A".to_string(), "x".to_string());
+    context.insert("B".to_string(), "y".to_string());
+    assert_eq!(ph.generate_with_context(&context), "x-y");
+}
+
+#[test]
+fn test_context_closure_is_lazy() {
+    let mut ph: Generator = "main = {A} | {B}".parse().unwrap();
+    let calls = Cell::new(0);
+    let context = |name: &str| {
+        calls.set(calls.get() + 1);
+        Some(name.to_lowercase())
+    };
+    for _ in 0..20 {
+        let s = ph.generate_with_context(&context);
+        assert!(s == "a" || s == "b");
+    }
+    // Only the selected expansion is requested.
+    assert_eq!(calls.get(), 20);
+}
+
+#[test]
+fn test_context_closure_unsolved() {
+    let mut ph: Generator = "main = {A}".parse().unwrap();
+    let context = |_: &str| None;
+    assert_eq!(ph.generate_with_context(&context), "A");
+}
+
+#[test]
+fn test_context_custom() {
+    struct Upper;
+    impl Context for Upper {
+        fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(name.to_uppercase()))
+        }
+    }
+    let mut ph: Generator = "main = {a}{b}".parse().unwrap();
+    assert_eq!(ph.generate_with_context(&Upper), "AB");
+}
+
+#[test]
+fn test_context_chained() {
+    let mut ph: Generator = "main = {A} {B} {C} {D}".parse().unwrap();
+    let first = ExtContext::from([("A".to_string(), "1".to_string())]);
+    let second = BTreeMap::from([
+        ("A".to_string(), "x".to_string()),
+        ("B".to_string(), "2".to_string()),
+    ]);
+    let third = |name: &str| {
+        if name == "C" || name == "B" {
+            Some("3".to_string())
+        } else {
+            None
+        }
+    };
+    let context = ChainedContext::new()
+        .with(&first)
+        .with(&second)
+        .with(&third);
+    assert_eq!(context.len(), 3);
+    assert_eq!(ph.generate_with_context(&context), "1 2 3 D");
+}
+
+#[test]
+fn test_context_chained_empty() {
+    let mut ph: Generator = "main = {A}".parse().unwrap();
+    let mut context = ChainedContext::new();
+    assert!(context.is_empty());
+    assert_eq!(ph.generate_with_context(&context), "A");
+    let inner = ChainedContext::new().with(&|_: &str| Some("z".to_string()));
+    context.push(&inner);
+    assert_eq!(ph.generate_with_context(&context), "z");
+}
+
+#[test]
+fn test_context_weight_by_closure() {
+    let ph: Generator = r#"main = sunny | "rainy" {w:RAIN_LEVEL}"#.parse().unwrap();
+    let level = 3;
+    let context = |name: &str| {
+        if name == "RAIN_LEVEL" {
+            Some(level.to_string())
+        } else {
+            None
+        }
+    };
+    assert_eq!(ph.probability_of("rainy", &context).unwrap(), 0.75);
+}
+
+#[test]
+fn test_context_iter_all_by_closure() {
+    let ph: Generator = "main = {A}{= x | y}".parse().unwrap();
+    let context = |_: &str| Some("a".to_string());
+    let phrases: Vec<String> = ph.iter_all(&context).collect();
+    assert_eq!(phrases, vec!["ax".to_string(), "ay".to_string()]);
+}
@@ -0,0 +1,186 @@
+//! Tests for the lists in the external context
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::collections::{BTreeMap, HashMap};
+use tphrase::*;
+
+fn party() -> HashMap<String, ContextValue<'static>> {
+    HashMap::from([
+        ("LEADER".to_string(), ContextValue::from("Alice")),
+        (
+            "MEMBER".to_string(),
+            ContextValue::list(["Bob", "Carol", "Dave"]),
+        ),
+    ])
+}
+
+#[test]
+fn test_context_list_generate() {
+    let mut ph: Generator = "main = {LEADER} meets {MEMBER}.".parse().unwrap();
+    let context = party();
+    let mut seen = Vec::new();
+    for _ in 0..200 {
+        let s = ph.generate_with_context(&context);
+        assert!(
+            s == "Alice meets Bob." || s == "Alice meets Carol." || s == "Alice meets Dave.",
+            "{}",
+            s
+        );
+        if !seen.contains(&s) {
+            seen.push(s);
+        }
+    }
+    assert_eq!(seen.len(), 3);
+}
+
+#[test]
+fn test_context_list_weighted() {
+    let ph: Generator = "main = {MEMBER}".parse().unwrap();
+    let context = BTreeMap::from([(
+        "MEMBER".to_string(),
+        ContextValue::weighted_list([("Bob", 3.0), ("Carol", 1.0), ("Dave", 0.0)]),
+    )]);
+    let mut rng: FastrandRng = SeedableRandomNumberGenerator::from_seed(7);
+    let mut bob = 0;
+    for _ in 0..1000 {
+        let s = ph.generate_with_rng(&context, &mut rng);
+        assert_ne!(s, "Dave");
+        if s == "Bob" {
+            bob += 1;
+        }
+    }
+    assert!(650 < bob && bob < 850, "{}", bob);
+}
+
+#[test]
+fn test_context_list_same_rng() {
+    let ph: Generator = "main = {MEMBER}".parse().unwrap();
+    let context = party();
+    let mut rng1: FastrandRng = SeedableRandomNumberGenerator::from_seed(42);
+    let mut rng2: FastrandRng = SeedableRandomNumberGenerator::from_seed(42);
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_rng(&context, &mut rng1),
+            ph.generate_with_rng(&context, &mut rng2)
+        );
+    }
+}
+
+#[test]
+fn test_context_list_trace() {
+    let mut ph: Generator = "main = {LEADER} meets {MEMBER}.".parse().unwrap();
+    let context = party();
+    let (phrase, trace) = ph.generate_traced_with_context(&context);
+    let root = trace.root().unwrap();
+    assert_eq!(root.context_keys(), ["LEADER", "MEMBER"]);
+    assert_eq!(root.children().len(), 1);
+    let member = &root.children()[0];
+    assert_eq!(member.nonterminal(), Some("MEMBER"));
+    let name = ["Bob", "Carol", "Dave"][member.option_index()];
+    assert_eq!(member.post_gsub(), name);
+    assert_eq!(phrase, format!("Alice meets {}.", name));
+
+    let derivation = trace.derivation();
+    assert_eq!(derivation.indices(), [0, 0, member.option_index()]);
+    assert_eq!(
+        ph.generate_from_derivation(&derivation, &context).unwrap(),
+        phrase
+    );
+    assert_eq!(
+        ph.probability_of_derivation(&derivation, &context).unwrap(),
+        1.0 / 3.0
+    );
+}
+
+#[test]
+fn test_context_list_in_rule_with_gsub() {
+    let mut ph: Generator = "main = {= {MEMBER} ~ /o/0/g}".parse().unwrap();
+    let context = party();
+    for _ in 0..20 {
+        let s = ph.generate_with_context(&context);
+        assert!(s == "B0b" || s == "Car0l" || s == "Dave", "{}", s);
+    }
+}
+
+#[test]
+fn test_context_list_history_window() {
+    let mut ph: Generator = "main = {MEMBER}, {MEMBER}, {MEMBER}".parse().unwrap();
+    ph.set_option_history_window("MEMBER", 2);
+    let context = party();
+    for _ in 0..20 {
+        let s = ph.generate_with_context(&context);
+        let mut names: Vec<&str> = s.split(", ").collect();
+        names.sort();
+        assert_eq!(names, ["Bob", "Carol", "Dave"]);
+    }
+}
+
+#[test]
+fn test_context_list_empty() {
+    let mut ph: Generator = "main = [{MEMBER}]".parse().unwrap();
+    let context = HashMap::from([(
+        "MEMBER".to_string(),
+        ContextValue::list(Vec::<String>::new()),
+    )]);
+    assert_eq!(ph.generate_with_context(&context), "[nil]");
+}
+
+#[test]
+fn test_context_list_representative() {
+    let ph: Generator = "main = {LEADER} meets {MEMBER}.".parse().unwrap();
+    let context = party();
+    assert_eq!(ph.nth(0, &context).unwrap(), "Alice meets Bob.");
+    let all: Vec<String> = ph.iter_all(&context).collect();
+    assert_eq!(all, ["Alice meets Bob."]);
+    assert_eq!(
+        ph.probability_of("Alice meets Bob.", &context).unwrap(),
+        1.0
+    );
+}
+
+#[test]
+fn test_context_list_chained() {
+    let mut ph: Generator = "main = {MEMBER}".parse().unwrap();
+    let first = party();
+    let second = HashMap::from([("MEMBER".to_string(), "Eve".to_string())]);
+    let context = ChainedContext::new().with(&second).with(&first);
+    assert_eq!(ph.generate_with_context(&context), "Eve");
+    let context = ChainedContext::new().with(&first).with(&second);
+    for _ in 0..20 {
+        assert_ne!(ph.generate_with_context(&context), "Eve");
+    }
+}
+
+#[test]
+fn test_context_list_max_len() {
+    let mut ph: Generator = "main = {LEADER} meets {MEMBER}.".parse().unwrap();
+    let context = party();
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len_and_context(16, LengthUnit::Chars, &context),
+            Some("Alice meets Bob.".to_string())
+        );
+    }
+    assert_eq!(
+        ph.generate_with_max_len_and_context(15, LengthUnit::Chars, &context),
+        None
+    );
+}
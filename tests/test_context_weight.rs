@@ -0,0 +1,167 @@
+//! Test for the weights referring to the external context
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::collections::HashMap;
+use tphrase::*;
+
+fn context(pairs: &[(&str, &str)]) -> ExtContext {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_context_weight_probability() {
+    let ph: Generator = r#"
+        main = sunny | "rainy" {w:RAIN_LEVEL}
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(
+        ph.probability_of("rainy", &context(&[("RAIN_LEVEL", "3")]))
+            .unwrap(),
+        0.75
+    );
+    assert_eq!(
+        ph.probability_of("rainy", &context(&[("RAIN_LEVEL", " 1.0 ")]))
+            .unwrap(),
+        0.5
+    );
+    assert_eq!(ph.probability_of("rainy", &ExtContext::new()).unwrap(), 0.0);
+    assert_eq!(
+        ph.probability_of("rainy", &context(&[("RAIN_LEVEL", "-1")]))
+            .unwrap(),
+        0.0
+    );
+    assert_eq!(
+        ph.probability_of("rainy", &context(&[("RAIN_LEVEL", "heavy")]))
+            .unwrap(),
+        0.0
+    );
+    // The number of the combination is static.
+    assert_eq!(ph.combination_number(), 2);
+}
+
+#[test]
+fn test_context_weight_expression() {
+    let ph: Generator = r#"
+        main = "x" 5 | "y" {w: A * (2 + B) - .5 } | "z" {w:-A+C.1/2}
+    "#
+    .parse()
+    .unwrap();
+    let ctx = context(&[("A", "1"), ("B", " 0.5 "), ("C.1", "4")]);
+    assert_eq!(ph.probability_of("y", &ctx).unwrap(), 0.25);
+    assert_eq!(ph.probability_of("z", &ctx).unwrap(), 0.125);
+    // The invalid values make the weight 0.
+    for ctx in [
+        context(&[("A", "1"), ("B", "-3")]),
+        context(&[("A", "x"), ("B", "1")]),
+        context(&[("B", "1")]),
+    ] {
+        assert_eq!(ph.probability_of("y", &ctx).unwrap(), 0.0);
+    }
+    let ctx = context(&[("A", "1"), ("B", "1"), ("C.1", "0")]);
+    assert_eq!(ph.probability_of("z", &ctx).unwrap(), 0.0);
+    let deep = "(".repeat(127) + "A" + &")".repeat(127);
+    let ph: Generator = format!("main = \"x\" | \"y\" {{w:{}}}", deep)
+        .parse()
+        .unwrap();
+    assert_eq!(ph.probability_of("y", &ctx).unwrap(), 0.5);
+    let ph: Generator = r#"main = "x" | "y" {w:A / B}"#.parse().unwrap();
+    let ctx = context(&[("A", "1"), ("B", "0")]);
+    assert_eq!(ph.probability_of("y", &ctx).unwrap(), 0.0);
+}
+
+#[test]
+fn test_context_weight_generate() {
+    let mut ph: Generator = r#"
+        main = {WEATHER}
+        WEATHER = "sunny" {w:SUN} | "rainy" {w:RAIN} | "cloudy" {w:CLOUD}
+    "#
+    .parse()
+    .unwrap();
+    let ctx = context(&[("SUN", "0"), ("RAIN", "1"), ("CLOUD", "3")]);
+    let mut count = HashMap::new();
+    for _ in 0..4000 {
+        *count.entry(ph.generate_with_context(&ctx)).or_insert(0) += 1;
+    }
+    assert!(!count.contains_key("sunny"));
+    assert!(count["rainy"] > 800 && count["rainy"] < 1200);
+    assert!(count["cloudy"] > 2800 && count["cloudy"] < 3200);
+}
+
+#[test]
+fn test_context_weight_propagation() {
+    // The default weight of "{B}" is 2, and it's propagated to A.
+    let ph: Generator = r#"
+        main = {A} | x
+        A = "{B}" {w:WB} | y
+        B = b1 | b2
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.weight(), 4.0);
+    assert_eq!(ph.probability_of("y", &no_context).unwrap(), 0.75);
+    assert_eq!(
+        ph.probability_of("b1", &context(&[("WB", "1")])).unwrap(),
+        0.75 * 0.5 * 0.5
+    );
+}
+
+#[test]
+fn test_context_weight_override() {
+    let mut ph: Generator = Generator::new();
+    let id = ph.add(r#"main = a | "b" {w:WB}"#.parse().unwrap()).unwrap();
+    assert_eq!(ph.probability_of("b", &ExtContext::new()).unwrap(), 0.0);
+    ph.set_option_weight(id, "main", 1, Some(3.0)).unwrap();
+    assert_eq!(ph.probability_of("b", &ExtContext::new()).unwrap(), 0.75);
+}
+
+#[test]
+fn test_context_weight_binary() {
+    let mut syntax: Syntax = r#"main = a | "b" {w:WB} | "c" {w:WB * 2 - 1}"#.parse().unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(Syntax::from_bytes(&syntax.to_bytes()).unwrap());
+    assert_eq!(ph.probability_of("b", &ExtContext::new()).unwrap(), 0.0);
+    assert_eq!(
+        ph.probability_of("b", &context(&[("WB", "1")])).unwrap(),
+        1.0 / 3.0
+    );
+    assert_eq!(
+        ph.probability_of("c", &context(&[("WB", "2")])).unwrap(),
+        0.5
+    );
+}
+
+#[test]
+fn test_context_weight_max_len() {
+    let mut ph: Generator = r#"main = "aaa" {w:WA} | "b" {w:WB} | cccc"#.parse().unwrap();
+    let ctx = context(&[("WA", "1")]);
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len_and_context(3, LengthUnit::Chars, &ctx),
+            Some("aaa".to_string())
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Test for the derivation and the replay
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+const SYNTAX: &str = r#"
+    main = {A} {_B} {C}
+    A = a1 | a2 | a3
+    _B = b1 | b2
+    C = {A}{X} ~ /1/one/
+"#;
+
+#[test]
+fn test_derivation_replay() {
+    let mut ph: Generator = Generator::with_seed(7);
+    ph.add(SYNTAX.parse().unwrap()).unwrap();
+    let ext_context = ExtContext::from([("X".to_string(), "x".to_string())]);
+    for _ in 0..100 {
+        let (phrase, trace) = ph.generate_traced_with_context(&ext_context);
+        let derivation = trace.derivation();
+        assert_eq!(derivation.indices().len(), 6);
+        assert_eq!(
+            ph.generate_from_derivation(&derivation, &ext_context)
+                .unwrap(),
+            phrase
+        );
+    }
+}
+
+#[test]
+fn test_derivation_explicit() {
+    let mut ph: Generator = Generator::new();
+    ph.add(SYNTAX.parse().unwrap()).unwrap();
+    ph.add("main = {A}\nA = x | y".parse().unwrap()).unwrap();
+    let ext_context = ExtContext::from([("X".to_string(), "x".to_string())]);
+    let d = Derivation::new(vec![0, 0, 1, 0, 0, 0]);
+    assert_eq!(
+        ph.generate_from_derivation(&d, &ext_context).unwrap(),
+        "a2 b1 aonex"
+    );
+    let d = Derivation::new(vec![1, 0, 1]);
+    assert_eq!(ph.generate_from_derivation(&d, &ext_context).unwrap(), "y");
+}
+
+#[test]
+fn test_derivation_translated() {
+    let ph_en: Generator = "main = {A} {B}\nA = one | two\nB = cat | dog"
+        .parse()
+        .unwrap();
+    let ph_fr: Generator = "main = {B} {A}\nA = un | deux\nB = chat | chien"
+        .parse()
+        .unwrap();
+    let d = Derivation::new(vec![0, 0, 1, 0]);
+    let no_context = ExtContext::new();
+    assert_eq!(
+        ph_en.generate_from_derivation(&d, &no_context).unwrap(),
+        "two cat"
+    );
+    // The order of the expansions differs, so the indices are applied in the order of the generation.
+    assert_eq!(
+        ph_fr.generate_from_derivation(&d, &no_context).unwrap(),
+        "chien un"
+    );
+}
+
+#[test]
+fn test_derivation_mismatch() {
+    let ph: Generator = "main = {A}\nA = x | y".parse().unwrap();
+    let no_context = ExtContext::new();
+
+    let err = ph
+        .generate_from_derivation(&Derivation::new(vec![0, 0, 2]), &no_context)
+        .unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The option index 2 at 2 is out of range."
+    );
+
+    let err = ph
+        .generate_from_derivation(&Derivation::new(vec![1, 0, 0]), &no_context)
+        .unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The option index 1 at 0 is out of range."
+    );
+
+    let err = ph
+        .generate_from_derivation(&Derivation::new(vec![0, 0]), &no_context)
+        .unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The derivation is shorter than the syntax requires."
+    );
+
+    let err = ph
+        .generate_from_derivation(&Derivation::new(vec![0, 0, 1, 0]), &no_context)
+        .unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The derivation is longer than the syntax requires."
+    );
+}
+
+#[test]
+fn test_derivation_empty_generator() {
+    let ph: Generator = Generator::new();
+    let no_context = ExtContext::new();
+    assert_eq!(
+        ph.generate_from_derivation(&Derivation::default(), &no_context)
+            .unwrap(),
+        "nil"
+    );
+    assert!(ph
+        .generate_from_derivation(&Derivation::new(vec![0]), &no_context)
+        .is_err());
+}
+
+#[test]
+fn test_derivation_bytes() {
+    let d = Derivation::new(vec![0, 1, 127, 128, 300, 1 << 40]);
+    let data = d.to_bytes();
+    assert_eq!(Derivation::from_bytes(&data).unwrap(), d);
+    assert_eq!(
+        Derivation::from_bytes(&Derivation::default().to_bytes()).unwrap(),
+        Derivation::default()
+    );
+
+    let mut broken = data.clone();
+    broken[17] ^= 1;
+    assert_eq!(
+        Derivation::from_bytes(&broken).unwrap_err().error_message(),
+        "The checksum is wrong."
+    );
+    assert_eq!(
+        Derivation::from_bytes(b"TPHS").unwrap_err().error_message(),
+        "Unknown data format."
+    );
+}
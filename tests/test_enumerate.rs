@@ -0,0 +1,98 @@
+//! Test for the enumeration of the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+#[test]
+fn test_iter_all_same_as_nth() {
+    let mut ph: Generator = Generator::new();
+    ph.add(
+        "main = {A}{_B}\nA = a | b ~ /b/B/\n_B = 1 | 2 | {X}"
+            .parse()
+            .unwrap(),
+    )
+    .unwrap();
+    ph.add("main = y | z".parse().unwrap()).unwrap();
+    let ext_context = ExtContext::from([("X".to_string(), "x".to_string())]);
+    let all: Vec<_> = ph.iter_all(&ext_context).collect();
+    assert_eq!(all.len(), ph.combination_number());
+    for (k, phrase) in all.iter().enumerate() {
+        assert_eq!(&ph.nth(k, &ext_context).unwrap(), phrase);
+    }
+    assert_eq!(all, ["a1", "a2", "ax", "B1", "B2", "Bx", "y", "z"]);
+}
+
+#[test]
+fn test_iter_all_distinct() {
+    let ph: Generator = "main = {A}{B} | {B}{A}\nA = a | b\nB = a | c"
+        .parse()
+        .unwrap();
+    let no_context = ExtContext::new();
+    let all: Vec<_> = ph.iter_all(&no_context).collect();
+    assert_eq!(all, ["aa", "ac", "ba", "bc", "aa", "ab", "ca", "cb"]);
+    let distinct: Vec<_> = ph.iter_all(&no_context).distinct().collect();
+    assert_eq!(distinct, ["aa", "ac", "ba", "bc", "ab", "ca", "cb"]);
+}
+
+#[test]
+fn test_iter_all_size_hint() {
+    let ph: Generator = "main = a | b | c".parse().unwrap();
+    let no_context = ExtContext::new();
+    let mut it = ph.iter_all(&no_context);
+    assert_eq!(it.size_hint(), (3, Some(3)));
+    it.next();
+    assert_eq!(it.size_hint(), (2, Some(2)));
+    assert_eq!(it.distinct().size_hint(), (0, Some(2)));
+}
+
+#[test]
+fn test_iter_all_large() {
+    let ph: Generator = r#"
+        main = {A}{A}{A}{A}{A}{A}{A}{A}
+        A = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.combination_number(), 100_000_000);
+    let first: Vec<_> = ph.iter_all(&no_context).take(3).collect();
+    assert_eq!(first, ["00000000", "00000001", "00000002"]);
+}
+
+#[test]
+fn test_iter_all_empty() {
+    let ph: Generator = Generator::new();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.iter_all(&no_context).next(), None);
+}
+
+#[test]
+fn test_enumerate_start_condition() {
+    let mut syntax: Syntax = "main = {A}-{A}\nA = p | q\nsub = {A}!".parse().unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(syntax.enumerate(&no_context).next(), None);
+    syntax.bind_syntax("sub").unwrap();
+    let all: Vec<_> = syntax.enumerate(&no_context).collect();
+    assert_eq!(all, ["p!", "q!"]);
+    syntax.bind_syntax("main").unwrap();
+    let all: Vec<_> = syntax.enumerate(&no_context).collect();
+    assert_eq!(all, ["p-p", "p-q", "q-p", "q-q"]);
+}
@@ -29,3 +29,28 @@ fn test_fastrand_rng() {
         assert!(0.0 <= a && a < 1.0);
     }
 }
+
+#[test]
+fn test_fastrand_rng_from_seed() {
+    let mut rng1 = FastrandRng::from_seed(1);
+    let mut rng2 = FastrandRng::from_seed(1);
+    let mut rng3 = FastrandRng::from_seed(2);
+    let mut diff = false;
+    for _ in 1..=100 {
+        let a = rng1.next();
+        assert_eq!(a, rng2.next());
+        diff = diff || a != rng3.next();
+    }
+    assert!(diff);
+}
+
+#[test]
+fn test_fastrand_rng_state() {
+    let mut rng = FastrandRng::from_seed(1);
+    let _ = rng.next();
+    let state = rng.state();
+    let v1: Vec<f64> = (0..10).map(|_| rng.next()).collect();
+    rng.set_state(state);
+    let v2: Vec<f64> = (0..10).map(|_| rng.next()).collect();
+    assert_eq!(v1, v2);
+}
@@ -0,0 +1,169 @@
+//! Tests for the filters in the expansions and the post filters
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::collections::HashMap;
+use tphrase::*;
+
+fn generate(syntax: &str, ctx: &ExtContext) -> String {
+    let mut ph: Generator = syntax.parse().unwrap();
+    ph.generate_with_context(ctx)
+}
+
+#[test]
+fn test_filter_apply() {
+    assert_eq!(Filter::Upper.apply("straße ǆ ﬁ"), "STRASSE Ǆ FI");
+    assert_eq!(Filter::Lower.apply("ΟΔΟΣ İ"), "οδος i\u{307}");
+    assert_eq!(Filter::Capitalize.apply("ǆungla"), "ǅungla");
+    assert_eq!(Filter::Capitalize.apply("ßa"), "Ssa");
+    assert_eq!(Filter::Capitalize.apply("ﬁsh"), "Fish");
+    assert_eq!(Filter::Capitalize.apply("¡hola, mundo!"), "¡Hola, mundo!");
+    assert_eq!(Filter::Capitalize.apply("3rd place"), "3rd place");
+    assert_eq!(Filter::Capitalize.apply("iPhone"), "IPhone");
+    assert_eq!(Filter::Capitalize.apply("ᾳ"), "ᾼ");
+    assert_eq!(Filter::Capitalize.apply(""), "");
+    assert_eq!(
+        Filter::Title.apply("jean-luc's \"new\" café ǳ"),
+        "Jean-Luc's \"New\" Café ǲ"
+    );
+    assert_eq!(
+        Filter::Title.apply("e\u{301}te\u{301}"),
+        "E\u{301}te\u{301}"
+    );
+    assert_eq!(Filter::Trim.apply("\u{3000} a b \n"), "a b");
+}
+
+#[test]
+fn test_filter_name() {
+    for f in [
+        Filter::Upper,
+        Filter::Lower,
+        Filter::Capitalize,
+        Filter::Title,
+        Filter::Trim,
+    ] {
+        assert_eq!(Filter::from_name(f.name()), Some(f));
+    }
+    assert_eq!(Filter::from_name("UPPER"), None);
+    assert_eq!(Filter::from_name(""), None);
+}
+
+#[test]
+fn test_filter_expansion() {
+    let ctx = ExtContext::from([
+        ("NAME".to_string(), "élodie".to_string()),
+        ("X".to_string(), "  padded ".to_string()),
+    ]);
+    assert_eq!(generate("main = {NAME|capitalize}", &ctx), "Élodie");
+    assert_eq!(generate("main = {NAME|upper}", &ctx), "ÉLODIE");
+    assert_eq!(generate("main = [{X|trim}]", &ctx), "[padded]");
+    assert_eq!(generate("main = [{X|trim|upper}]", &ctx), "[PADDED]");
+    assert_eq!(
+        generate("main = {A|title}\nA = the old man", &ctx),
+        "The Old Man"
+    );
+    assert_eq!(generate("main = {_A|upper}\n_A = abc", &ctx), "ABC");
+    // The unsolved nonterminal is filtered.
+    assert_eq!(generate("main = {unknown|upper}", &ctx), "UNKNOWN");
+}
+
+#[test]
+fn test_filter_with_format_spec() {
+    let ctx = ExtContext::from([("NAME".to_string(), "bob".to_string())]);
+    assert_eq!(generate("main = [{NAME:>5|upper}]", &ctx), "[  BOB]");
+    assert_eq!(generate("main = [{NAME:|>5|upper}]", &ctx), "[||BOB]");
+    assert_eq!(generate("main = [{NAME:|>5}]", &ctx), "[||bob]");
+    assert_eq!(generate("main = [{NAME:>5|trim}]", &ctx), "[bob]");
+}
+
+#[test]
+fn test_filter_unknown_is_literal() {
+    let ctx = ExtContext::new();
+    assert_eq!(generate("main = {A|B}", &ctx), "A|B");
+    assert_eq!(generate("main = {NAME|uper}", &ctx), "NAME|uper");
+    assert_eq!(generate("main = {NAME|upper|x}", &ctx), "NAME|upper|x");
+    assert_eq!(generate("main = {|upper}", &ctx), "|upper");
+    assert_eq!(generate("main = {A B|upper}", &ctx), "A B|upper");
+    assert_eq!(generate("main = {N:x|upper}", &ctx), "N:x|upper");
+    assert_eq!(generate("main = a{* x|upper}b", &ctx), "ab");
+}
+
+#[test]
+fn test_filter_list_and_number() {
+    let ctx = HashMap::from([
+        ("L".to_string(), ContextValue::list(["ab"])),
+        ("N".to_string(), ContextValue::from(1234.5)),
+    ]);
+    let mut ph: Generator = "main = {L|upper} {N:,.1|trim}".parse().unwrap();
+    assert_eq!(ph.generate_with_context(&ctx), "AB 1,234.5");
+    let (s, trace) = ph.generate_traced_with_context(&ctx);
+    assert_eq!(s, "AB 1,234.5");
+    assert_eq!(trace.root().unwrap().children()[0].post_gsub(), "ab");
+}
+
+#[test]
+fn test_filter_post() {
+    let mut ph: Generator = r#"main = " {A} "
+        A = hello | world"#
+        .parse()
+        .unwrap();
+    assert!(ph.post_filters().is_empty());
+    ph.set_post_filters(vec![Filter::Trim, Filter::Capitalize]);
+    assert_eq!(ph.post_filters(), [Filter::Trim, Filter::Capitalize]);
+    let ctx = ExtContext::new();
+    for _ in 0..10 {
+        let s = ph.generate();
+        assert!(s == "Hello" || s == "World");
+    }
+    assert_eq!(ph.nth(0, &ctx), Some("Hello".to_string()));
+    let all: Vec<_> = ph.iter_all(&ctx).collect();
+    assert_eq!(all, ["Hello", "World"]);
+    assert_eq!(ph.probability_of("World", &ctx).unwrap(), 0.5);
+    let (s, trace) = ph.generate_traced();
+    assert_eq!(
+        s,
+        Filter::Capitalize.apply(trace.root().unwrap().post_gsub().trim())
+    );
+    let d = ph.nth_derivation(1).unwrap();
+    assert_eq!(ph.generate_from_derivation(&d, &ctx).unwrap(), "World");
+    assert!(ph
+        .generate_with_max_len(7, LengthUnit::Chars)
+        .is_some_and(|s| s == "Hello" || s == "World"));
+}
+
+#[test]
+fn test_filter_binary() {
+    let mut syntax: Syntax = "main = {A|upper}{N:03|trim}{_B:.1|title}\nA = ab\n_B = cd"
+        .parse()
+        .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let restored: Syntax = Syntax::from_bytes(&syntax.to_bytes()).unwrap();
+    let ctx = HashMap::from([("N".to_string(), ContextValue::from(7))]);
+    let mut rng: FastrandRng = RandomNumberGenerator::new();
+    assert_eq!(restored.generate_with_rng(&ctx, &mut rng), "AB007C");
+}
+
+#[test]
+fn test_filter_length_range() {
+    let ph: Generator = "main = {A|upper}\nA = ab | abc".parse().unwrap();
+    let range = ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!((range.min(), range.max()), (2, 3));
+    assert!(range.is_approximate());
+}
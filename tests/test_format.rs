@@ -0,0 +1,235 @@
+//! Tests for the typed values in the external context and the format specs
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::collections::HashMap;
+use tphrase::*;
+
+fn context(pairs: Vec<(&str, ContextValue<'static>)>) -> HashMap<String, ContextValue<'static>> {
+    pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+fn generate(syntax: &str, ctx: &HashMap<String, ContextValue<'static>>) -> String {
+    let mut ph: Generator = syntax.parse().unwrap();
+    ph.generate_with_context(ctx)
+}
+
+#[test]
+fn test_format_integer() {
+    let ctx = context(vec![
+        ("N", ContextValue::from(5)),
+        ("M", ContextValue::from(-42)),
+        ("BIG", ContextValue::from(1234567_i64)),
+    ]);
+    assert_eq!(generate("main = {N}", &ctx), "5");
+    assert_eq!(generate("main = {N:03}", &ctx), "005");
+    assert_eq!(generate("main = {M:05}", &ctx), "-0042");
+    assert_eq!(generate("main = {N:+}", &ctx), "+5");
+    assert_eq!(generate("main = [{N:3}]", &ctx), "[  5]");
+    assert_eq!(generate("main = [{N:<3}]", &ctx), "[5  ]");
+    assert_eq!(generate("main = [{N:*^5}]", &ctx), "[**5**]");
+    assert_eq!(generate("main = {BIG}", &ctx), "1234567");
+    assert_eq!(generate("main = {BIG:,}", &ctx), "1,234,567");
+    assert_eq!(generate("main = {M:,}", &ctx), "-42");
+    // The precision is ignored for the integers.
+    assert_eq!(generate("main = {N:.2}", &ctx), "5");
+}
+
+#[test]
+fn test_format_float() {
+    let ctx = context(vec![
+        ("PRICE", ContextValue::from(1234.5)),
+        ("NEG", ContextValue::from(-0.125)),
+        ("NAN", ContextValue::from(f64::NAN)),
+        ("INF", ContextValue::from(f64::NEG_INFINITY)),
+    ]);
+    assert_eq!(generate("main = {PRICE}", &ctx), "1234.5");
+    assert_eq!(generate("main = {PRICE:.2}", &ctx), "1234.50");
+    assert_eq!(generate("main = {PRICE:,.2}", &ctx), "1,234.50");
+    assert_eq!(generate("main = {PRICE:.0}", &ctx), "1234");
+    assert_eq!(generate("main = {PRICE:010.1}", &ctx), "00001234.5");
+    assert_eq!(generate("main = {NEG:.2}", &ctx), "-0.12");
+    assert_eq!(generate("main = {NEG:+08.3}", &ctx), "-000.125");
+    assert_eq!(generate("main = {NAN:.2}", &ctx), "NaN");
+    assert_eq!(generate("main = {INF:.2}", &ctx), "-inf");
+}
+
+#[test]
+fn test_format_locale() {
+    let ctx = context(vec![
+        ("PRICE", ContextValue::from(1234567.891)),
+        ("N", ContextValue::from(1234567)),
+    ]);
+    let mut ph: Generator = "main = {N:,} {PRICE:,.2} {PRICE:.1}".parse().unwrap();
+    assert_eq!(ph.locale(), &Locale::default());
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "1,234,567 1,234,567.89 1234567.9"
+    );
+    ph.set_locale(Locale::from_tag("de_DE").unwrap());
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "1.234.567 1.234.567,89 1234567,9"
+    );
+    ph.set_locale(Locale::from_tag("de-CH").unwrap());
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "1\u{2019}234\u{2019}567 1\u{2019}234\u{2019}567.89 1234567.9"
+    );
+    ph.set_locale(Locale::from_tag("EN-in").unwrap());
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "12,34,567 12,34,567.89 1234567.9"
+    );
+    ph.set_locale(Locale::new("/", "_").with_group_sizes(4, 0));
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "123_4567 123_4567/89 1234567/9"
+    );
+    ph.set_locale(Locale::new(".", ",").with_group_sizes(0, 0));
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "1234567 1234567.89 1234567.9"
+    );
+}
+
+#[test]
+fn test_format_locale_from_tag() {
+    assert_eq!(Locale::from_tag("en"), Some(Locale::default()));
+    assert_eq!(Locale::from_tag("ja-JP"), Some(Locale::default()));
+    assert_eq!(Locale::from_tag("pt-BR").unwrap().decimal_separator(), ",");
+    assert_eq!(Locale::from_tag("pt-BR").unwrap().grouping_separator(), ".");
+    assert_eq!(
+        Locale::from_tag("pt-PT").unwrap().grouping_separator(),
+        "\u{a0}"
+    );
+    assert_eq!(Locale::from_tag("fr-CA").unwrap().decimal_separator(), ",");
+    assert_eq!(Locale::from_tag("hi").unwrap().secondary_group(), 2);
+    assert_eq!(Locale::from_tag("xx"), None);
+    assert_eq!(Locale::from_tag(""), None);
+}
+
+#[test]
+fn test_format_text() {
+    let ctx = context(vec![
+        ("NAME", ContextValue::from("Alexander")),
+        ("MEMBER", ContextValue::list(["Bob"])),
+    ]);
+    assert_eq!(generate("main = [{NAME:.4}]", &ctx), "[Alex]");
+    assert_eq!(generate("main = [{NAME:12}]", &ctx), "[Alexander   ]");
+    assert_eq!(generate("main = [{NAME:>12.4}]", &ctx), "[        Alex]");
+    assert_eq!(generate("main = [{MEMBER:-^7}]", &ctx), "[--Bob--]");
+    // The text that is a decimal number is padded with "0" as a number.
+    let ctx = ExtContext::from([
+        ("N".to_string(), "5".to_string()),
+        ("M".to_string(), "-1.5".to_string()),
+        ("P".to_string(), "+.5".to_string()),
+        ("T".to_string(), "a5".to_string()),
+    ]);
+    let mut ph: Generator = "main = [{N:03}][{M:06}][{P:05}][{T:03}][{N:3}][{N:<03}][{M:03.2}]"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        ph.generate_with_context(&ctx),
+        "[005][-001.5][+00.5][a5 ][5  ][5  ][-01]"
+    );
+}
+
+#[test]
+fn test_format_rule() {
+    let ctx = context(vec![]);
+    assert_eq!(generate("main = [{A:>5}]\nA = abc", &ctx), "[  abc]");
+    assert_eq!(generate("main = [{_A:.2}]\n_A = abc", &ctx), "[ab]");
+}
+
+#[test]
+fn test_format_unsolved_and_invalid() {
+    let ctx = context(vec![]);
+    // The unsolved nonterminal is expanded into its name.
+    assert_eq!(generate("main = {N:03}", &ctx), "N");
+    // The invalid spec is a part of the text as before.
+    assert_eq!(generate("main = {N:x}", &ctx), "N:x");
+    assert_eq!(generate("main = {N:}", &ctx), "N:");
+    assert_eq!(generate("main = {N:3.}", &ctx), "N:3.");
+    assert_eq!(generate("main = {N:99999}", &ctx), "N:99999");
+    assert_eq!(generate("main = {w:NAME}", &ctx), "w:NAME");
+    assert_eq!(generate("main = {A B:3}", &ctx), "A B:3");
+}
+
+#[test]
+fn test_format_context_weight_by_number() {
+    let ph: Generator = r#"main = sunny | "rainy" {w:RAIN}"#.parse().unwrap();
+    let ctx = context(vec![("RAIN", ContextValue::from(3))]);
+    assert_eq!(ph.probability_of("rainy", &ctx).unwrap(), 0.75);
+    let ctx = context(vec![("RAIN", ContextValue::from(1.0))]);
+    assert_eq!(ph.probability_of("rainy", &ctx).unwrap(), 0.5);
+}
+
+#[test]
+fn test_format_trace() {
+    let mut ph: Generator = "main = {N:03}".parse().unwrap();
+    let ctx = context(vec![("N", ContextValue::from(7))]);
+    let (s, trace) = ph.generate_traced_with_context(&ctx);
+    assert_eq!(s, "007");
+    assert_eq!(trace.root().unwrap().context_keys(), ["N"]);
+}
+
+#[test]
+fn test_format_binary() {
+    let mut syntax: Syntax = "main = {A:>4}{N:03}{_B:.1}\nA = ab\n_B = cd"
+        .parse()
+        .unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let restored: Syntax = Syntax::from_bytes(&syntax.to_bytes()).unwrap();
+    let ctx = context(vec![("N", ContextValue::from(7))]);
+    let mut rng: FastrandRng = RandomNumberGenerator::new();
+    assert_eq!(restored.generate_with_rng(&ctx, &mut rng), "  ab007c");
+}
+
+#[test]
+fn test_format_length() {
+    let mut ph: Generator = "main = {A:5}|{N:08}\nA = ab | abcdefg".parse().unwrap();
+    let ctx = context(vec![("N", ContextValue::from(7))]);
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len_and_context(5, LengthUnit::Chars, &ctx),
+            Some("ab   ".to_string())
+        );
+    }
+    let range = ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(range.min(), 1);
+    assert_eq!(range.max(), 7);
+    let bounds = ExtLengthBounds::from([("N".to_string(), LengthRange::new(1, 3))]);
+    let range = ph.length_range(LengthUnit::Chars, &bounds);
+    assert_eq!(range.min(), 5);
+    assert_eq!(range.max(), 8);
+}
+
+#[test]
+fn test_format_static() {
+    let ph: Generator = "main = {N:03}|{L:>3}".parse().unwrap();
+    let ctx = context(vec![
+        ("N", ContextValue::from(7)),
+        ("L", ContextValue::list(["a", "b"])),
+    ]);
+    assert_eq!(ph.nth(0, &ctx), Some("007".to_string()));
+    assert_eq!(ph.nth(1, &ctx), Some("  a".to_string()));
+    assert_eq!(ph.probability_of("007", &ctx).unwrap(), 0.5);
+}
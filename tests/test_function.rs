@@ -0,0 +1,200 @@
+//! Tests for the functions called in the phrase syntax
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tphrase::*;
+
+fn generator() -> Generator {
+    let mut ph: Generator = Generator::new();
+    ph.register_function("join", |args, _context, _rng| args.join("+"));
+    ph.register_function("lookup", |args, context, _rng| {
+        args.iter()
+            .map(|a| context.get(a).map_or("?".to_string(), |v| v.to_string()))
+            .collect::<Vec<_>>()
+            .join(",")
+    });
+    ph.register_function("dice", |_args, _context, rng| {
+        ((rng.next() * 6.0) as u32 + 1).to_string()
+    });
+    ph
+}
+
+#[test]
+fn test_function_call() {
+    let mut ph = generator();
+    ph.add(
+        "main = [{@join a b  c}][{@join}][{@join\n x }]"
+            .parse()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(ph.generate(), "[a+b+c][][x]");
+}
+
+#[test]
+fn test_function_context() {
+    let mut ph = generator();
+    ph.add("main = {@lookup ITEM_ID OTHER}".parse().unwrap())
+        .unwrap();
+    let ctx = ExtContext::from([("ITEM_ID".to_string(), "sword".to_string())]);
+    assert_eq!(ph.generate_with_context(&ctx), "sword,?");
+}
+
+#[test]
+fn test_function_in_rules() {
+    let mut ph = generator();
+    ph.add(
+        "main = {A}|{_B}|{= {@join x}}|{N:>4}\nA = {@join a}\n_B = {@join b}"
+            .parse()
+            .unwrap(),
+    )
+    .unwrap();
+    let ctx = ExtContext::new();
+    let all: Vec<_> = ph.iter_all(&ctx).collect();
+    assert_eq!(all, ["a", "b", "x", "N"]);
+}
+
+#[test]
+fn test_function_not_registered() {
+    let mut ph = generator();
+    let err = ph
+        .add(
+            "main = {@foo} {A}\nA = {@bar 1}\nB = {@foo}"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        *err.error_messages(),
+        [
+            r#"The function "bar" is not registered."#,
+            r#"The function "foo" is not registered."#,
+        ]
+    );
+    assert_eq!(ph.number_of_syntax(), 0);
+}
+
+#[test]
+fn test_function_unregister() {
+    let mut ph = generator();
+    ph.add("main = {@join a b}".parse().unwrap()).unwrap();
+    assert!(ph.unregister_function("join"));
+    assert!(!ph.unregister_function("join"));
+    assert_eq!(ph.generate(), "@join a b");
+}
+
+#[test]
+fn test_function_not_a_call() {
+    let mut ph = generator();
+    ph.add("main = {@}{@ join a}{@a-b}".parse().unwrap())
+        .unwrap();
+    assert_eq!(ph.generate(), "@@ join a@a-b");
+}
+
+#[test]
+fn test_function_replace() {
+    let mut ph = generator();
+    ph.register_function("join", |args, _context, _rng| args.join("-"));
+    ph.add("main = {@join a b}".parse().unwrap()).unwrap();
+    assert_eq!(ph.generate(), "a-b");
+}
+
+#[test]
+fn test_function_rng() {
+    let syntax = "main = {@dice}{@dice}{@dice}{@dice}";
+    let mut ph1 = generator();
+    ph1.add(syntax.parse().unwrap()).unwrap();
+    let mut ph2 = generator();
+    ph2.add(syntax.parse().unwrap()).unwrap();
+    ph1.set_rng_state(FastrandRng::from_seed(7).state());
+    ph2.set_rng_state(FastrandRng::from_seed(7).state());
+    let mut results = std::collections::HashSet::new();
+    for _ in 0..20 {
+        let s = ph1.generate();
+        assert_eq!(s, ph2.generate());
+        assert_eq!(s.len(), 4);
+        assert!(s.chars().all(|c| ('1'..='6').contains(&c)));
+        results.insert(s);
+    }
+    assert!(results.len() > 1);
+    assert_eq!(ph1.generate_for_key(b"k"), ph2.generate_for_key(b"k"));
+    // The call is seeded by itself without the random numbers.
+    let ctx = ExtContext::new();
+    assert_eq!(ph1.nth(0, &ctx), ph2.nth(0, &ctx));
+    let s = ph1.nth(0, &ctx).unwrap();
+    assert_eq!(s[0..1], s[1..2]);
+}
+
+#[test]
+fn test_function_calls_count() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let c = Arc::clone(&count);
+    let mut ph: Generator = Generator::new();
+    ph.register_function("count", move |_args, _context, _rng| {
+        c.fetch_add(1, Ordering::SeqCst).to_string()
+    });
+    ph.add("main = {@count}|x".parse().unwrap()).unwrap();
+    ph.equalize_chance(true);
+    let mut n = 0;
+    for _ in 0..20 {
+        if ph.generate() != "x" {
+            n += 1;
+        }
+    }
+    assert_eq!(count.load(Ordering::SeqCst), n);
+}
+
+#[test]
+fn test_function_binary() {
+    let mut syntax: Syntax = "main = {@join a b}".parse().unwrap();
+    syntax.bind_syntax("main").unwrap();
+    let restored: Syntax = Syntax::from_bytes(&syntax.to_bytes()).unwrap();
+    let mut ph = generator();
+    ph.add(restored).unwrap();
+    assert_eq!(ph.generate(), "a+b");
+}
+
+#[test]
+fn test_function_length() {
+    let mut ph = generator();
+    ph.add("main = ab{@join x}".parse().unwrap()).unwrap();
+    let range = ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!((range.min(), range.max()), (2, 2));
+    assert!(range.is_approximate());
+    let bounds = ExtLengthBounds::from([("@join".to_string(), LengthRange::new(1, 5))]);
+    let range = ph.length_range(LengthUnit::Chars, &bounds);
+    assert_eq!((range.min(), range.max()), (3, 7));
+    assert_eq!(
+        ph.generate_with_max_len(3, LengthUnit::Chars),
+        Some("abx".to_string())
+    );
+    assert_eq!(ph.generate_with_max_len(2, LengthUnit::Chars), None);
+}
+
+#[test]
+fn test_function_send_sync() {
+    fn check<T: Send + Sync + Clone + std::fmt::Debug>(_: &T) {}
+    let ph = generator();
+    check(&ph);
+    let ph2 = ph.clone();
+    assert!(format!("{:?}", ph2).contains("\"dice\", \"join\", \"lookup\""));
+}
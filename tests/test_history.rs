@@ -0,0 +1,137 @@
+//! Test for the history of the generated phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+mod utils;
+use utils::*;
+
+#[test]
+fn test_history_phrase_window() {
+    let mut ph: Generator = Generator::with_seed(1);
+    ph.add("main = A | B | C | D".parse().unwrap()).unwrap();
+    ph.set_history_window(3);
+    let mut last: Vec<String> = Vec::new();
+    for _ in 0..100 {
+        let s = ph.generate();
+        assert!(!last.contains(&s));
+        last.push(s);
+        if last.len() > 3 {
+            last.remove(0);
+        }
+    }
+    let recent: Vec<_> = ph.history().recent_phrases().collect();
+    assert_eq!(recent, last);
+}
+
+#[test]
+fn test_history_all_in_window() {
+    let mut ph: Generator = Generator::with_seed(2);
+    ph.add("main = A | B".parse().unwrap()).unwrap();
+    ph.set_history_window(5);
+    for _ in 0..10 {
+        let s = ph.generate();
+        assert!(s == "A" || s == "B");
+    }
+    assert_eq!(ph.history().recent_phrases().count(), 5);
+}
+
+#[test]
+fn test_history_renormalized() {
+    // The same option can't be selected twice in a row.
+    let mut ph: Generator = Generator::with_seed(3);
+    ph.add("main = A | B | \"C\" 2".parse().unwrap()).unwrap();
+    ph.set_option_history_window("main", 1);
+    let mut prev = String::new();
+    let mut count_c = 0;
+    for _ in 0..10000 {
+        let s = ph.generate();
+        assert_ne!(s, prev);
+        if s == "C" {
+            count_c += 1;
+        }
+        prev = s;
+    }
+    // "C" follows "A" or "B" by 2/3, so the stationary chance of "C" is 0.4.
+    let p = count_c as f64 / 10000.0;
+    assert!((p - 0.4).abs() < 0.02);
+}
+
+#[test]
+fn test_history_option_window_zero_weight() {
+    let mut ph: Generator<ZeroNG> = Generator::new();
+    ph.add("main = {A}{A}{A}\nA = a | b | c".parse().unwrap())
+        .unwrap();
+    ph.set_option_history_window("A", 2);
+    assert_eq!(ph.generate(), "abc");
+    assert_eq!(ph.generate(), "abc");
+    let recent: Vec<_> = ph.history().recent_options("A").collect();
+    assert_eq!(recent, [1, 2]);
+    ph.set_option_history_window("A", 0);
+    assert_eq!(ph.generate(), "aaa");
+}
+
+#[test]
+fn test_history_traced() {
+    let mut ph: Generator = Generator::with_seed(4);
+    ph.add("main = {A}{A}\nA = a | b".parse().unwrap()).unwrap();
+    ph.set_option_history_window("A", 1);
+    for _ in 0..10 {
+        let (s, trace) = ph.generate_traced();
+        assert!(s == "ab" || s == "ba");
+        assert_eq!(trace.root().unwrap().post_gsub(), s);
+    }
+}
+
+#[test]
+fn test_history_bytes() {
+    let mut ph: Generator = Generator::with_seed(5);
+    ph.add("main = {A}{B}\nA = a | b | c\nB = x | y".parse().unwrap())
+        .unwrap();
+    ph.set_history_window(4);
+    ph.set_option_history_window("A", 2);
+    ph.set_option_history_window("B", 1);
+    for _ in 0..3 {
+        let _ = ph.generate();
+    }
+    let data = ph.history().to_bytes();
+    let history = History::from_bytes(&data).unwrap();
+    assert_eq!(&history, ph.history());
+    assert_eq!(history.recent_phrases().count(), 3);
+    assert_eq!(history.recent_options("A").count(), 2);
+    assert_eq!(history.recent_options("B").count(), 1);
+    assert_eq!(history.recent_options("C").count(), 0);
+
+    let mut ph2: Generator = Generator::with_seed(5);
+    ph2.add("main = {A}{B}\nA = a | b | c\nB = x | y".parse().unwrap())
+        .unwrap();
+    ph2.set_history(history);
+    assert_eq!(ph2.history(), ph.history());
+
+    assert_eq!(
+        History::from_bytes(&data[..data.len() - 1])
+            .unwrap_err()
+            .error_message(),
+        "The data size is wrong."
+    );
+    let mut h = History::new();
+    h.clear();
+    assert_eq!(History::from_bytes(&h.to_bytes()).unwrap(), h);
+}
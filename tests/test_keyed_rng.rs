@@ -0,0 +1,131 @@
+//! Test for KeyedRng and the generation derived from a key
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+#[test]
+fn test_keyed_rng_range() {
+    let mut rng = KeyedRng::from_key(b"range");
+    for _ in 1..=10000 {
+        let a = rng.next();
+        assert!((0.0..1.0).contains(&a));
+    }
+}
+
+#[test]
+fn test_keyed_rng_pinned_sequence() {
+    // These values must not change in any version.
+    let mut rng = KeyedRng::from_seed(0);
+    assert_eq!(rng.next(), 0.8833108082136426);
+    assert_eq!(rng.next(), 0.43152799704850997);
+    assert_eq!(rng.next(), 0.026433771592597743);
+
+    let mut rng = KeyedRng::from_key(b"");
+    assert_eq!(rng.next(), 0.7636945250957473);
+    assert_eq!(rng.next(), 0.06269613950347463);
+    assert_eq!(rng.next(), 0.3311103232963081);
+
+    let mut rng = KeyedRng::from_key(b"tphrase");
+    assert_eq!(rng.next(), 0.9248145081268119);
+    assert_eq!(rng.next(), 0.936594171969264);
+    assert_eq!(rng.next(), 0.31194184608402264);
+}
+
+#[test]
+fn test_keyed_rng_state() {
+    let mut rng = KeyedRng::from_key(b"state");
+    let state = rng.state();
+    let a = rng.next();
+    rng.set_state(state);
+    assert_eq!(rng.next(), a);
+}
+
+#[test]
+fn test_generate_for_key_pinned() {
+    // These phrases must not change as long as SELECTION_ALGORITHM_VERSION is 3.
+    assert_eq!(SELECTION_ALGORITHM_VERSION, 3);
+    let ph: Generator = r#"
+        main = {PREFIX}{SUFFIX} the {TITLE}
+        PREFIX = Gor | Mag | Tur | Zog
+        SUFFIX = ash | uk | dug | nar
+        TITLE := Brave | Cruel | "{PREFIX}-Slayer" 3
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(ph.generate_for_key(b"orc-1"), "Magash the Brave");
+    assert_eq!(ph.generate_for_key(b"orc-2"), "Zognar the Brave");
+    assert_eq!(ph.generate_for_key(b"orc-3"), "Maguk the Mag-Slayer");
+    assert_eq!(ph.generate_for_key(b"orc-4"), "Turash the Mag-Slayer");
+    assert_eq!(ph.generate_for_key(b"orc-5"), "Turnar the Cruel");
+    assert_eq!(ph.generate_for_key(b""), "Zogash the Brave");
+}
+
+#[test]
+fn test_generate_for_key_independent_of_rng() {
+    let mut ph: Generator = r#"
+        main = {A}{A}{A}
+        A = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+    .parse()
+    .unwrap();
+    let s = ph.generate_for_key(b"key");
+    for _ in 0..10 {
+        let _ = ph.generate();
+        assert_eq!(ph.generate_for_key(b"key"), s);
+    }
+    let ph2 = ph.clone();
+    assert_eq!(ph2.generate_for_key(b"key"), s);
+}
+
+#[test]
+fn test_generate_for_key_independent_of_temperature() {
+    let mut ph: Generator = r#"
+        main = {A}{A}{A}
+        A = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | "9" 10
+    "#
+    .parse()
+    .unwrap();
+    let keys: Vec<String> = (0..20).map(|i| format!("key-{}", i)).collect();
+    let phrases: Vec<String> = keys
+        .iter()
+        .map(|k| ph.generate_for_key(k.as_bytes()))
+        .collect();
+    for t in [0.1, 0.5, 2.0, 10.0] {
+        ph.set_temperature(t).unwrap();
+        for (k, s) in keys.iter().zip(phrases.iter()) {
+            assert_eq!(&ph.generate_for_key(k.as_bytes()), s);
+        }
+    }
+}
+
+#[test]
+fn test_generate_for_key_with_context() {
+    let ph: Generator = r#"
+        main = {A} {NAME}
+        A = a | b | c | d
+    "#
+    .parse()
+    .unwrap();
+    let context = ExtContext::from([("NAME".to_string(), "Alice".to_string())]);
+    let s = ph.generate_for_key_with_context(b"key", &context);
+    assert!(s.ends_with(" Alice"));
+    assert_eq!(ph.generate_for_key(b"key"), s.replace("Alice", "NAME"));
+}
@@ -0,0 +1,184 @@
+//! Test for the length range analysis
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+#[test]
+fn test_length_range_literals_and_expansions() {
+    let ph: Generator = r#"
+        main = {A}-{B} | x
+        A = a | aaa
+        B = {= b | bbbbb }
+    "#
+    .parse()
+    .unwrap();
+    let range = ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(range, LengthRange::new(1, 9));
+    assert!(!range.is_approximate());
+    let ranges = ph.length_ranges(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(ranges.len(), 3);
+    assert_eq!(ranges["main"], LengthRange::new(1, 9));
+    assert_eq!(ranges["A"], LengthRange::new(1, 3));
+    assert_eq!(ranges["B"], LengthRange::new(1, 5));
+}
+
+#[test]
+fn test_length_range_matches_enumeration() {
+    let ph: Generator = r#"
+        main = {A} {B} | {B}
+        A = one | three | eleven
+        B = {= x | yy } z | long word
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    let lens: Vec<usize> = ph.iter_all(&no_context).map(|s| s.len()).collect();
+    let range = ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(range.min(), *lens.iter().min().unwrap());
+    assert_eq!(range.max(), *lens.iter().max().unwrap());
+}
+
+#[test]
+fn test_length_range_ext_bounds() {
+    let ph: Generator = "main = [{NAME}] | [{UNKNOWN}]".parse().unwrap();
+    let bounds = ExtLengthBounds::from([("NAME".to_string(), LengthRange::new(0, 30))]);
+    assert_eq!(
+        ph.length_range(LengthUnit::Chars, &bounds),
+        LengthRange::new(2, 32)
+    );
+    // Without the bounds, the name is measured.
+    assert_eq!(
+        ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new()),
+        LengthRange::new(6, 9)
+    );
+    let bounds = ExtLengthBounds::from([(
+        "NAME".to_string(),
+        LengthRange::exact(4).with_approximate(true),
+    )]);
+    assert!(ph.length_range(LengthUnit::Chars, &bounds).is_approximate());
+}
+
+#[test]
+fn test_length_range_gsub() {
+    let ph: Generator = r#"
+        main = {A} | b
+        A = aaa ~ /a/xx/g
+    "#
+    .parse()
+    .unwrap();
+    let ranges = ph.length_ranges(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(ranges["A"], LengthRange::new(3, 3).with_approximate(true));
+    assert_eq!(
+        ranges["main"],
+        LengthRange::new(1, 3).with_approximate(true)
+    );
+    assert_eq!(ranges["main"].to_string(), "1..=3 (approximate)");
+}
+
+#[test]
+fn test_length_range_zero_weight() {
+    let ph: Generator = r#"
+        main = "short" | "very very long" 0
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(
+        ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new()),
+        LengthRange::exact(5)
+    );
+}
+
+#[test]
+fn test_length_range_multiple_syntaxes() {
+    let mut ph: Generator = "main = a".parse().unwrap();
+    let _ = ph.add("main = abcdef".parse().unwrap());
+    assert_eq!(
+        ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new()),
+        LengthRange::new(1, 6)
+    );
+}
+
+#[test]
+fn test_length_range_empty() {
+    let ph: Generator = Generator::new();
+    assert_eq!(
+        ph.length_range(LengthUnit::Chars, &ExtLengthBounds::new()),
+        LengthRange::exact(3)
+    );
+    assert!(ph
+        .length_ranges(LengthUnit::Chars, &ExtLengthBounds::new())
+        .is_empty());
+}
+
+#[test]
+fn test_length_range_syntax() {
+    let mut syntax: Syntax = r#"
+        main = {A}{A}
+        A = a | bb
+        other = {A}ccc
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(
+        syntax.length_range(LengthUnit::Chars, &ExtLengthBounds::new()),
+        None
+    );
+    assert!(syntax
+        .length_ranges(LengthUnit::Chars, &ExtLengthBounds::new())
+        .is_empty());
+    syntax.bind_syntax("main").unwrap();
+    assert_eq!(
+        syntax.length_range(LengthUnit::Chars, &ExtLengthBounds::new()),
+        Some(LengthRange::new(2, 4))
+    );
+    let ranges = syntax.length_ranges(LengthUnit::Chars, &ExtLengthBounds::new());
+    assert_eq!(ranges.len(), 2);
+    assert!(!ranges.contains_key("other"));
+}
+
+#[test]
+fn test_length_range_struct() {
+    let range = LengthRange::new(5, 2);
+    assert_eq!((range.min(), range.max()), (5, 5));
+    assert_eq!(
+        format!("{:?}", LengthRange::new(1, 2)),
+        "LengthRange { min: 1, max: 2, approximate: false }"
+    );
+    assert_eq!(LengthRange::new(1, 2).to_string(), "1..=2");
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn test_length_range_display_width() {
+    let ph: Generator = "main = 日本{A}\nA = 語 | go".parse().unwrap();
+    let range = ph.length_range(LengthUnit::DisplayWidth, &ExtLengthBounds::new());
+    assert_eq!((range.min(), range.max()), (6, 6));
+    assert!(range.is_approximate());
+}
+
+#[cfg(feature = "unicode-segmentation")]
+#[test]
+fn test_length_range_graphemes() {
+    let ph: Generator = "main = e\u{301}{A}\nA = x | yz".parse().unwrap();
+    let range = ph.length_range(LengthUnit::Graphemes, &ExtLengthBounds::new());
+    assert_eq!((range.min(), range.max()), (2, 3));
+    assert!(range.is_approximate());
+}
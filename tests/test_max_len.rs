@@ -0,0 +1,205 @@
+//! Test for Generator::generate_with_max_len()
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::collections::HashMap;
+use tphrase::*;
+
+#[test]
+fn test_max_len_selects_fitting_phrases() {
+    let mut ph: Generator = r#"
+        main = {A}{B}
+        A = a | aa | aaa
+        B = b | bbbb
+    "#
+    .parse()
+    .unwrap();
+    let mut count = HashMap::new();
+    for _ in 0..3000 {
+        let s = ph.generate_with_max_len(3, LengthUnit::Chars).unwrap();
+        assert!(s.chars().count() <= 3);
+        *count.entry(s).or_insert(0) += 1;
+    }
+    assert_eq!(count.len(), 2);
+    assert!(count["ab"] > 1200 && count["ab"] < 1800);
+    assert!(count["aab"] > 1200 && count["aab"] < 1800);
+}
+
+#[test]
+fn test_max_len_follows_weights() {
+    let mut ph: Generator = r#"
+        main = "{A}" | "{B}" 3 | "too long"
+        A = x | y
+        B = z
+    "#
+    .parse()
+    .unwrap();
+    let mut count = HashMap::new();
+    for _ in 0..4000 {
+        let s = ph.generate_with_max_len(1, LengthUnit::Chars).unwrap();
+        *count.entry(s).or_insert(0) += 1;
+    }
+    // The weights are 2 (the default), 3, and 1, so the chances are 1/5, 1/5, and 3/5 conditioned on the length.
+    assert!(count["x"] > 600 && count["x"] < 1000);
+    assert!(count["y"] > 600 && count["y"] < 1000);
+    assert!(count["z"] > 2200 && count["z"] < 2600);
+}
+
+#[test]
+fn test_max_len_none() {
+    let mut ph: Generator = "main = Hello | Good morning".parse().unwrap();
+    assert_eq!(ph.generate_with_max_len(4, LengthUnit::Chars), None);
+    assert_eq!(
+        ph.generate_with_max_len(5, LengthUnit::Chars),
+        Some("Hello".to_string())
+    );
+}
+
+#[test]
+fn test_max_len_empty_generator() {
+    let mut ph: Generator = Generator::new();
+    assert_eq!(
+        ph.generate_with_max_len(3, LengthUnit::Chars),
+        Some("nil".to_string())
+    );
+    assert_eq!(ph.generate_with_max_len(2, LengthUnit::Chars), None);
+}
+
+#[test]
+fn test_max_len_multiple_syntaxes() {
+    let mut ph: Generator = "main = short".parse().unwrap();
+    let _ = ph.add("main = much longer".parse().unwrap());
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len(5, LengthUnit::Chars),
+            Some("short".to_string())
+        );
+    }
+}
+
+#[test]
+fn test_max_len_context() {
+    let mut ph: Generator = "main = {name}! | Hi, {name}!".parse().unwrap();
+    let mut ctx = ExtContext::new();
+    let _ = ctx.insert("name".to_string(), "Alice".to_string());
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len_and_context(8, LengthUnit::Chars, &ctx),
+            Some("Alice!".to_string())
+        );
+    }
+}
+
+#[test]
+fn test_max_len_anonymous_rule() {
+    let mut ph: Generator = "main = {= a | bb }{= c | dd }".parse().unwrap();
+    for _ in 0..50 {
+        assert_eq!(
+            ph.generate_with_max_len(2, LengthUnit::Chars),
+            Some("ac".to_string())
+        );
+    }
+}
+
+#[test]
+fn test_max_len_gsub() {
+    // The gsub makes the phrase longer, so the estimation is wrong and the phrase is checked again.
+    let mut ph: Generator = r#"
+        main = {A} ~ /b/bbb/
+        A = a | b
+    "#
+    .parse()
+    .unwrap();
+    for _ in 0..50 {
+        assert_eq!(
+            ph.generate_with_max_len(1, LengthUnit::Chars),
+            Some("a".to_string())
+        );
+    }
+    assert_eq!(ph.generate_with_max_len(0, LengthUnit::Chars), None);
+}
+
+#[test]
+fn test_max_len_huge_limit() {
+    let mut ph: Generator = r#"
+        main = {A} {name} | {B}
+        A = a | aa
+        B = {= bbb | bbbb }
+    "#
+    .parse()
+    .unwrap();
+    let mut count = HashMap::new();
+    for _ in 0..1000 {
+        let s = ph
+            .generate_with_max_len(usize::MAX, LengthUnit::Chars)
+            .unwrap();
+        *count.entry(s).or_insert(0) += 1;
+    }
+    assert_eq!(count.len(), 4);
+
+    // The context values are measured to fit the limit.
+    let mut ctx = ExtContext::new();
+    let _ = ctx.insert("name".to_string(), "a long name".to_string());
+    for _ in 0..50 {
+        let s = ph
+            .generate_with_max_len_and_context(usize::MAX, LengthUnit::Chars, &ctx)
+            .unwrap();
+        assert!(s.starts_with('a') || s.starts_with('b'));
+    }
+    for _ in 0..50 {
+        let s = ph
+            .generate_with_max_len_and_context(13, LengthUnit::Chars, &ctx)
+            .unwrap();
+        assert!(s == "a a long name" || s.starts_with('b'));
+    }
+}
+
+#[test]
+fn test_length_unit() {
+    assert_eq!(LengthUnit::default(), LengthUnit::Chars);
+    assert_eq!(LengthUnit::Chars.measure(""), 0);
+    assert_eq!(LengthUnit::Chars.measure("e\u{301}"), 2);
+}
+
+#[cfg(feature = "unicode-segmentation")]
+#[test]
+fn test_length_unit_graphemes() {
+    assert_eq!(LengthUnit::Graphemes.measure("e\u{301}"), 1);
+    let mut ph: Generator = "main = e\u{301}e\u{301} | abc".parse().unwrap();
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len(2, LengthUnit::Graphemes),
+            Some("e\u{301}e\u{301}".to_string())
+        );
+    }
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn test_length_unit_display_width() {
+    assert_eq!(LengthUnit::DisplayWidth.measure("日本"), 4);
+    let mut ph: Generator = "main = 日本語 | abcd".parse().unwrap();
+    for _ in 0..20 {
+        assert_eq!(
+            ph.generate_with_max_len(5, LengthUnit::DisplayWidth),
+            Some("abcd".to_string())
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Test for the index of the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+#[test]
+fn test_nth_order() {
+    let mut ph: Generator = Generator::new();
+    ph.add(
+        r#"
+        main = {A}{_B}{X} | {A}
+        A = a | b ~ /b/B/
+        _B = 1 | 2 | 3
+    "#
+        .parse()
+        .unwrap(),
+    )
+    .unwrap();
+    ph.add("main = y | z".parse().unwrap()).unwrap();
+    let ext_context = ExtContext::from([("X".to_string(), "x".to_string())]);
+    assert_eq!(ph.combination_number(), 10);
+    let all: Vec<_> = (0..ph.combination_number())
+        .map(|k| ph.nth(k, &ext_context).unwrap())
+        .collect();
+    assert_eq!(
+        all,
+        ["a1x", "a2x", "a3x", "B1x", "B2x", "B3x", "a", "B", "y", "z"]
+    );
+    assert_eq!(ph.nth(10, &ext_context), None);
+    assert_eq!(ph.nth_derivation(10), None);
+}
+
+#[test]
+fn test_nth_rank_round_trip() {
+    let mut ph: Generator = Generator::new();
+    ph.add(
+        r#"
+        main = {A}{B}{A} | {B}
+        A = a | {B}b | c
+        B = 1 | 2 | {C}
+        C = p | q | r | s
+    "#
+        .parse()
+        .unwrap(),
+    )
+    .unwrap();
+    let no_context = ExtContext::new();
+    let n = ph.combination_number();
+    assert_eq!(n, 8 * 6 * 8 + 6);
+    let mut derivations = HashSet::new();
+    for k in 0..n {
+        let d = ph.nth_derivation(k).unwrap();
+        assert_eq!(ph.rank(&d), Some(k));
+        let phrase = ph.generate_from_derivation(&d, &no_context).unwrap();
+        assert_eq!(ph.nth(k, &no_context).unwrap(), phrase);
+        derivations.insert(d);
+    }
+    assert_eq!(derivations.len(), n);
+}
+
+#[test]
+fn test_rank_generated() {
+    let mut ph: Generator = Generator::with_seed(3);
+    ph.add("main = {A}{A}{A}\nA = a | b | c | d".parse().unwrap())
+        .unwrap();
+    let no_context = ExtContext::new();
+    for _ in 0..100 {
+        let (phrase, trace) = ph.generate_traced();
+        let k = ph.rank(&trace.derivation()).unwrap();
+        assert_eq!(ph.nth(k, &no_context).unwrap(), phrase);
+    }
+}
+
+#[test]
+fn test_rank_mismatch() {
+    let ph: Generator = "main = {A}\nA = x | y".parse().unwrap();
+    assert_eq!(ph.rank(&Derivation::new(vec![0, 0, 1])), Some(1));
+    assert_eq!(ph.rank(&Derivation::new(vec![0, 0, 2])), None);
+    assert_eq!(ph.rank(&Derivation::new(vec![1, 0, 0])), None);
+    assert_eq!(ph.rank(&Derivation::new(vec![0, 0])), None);
+    assert_eq!(ph.rank(&Derivation::new(vec![0, 0, 1, 0])), None);
+    assert_eq!(ph.rank(&Derivation::default()), None);
+}
+
+#[test]
+fn test_nth_overflow() {
+    let ph: Generator = r#"
+        main = {A}{A}{A}{A}{A}{A}{B}
+        A = {C}{C}{C}{C}{C}{C}{C}{C}
+        B = x | y
+        C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.combination_number_exact(), None);
+    let zeros = "0".repeat(48);
+    assert_eq!(ph.nth(0, &no_context).unwrap(), zeros.clone() + "x");
+    assert_eq!(ph.nth(1, &no_context).unwrap(), zeros.clone() + "y");
+    assert_eq!(ph.nth(25, &no_context).unwrap(), "0".repeat(46) + "12y");
+    for k in [0, 1, 12345, usize::MAX] {
+        let d = ph.nth_derivation(k).unwrap();
+        assert_eq!(ph.rank(&d), Some(k));
+    }
+    let mut nines = vec![0, 0];
+    for _ in 0..6 {
+        nines.push(0);
+        nines.extend_from_slice(&[9; 8]);
+    }
+    nines.push(1);
+    assert_eq!(
+        ph.generate_from_derivation(&Derivation::new(nines.clone()), &no_context)
+            .unwrap(),
+        "9".repeat(48) + "y"
+    );
+    assert_eq!(ph.rank(&Derivation::new(nines)), None);
+}
+
+#[test]
+fn test_nth_exact() {
+    let ph: Generator = r#"
+        main = {A}{A}{A}{A}{B}
+        A = {C}{C}{C}{C}{C}{C}{C}{C}
+        B = x | y
+        C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    let n = 2 * 10u128.pow(32);
+    assert_eq!(ph.combination_number_exact(), Some(n));
+    assert_eq!(ph.combination_number(), usize::MAX);
+    assert_eq!(
+        ph.nth_exact(n - 1, &no_context).unwrap(),
+        "9".repeat(32) + "y"
+    );
+    assert_eq!(ph.nth_exact(n, &no_context), None);
+    assert_eq!(ph.nth_derivation_exact(n), None);
+    for k in [0, 1, usize::MAX as u128, usize::MAX as u128 + 1, n - 1] {
+        let d = ph.nth_derivation_exact(k).unwrap();
+        assert_eq!(ph.rank_exact(&d), Some(k));
+        assert_eq!(ph.rank(&d), usize::try_from(k).ok());
+        if let Ok(k) = usize::try_from(k) {
+            assert_eq!(ph.nth(k, &no_context), ph.nth_exact(k as u128, &no_context));
+        }
+    }
+}
@@ -185,6 +185,55 @@ fn test_parse_text_quoted_with_number_decimal_only() {
     assert!(err.error_messages()[0].contains("A number is expected. (\".\" is not a number.)"));
 }
 
+#[test]
+fn test_parse_text_quoted_with_context_weight() {
+    let mut ph: Generator = r#"
+        main = "text1" 0 | "text2" {w:W2} | 'text3'{w:W3}
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(ph.weight(), 2.0);
+    let context = ExtContext::from([("W3".to_string(), "1".to_string())]);
+    assert_eq!(ph.generate_with_context(&context), "text3");
+}
+
+#[test]
+fn test_parse_text_quoted_with_context_weight_no_colon() {
+    let ph: Result<Generator, _> = r#"
+        main = text1 | "text2" {w}
+    "#
+    .parse();
+    assert!(ph.is_err());
+    let err = ph.err().unwrap();
+    assert_eq!(err.error_messages().len(), 1);
+    assert!(err.error_messages()[0].contains("\":\" is expected after \"{w\"."));
+}
+
+#[test]
+fn test_parse_text_quoted_with_context_weight_unclosed() {
+    let ph: Result<Generator, _> = r#"
+        main = text1 | "text2" {w:W2
+    "#
+    .parse();
+    assert!(ph.is_err());
+    let err = ph.err().unwrap();
+    assert_eq!(err.error_messages().len(), 1);
+    assert!(err.error_messages()[0].contains("The end of the context weight \"}\" is expected."));
+}
+
+#[test]
+fn test_parse_text_quoted_with_context_weight_invalid() {
+    let long = "(".repeat(128) + "W2" + &")".repeat(127);
+    for expr in ["", "W2 W3", "W2 +", "(W2", "W2)", "W2 % 2", "{W2}", &long] {
+        let ph: Result<Generator, _> = format!("main = text1 | \"text2\" {{w:{}}}\n", expr).parse();
+        assert!(ph.is_err(), "{}", expr);
+        let err = ph.err().unwrap();
+        assert_eq!(err.error_messages().len(), 1);
+        assert!(err.error_messages()[0]
+            .contains("The context weight must be a nonterminal or a simple expression."));
+    }
+}
+
 #[test]
 fn test_parse_text_non_quoted() {
     let mut ph: Generator<ZeroNG> = r#"
@@ -0,0 +1,130 @@
+//! Test for the probability of the phrases
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+mod utils;
+use utils::*;
+
+const SYNTAX: &str = r#"
+    main = {A} {B} | "{C}" 2
+    A = a1 | "a2" 3
+    B := b1 | {C}
+    C = c1 | c2 | c3
+"#;
+
+#[test]
+fn test_probability_of_derivation() {
+    let ph: Generator = SYNTAX.parse().unwrap();
+    let no_context = ExtContext::new();
+    let p = |v: Vec<usize>| {
+        ph.probability_of_derivation(&Derivation::new(v), &no_context)
+            .unwrap()
+    };
+    // The weight of "{A} {B}" is 4 * 4 = 16, and the weight of "{C}" is 2.
+    assert_eq!(p(vec![0, 1, 0]), 2.0 / 18.0 / 3.0);
+    assert_eq!(p(vec![0, 1, 2]), 2.0 / 18.0 / 3.0);
+    assert_eq!(p(vec![0, 0, 0, 0]), 16.0 / 18.0 * 0.25 * 0.5);
+    assert_eq!(p(vec![0, 0, 1, 1, 2]), 16.0 / 18.0 * 0.75 * 0.5 / 3.0);
+    assert!(ph
+        .probability_of_derivation(&Derivation::new(vec![0, 2]), &no_context)
+        .is_err());
+}
+
+#[test]
+fn test_probability_sum() {
+    let ph: Generator = SYNTAX.parse().unwrap();
+    let no_context = ExtContext::new();
+    let sum: f64 = (0..ph.combination_number())
+        .map(|k| {
+            ph.probability_of_derivation(&ph.nth_derivation(k).unwrap(), &no_context)
+                .unwrap()
+        })
+        .sum();
+    assert!((sum - 1.0).abs() < 1e-12);
+    let sum: f64 = ph
+        .iter_all(&no_context)
+        .distinct()
+        .map(|s| ph.probability_of(&s, &no_context).unwrap())
+        .sum();
+    assert!((sum - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_probability_of_ambiguous() {
+    let ph: Generator = "main = {A}{B}\nA = x | xy\nB = yz | z".parse().unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.probability_of("xyz", &no_context).unwrap(), 0.5);
+    assert_eq!(ph.probability_of("xyyz", &no_context).unwrap(), 0.25);
+    assert_eq!(ph.probability_of("xz", &no_context).unwrap(), 0.25);
+    assert_eq!(ph.probability_of("x", &no_context).unwrap(), 0.0);
+}
+
+#[test]
+fn test_probability_of_generator_equalized() {
+    let mut ph: Generator = Generator::new();
+    ph.add("main = a | b | c".parse().unwrap()).unwrap();
+    ph.add("main = d".parse().unwrap()).unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.probability_of("a", &no_context).unwrap(), 0.25);
+    assert_eq!(ph.probability_of("d", &no_context).unwrap(), 0.25);
+    ph.equalize_chance(true);
+    assert_eq!(ph.probability_of("a", &no_context).unwrap(), 0.5 / 3.0);
+    assert_eq!(ph.probability_of("d", &no_context).unwrap(), 0.5);
+}
+
+#[test]
+fn test_probability_matches_generation() {
+    let mut ph: Generator = Generator::with_seed(11);
+    ph.add(SYNTAX.parse().unwrap()).unwrap();
+    let no_context = ExtContext::new();
+    let mut dist = TextDistribution::new();
+    for s in ph.iter_all(&no_context).distinct() {
+        let p = ph.probability_of(&s, &no_context).unwrap();
+        dist.insert(s, p);
+    }
+    assert!(check_distribution(&mut ph, 100000, &dist, 0.01));
+}
+
+#[test]
+fn test_probability_of_limit() {
+    let ph: Generator = r#"
+        main = {A}{A}{A}{A}{B}
+        A = {C}{C}{C}
+        B = 0 | 1
+        C = 0 | 1 | 2 | 3
+    "#
+    .parse()
+    .unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.combination_number_exact(), Some(ENUMERATION_LIMIT * 2));
+    assert_eq!(ph.probability_of("0000000000000", &no_context), None);
+
+    let ph: Generator = r#"
+        main = {A}{A}{A}
+        A = {B}{B}{B}{B}
+        B = {C}{C}{C}{C}
+        C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(ph.combination_number_exact(), None);
+    assert_eq!(ph.probability_of("0", &no_context), None);
+}
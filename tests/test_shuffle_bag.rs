@@ -0,0 +1,126 @@
+//! Test for the avoidance of the repetition
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+use std::collections::HashSet;
+
+fn digits(n: usize) -> String {
+    let mut s = "main = ".to_string();
+    for i in 0..n {
+        if i > 0 {
+            s += " | ";
+        }
+        s += &i.to_string();
+    }
+    return s;
+}
+
+#[test]
+fn test_avoid_repeat_whole() {
+    for n in [1, 2, 3, 4, 5, 7, 16, 17, 100] {
+        let mut ph: Generator = Generator::with_seed(n as u64);
+        ph.add(digits(n).parse().unwrap()).unwrap();
+        ph.avoid_repeat(Some(1.0));
+        for _ in 0..3 {
+            let set: HashSet<_> = (0..n).map(|_| ph.generate()).collect();
+            assert_eq!(set.len(), n);
+        }
+    }
+}
+
+#[test]
+fn test_avoid_repeat_fraction() {
+    let mut ph: Generator = Generator::with_seed(5);
+    ph.add(digits(10).parse().unwrap()).unwrap();
+    ph.avoid_repeat(Some(0.5));
+    let mut reshuffled = false;
+    for _ in 0..20 {
+        let a: Vec<_> = (0..5).map(|_| ph.generate()).collect();
+        let set: HashSet<_> = a.iter().collect();
+        assert_eq!(set.len(), 5);
+        let b: Vec<_> = (0..5).map(|_| ph.generate()).collect();
+        reshuffled |= b.iter().any(|x| a.contains(x));
+    }
+    assert!(reshuffled);
+}
+
+#[test]
+fn test_avoid_repeat_traced() {
+    let mut ph: Generator = Generator::with_seed(9);
+    ph.add("main = {A}{A}\nA = a | b | c".parse().unwrap())
+        .unwrap();
+    ph.avoid_repeat(Some(1.0));
+    let mut set = HashSet::new();
+    for _ in 0..9 {
+        let (phrase, trace) = ph.generate_traced();
+        assert_eq!(trace.root().unwrap().post_gsub(), phrase);
+        set.insert(phrase);
+    }
+    assert_eq!(set.len(), 9);
+}
+
+#[test]
+fn test_avoid_repeat_add_and_disable() {
+    let mut ph: Generator = Generator::with_seed(1);
+    ph.add("main = a | b".parse().unwrap()).unwrap();
+    ph.avoid_repeat(Some(1.0));
+    let _ = ph.generate();
+    ph.add("main = c | d".parse().unwrap()).unwrap();
+    let set: HashSet<_> = (0..4).map(|_| ph.generate()).collect();
+    assert_eq!(set.len(), 4);
+    ph.avoid_repeat(None);
+    let mut seen = HashSet::new();
+    let mut repeated = false;
+    for _ in 0..10 {
+        repeated |= !seen.insert(ph.generate());
+    }
+    assert!(repeated);
+}
+
+#[test]
+fn test_sample_distinct_large() {
+    let mut ph: Generator = Generator::with_seed(3);
+    ph.add(
+        r#"
+        main = {A}{A}{A}{A}{A}{A}{A}{A}
+        A = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+        .parse()
+        .unwrap(),
+    )
+    .unwrap();
+    let phrases = ph.sample_distinct(1000);
+    assert_eq!(phrases.len(), 1000);
+    let set: HashSet<_> = phrases.iter().collect();
+    assert_eq!(set.len(), 1000);
+}
+
+#[test]
+fn test_sample_distinct_all() {
+    let mut ph: Generator = Generator::with_seed(4);
+    ph.add(digits(7).parse().unwrap()).unwrap();
+    let mut phrases = ph.sample_distinct(100);
+    phrases.sort();
+    assert_eq!(phrases, ["0", "1", "2", "3", "4", "5", "6"]);
+    let mut empty: Generator = Generator::new();
+    assert!(empty.sample_distinct(3).is_empty());
+}
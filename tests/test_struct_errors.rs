@@ -50,3 +50,30 @@ fn test_syntax_remove_error() {
     assert_eq!(format!("{}", err), "error in remove()");
     assert_eq!(err.to_string(), "error in remove()");
 }
+
+#[test]
+fn test_decode_error() {
+    let err = DecodeError::new("Message.".to_string());
+    assert_eq!(err.error_message(), "Message.");
+    assert_eq!(err.to_string(), "decode error: \"Message.\"");
+    assert_eq!(DecodeError::new(String::new()).to_string(), "decode error");
+}
+
+#[test]
+fn test_derivation_error() {
+    let err = DerivationError::new("Message.".to_string());
+    assert_eq!(err.error_message(), "Message.");
+    assert_eq!(err.to_string(), "derivation error: \"Message.\"");
+    assert_eq!(
+        DerivationError::new(String::new()).to_string(),
+        "derivation error"
+    );
+}
+
+#[test]
+fn test_weight_error() {
+    let err = WeightError::new("Message.".to_string());
+    assert_eq!(err.error_message(), "Message.");
+    assert_eq!(err.to_string(), "weight error: \"Message.\"");
+    assert_eq!(WeightError::new(String::new()).to_string(), "weight error");
+}
@@ -500,3 +500,85 @@ fn test_struct_generator_alternaitve_rng_and_subst() {
     assert_eq!(ph.weight(), 14.0);
     assert_eq!(ph.number_of_syntax(), 2);
 }
+
+#[test]
+fn test_struct_generator_generate_with_rng() {
+    let ph: Generator<ZeroNG> = r#"
+        main = A | B | C | {D}
+    "#
+    .parse()
+    .unwrap();
+    let context = ExtContext::from([("D".to_string(), "d".to_string())]);
+
+    let mut rng1 = LinearNG3::new();
+    assert_eq!(ph.generate_with_rng(&context, &mut rng1), "A");
+    assert_eq!(ph.generate_with_rng(&context, &mut rng1), "B");
+    let mut rng2 = LinearNG3::new();
+    assert_eq!(ph.generate_with_rng(&context, &mut rng2), "A");
+    assert_eq!(ph.generate_with_rng(&context, &mut rng1), "d");
+    assert_eq!(ph.generate_with_rng(&context, &mut rng2), "B");
+
+    let empty: Generator = Generator::new();
+    assert_eq!(empty.generate_with_rng(&context, &mut rng1), "nil");
+}
+
+#[test]
+fn test_struct_generator_with_seed() {
+    let syntax: Syntax = r#"
+        main = {A} {B} {C}
+        A = 1 | 2 | 3 | 4 | 5
+        B = 1 | 2 | 3 | 4 | 5
+        C = 1 | 2 | 3 | 4 | 5
+    "#
+    .parse()
+    .unwrap();
+    let mut ph1: Generator = Generator::with_seed(100);
+    let _ = ph1.add(syntax.clone()).unwrap();
+    let mut ph2: Generator = Generator::with_seed(100);
+    let _ = ph2.add(syntax).unwrap();
+    let v1: Vec<String> = (0..20).map(|_| ph1.generate()).collect();
+    let v2: Vec<String> = (0..20).map(|_| ph2.generate()).collect();
+    assert_eq!(v1, v2);
+
+    let state = ph1.rng_state();
+    let v3: Vec<String> = (0..20).map(|_| ph1.generate()).collect();
+    ph2.set_rng_state(state);
+    let v4: Vec<String> = (0..20).map(|_| ph2.generate()).collect();
+    assert_eq!(v3, v4);
+}
+
+#[test]
+fn test_struct_generator_combination_number_exact() {
+    let mut ph: Generator = Generator::new();
+    assert_eq!(ph.combination_number_exact(), Some(0));
+    ph.add(
+        r#"
+        main = {A}{A}
+        A = {B}{B}{B}{B}
+        B = {C}{C}{C}{C}
+        C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+        .parse()
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(ph.combination_number_exact(), Some(10u128.pow(32)));
+    assert_eq!(ph.combination_number(), usize::MAX);
+    let id = ph
+        .add(
+            r#"
+        main = {A}{A}{A}
+        A = {B}{B}{B}{B}
+        B = {C}{C}{C}{C}
+        C = 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9
+    "#
+            .parse()
+            .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(ph.combination_number_exact(), None);
+    assert_eq!(ph.combination_number(), usize::MAX);
+    ph.remove(id).unwrap();
+    ph.add("main = a | b".parse().unwrap()).unwrap();
+    assert_eq!(ph.combination_number_exact(), Some(10u128.pow(32) + 2));
+}
@@ -21,6 +21,9 @@
 extern crate tphrase;
 use tphrase::*;
 
+mod utils;
+use utils::*;
+
 #[test]
 fn test_struct_syntax_new() {
     let syntax: Syntax = Syntax::new();
@@ -152,3 +155,39 @@ fn test_struct_syntax_add() {
     let _ = ph.add(syntax1).unwrap();
     assert_eq!(ph.generate(), "Hello, World!");
 }
+
+#[test]
+fn test_struct_syntax_generate_with_rng() {
+    let mut syntax: Syntax = r#"
+        main = {HELLO}, {WORLD}!
+        HELLO = Hi | Hello | Greetings
+    "#
+    .parse()
+    .unwrap();
+    let context = ExtContext::from([("WORLD".to_string(), "World".to_string())]);
+    let mut rng = LinearNG3::new();
+
+    // Unbound
+    assert_eq!(syntax.generate_with_rng(&context, &mut rng), "nil");
+
+    syntax.bind_syntax("main").unwrap();
+    assert_eq!(syntax.generate_with_rng(&context, &mut rng), "Hi, World!");
+    assert_eq!(
+        syntax.generate_with_rng(&context, &mut rng),
+        "Hello, World!"
+    );
+}
+
+#[test]
+fn test_struct_syntax_add_bound_syntax() {
+    let mut syntax1: Syntax = "main = {B}".parse().unwrap();
+    syntax1.bind_syntax("main").unwrap();
+    let mut syntax2: Syntax = Syntax::new();
+    syntax2.add(syntax1).unwrap();
+    syntax2.add("B = x".parse().unwrap()).unwrap();
+
+    // The rules moved from the bound syntax are bound again.
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(syntax2).unwrap();
+    assert_eq!(ph.generate(), "x");
+}
@@ -0,0 +1,148 @@
+//! Test for Generator::set_temperature()
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use std::collections::HashMap;
+use tphrase::*;
+
+const SYNTAX: &str = r#"
+    main = {A} | {B} | "{C}" 0
+    A = "a1" 8 | a2
+    B := b1 | "b2" 5
+    C = c
+"#;
+
+fn assert_near(x: f64, y: f64) {
+    assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+}
+
+#[test]
+fn test_temperature_one_is_unchanged() {
+    let mut ph1: Generator = Generator::with_seed(42);
+    let _ = ph1.add(SYNTAX.parse().unwrap());
+    let mut ph2: Generator = Generator::with_seed(42);
+    let _ = ph2.add(SYNTAX.parse().unwrap());
+    ph2.set_temperature(1.0).unwrap();
+    assert_eq!(ph2.temperature(), 1.0);
+    for _ in 0..100 {
+        assert_eq!(ph1.generate(), ph2.generate());
+    }
+    assert_eq!(ph2.generate_for_key(b"key"), ph1.generate_for_key(b"key"));
+}
+
+#[test]
+fn test_temperature_probability() {
+    let mut ph: Generator = SYNTAX.parse().unwrap();
+    let no_context = ExtContext::new();
+    // main: {A} 9, {B} 6, {C} 0
+    assert_near(
+        ph.probability_of("a1", &no_context).unwrap(),
+        9.0 / 15.0 * 8.0 / 9.0,
+    );
+    assert_near(
+        ph.probability_of("b1", &no_context).unwrap(),
+        6.0 / 15.0 * 0.5,
+    );
+
+    ph.set_temperature(0.5).unwrap();
+    // main: 81, 36, 0 / A: 64, 1 / B is equalized.
+    assert_near(
+        ph.probability_of("a1", &no_context).unwrap(),
+        81.0 / 117.0 * 64.0 / 65.0,
+    );
+    assert_near(
+        ph.probability_of("b1", &no_context).unwrap(),
+        36.0 / 117.0 * 0.5,
+    );
+    assert_eq!(ph.probability_of("c", &no_context).unwrap(), 0.0);
+
+    ph.set_temperature(f64::INFINITY).unwrap();
+    assert_near(ph.probability_of("a2", &no_context).unwrap(), 0.25);
+    assert_near(ph.probability_of("b2", &no_context).unwrap(), 0.25);
+    assert_eq!(ph.probability_of("c", &no_context).unwrap(), 0.0);
+
+    ph.set_temperature(0.0).unwrap();
+    assert_eq!(ph.probability_of("a1", &no_context).unwrap(), 1.0);
+}
+
+#[test]
+fn test_temperature_generate() {
+    let mut ph: Generator = r#"main = "x" 3 | y"#.parse().unwrap();
+    ph.set_temperature(2.0).unwrap();
+    let mut count = HashMap::new();
+    for _ in 0..4000 {
+        *count.entry(ph.generate()).or_insert(0) += 1;
+    }
+    // sqrt(3) : 1
+    let x = 3f64.sqrt() / (3f64.sqrt() + 1.0) * 4000.0;
+    assert!((count["x"] as f64 - x).abs() < 200.0);
+
+    ph.set_temperature(0.0).unwrap();
+    for _ in 0..50 {
+        assert_eq!(ph.generate(), "x");
+        assert_eq!(ph.generate_traced().0, "x");
+    }
+}
+
+#[test]
+fn test_temperature_equalized_generator() {
+    let mut ph: Generator = r#"main = "x" 3 | y"#.parse().unwrap();
+    let _ = ph.add("main = z".parse().unwrap());
+    ph.equalize_chance(true);
+    ph.set_temperature(0.0).unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.probability_of("z", &no_context).unwrap(), 0.5);
+    assert_eq!(ph.probability_of("x", &no_context).unwrap(), 0.5);
+    assert_eq!(ph.probability_of("y", &no_context).unwrap(), 0.0);
+}
+
+#[test]
+fn test_temperature_with_rng_and_max_len() {
+    let mut ph: Generator = r#"main = "long text" 9 | "short" 1 | "tiny" 4"#.parse().unwrap();
+    ph.set_temperature(0.0).unwrap();
+    let mut rng: FastrandRng = RandomNumberGenerator::new();
+    assert_eq!(
+        ph.generate_with_rng(&ExtContext::new(), &mut rng),
+        "long text"
+    );
+    // Only "long text" can be selected.
+    assert_eq!(ph.generate_with_max_len(5, LengthUnit::Chars), None);
+
+    ph.set_temperature(f64::INFINITY).unwrap();
+    let mut count = HashMap::new();
+    for _ in 0..2000 {
+        let s = ph.generate_with_max_len(5, LengthUnit::Chars).unwrap();
+        *count.entry(s).or_insert(0) += 1;
+    }
+    assert!(count["short"] > 850 && count["short"] < 1150);
+    assert!(count["tiny"] > 850 && count["tiny"] < 1150);
+}
+
+#[test]
+fn test_temperature_errors() {
+    let mut ph: Generator = "main = x".parse().unwrap();
+    let err = ph.set_temperature(-0.5).unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The temperature -0.5 is not a non-negative number."
+    );
+    assert!(ph.set_temperature(f64::NAN).is_err());
+    assert_eq!(ph.temperature(), 1.0);
+}
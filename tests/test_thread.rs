@@ -0,0 +1,142 @@
+//! Test for sharing the syntaxes and the generators between threads
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+mod utils;
+use utils::*;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_thread_send_sync() {
+    assert_send_sync::<Syntax>();
+    assert_send_sync::<Generator>();
+    assert_send_sync::<Generator<LinearNG3>>();
+    assert_send_sync::<CompileError>();
+    assert_send_sync::<DecodeError>();
+}
+
+#[test]
+fn test_thread_static_syntax() {
+    static SYNTAX: std::sync::OnceLock<Syntax> = std::sync::OnceLock::new();
+    let syntax = SYNTAX.get_or_init(|| {
+        r#"
+            main = {A} | {B} | {C}
+            A = a
+            B = b
+            C = c
+        "#
+        .parse()
+        .unwrap()
+    });
+
+    let mut handles = Vec::new();
+    for _ in 0..4 {
+        handles.push(std::thread::spawn(move || {
+            let mut ph: Generator<LinearNG3> = Generator::new();
+            let _ = ph.add(syntax.clone()).unwrap();
+            vec![ph.generate(), ph.generate(), ph.generate()]
+        }));
+    }
+    for h in handles {
+        assert_eq!(h.join().unwrap(), vec!["a", "b", "c"]);
+    }
+}
+
+#[test]
+fn test_thread_move_generator() {
+    let mut ph: Generator = r#"
+        main = {A} {B}
+        A = a1 | a2
+        B = b1 | b2 ~ /b/B/
+    "#
+    .parse()
+    .unwrap();
+    let ph2 = ph.clone();
+    let handle = std::thread::spawn(move || {
+        let mut ph2 = ph2;
+        let s = ph2.generate();
+        (s, ph2.combination_number())
+    });
+    let (s, comb) = handle.join().unwrap();
+    assert!(s == "a1 B1" || s == "a1 B2" || s == "a2 B1" || s == "a2 B2");
+    assert_eq!(comb, 4);
+    assert_eq!(ph.combination_number(), 4);
+    let _ = ph.generate();
+}
+
+#[test]
+fn test_thread_shared_generator() {
+    let ph: Generator = r#"
+        main = {A} {B}
+        A = a1 | a2
+        B = b1 | b2
+    "#
+    .parse()
+    .unwrap();
+    let ph = std::sync::Arc::new(std::sync::Mutex::new(ph));
+    let mut handles = Vec::new();
+    for _ in 0..4 {
+        let ph = std::sync::Arc::clone(&ph);
+        handles.push(std::thread::spawn(move || {
+            let mut results = Vec::new();
+            for _ in 0..100 {
+                results.push(ph.lock().unwrap().generate());
+            }
+            results
+        }));
+    }
+    for h in handles {
+        for s in h.join().unwrap() {
+            assert!(s == "a1 b1" || s == "a1 b2" || s == "a2 b1" || s == "a2 b2");
+        }
+    }
+}
+
+#[test]
+fn test_thread_generate_with_rng() {
+    let ph: Generator = r#"
+        main = {A} | {B} | {C}
+        A = a
+        B = b
+        C = c
+    "#
+    .parse()
+    .unwrap();
+    let ph = std::sync::Arc::new(ph);
+    let mut handles = Vec::new();
+    for _ in 0..4 {
+        let ph = std::sync::Arc::clone(&ph);
+        handles.push(std::thread::spawn(move || {
+            let mut rng = LinearNG3::new();
+            let context = ExtContext::new();
+            vec![
+                ph.generate_with_rng(&context, &mut rng),
+                ph.generate_with_rng(&context, &mut rng),
+                ph.generate_with_rng(&context, &mut rng),
+            ]
+        }));
+    }
+    for h in handles {
+        assert_eq!(h.join().unwrap(), vec!["a", "b", "c"]);
+    }
+}
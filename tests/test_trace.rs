@@ -0,0 +1,123 @@
+//! Test for the derivation trace
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+mod utils;
+use utils::*;
+
+#[test]
+fn test_trace_tree() {
+    let mut ph: Generator<Point9NG> = Generator::new();
+    let syntax: Syntax = r#"
+        main = {A} {B} {_C}
+        A = a1 | a2 ~ /2/two/
+        B = {A}|{X}
+        _C = c1 | c2 | c3
+    "#
+    .parse()
+    .unwrap();
+    let id = ph.add(syntax).unwrap();
+    let ext_context = ExtContext::from([("X".to_string(), "x".to_string())]);
+    let (phrase, trace) = ph.generate_traced_with_context(&ext_context);
+    assert_eq!(phrase, "atwo x c3");
+    assert_eq!(trace.syntax_id(), Some(id));
+
+    let root = trace.root().unwrap();
+    assert_eq!(root.nonterminal(), Some("main"));
+    assert_eq!(root.option_index(), 0);
+    assert_eq!(root.post_gsub(), "atwo x c3");
+    assert_eq!(root.children().len(), 3);
+
+    let a = &root.children()[0];
+    assert_eq!(a.nonterminal(), Some("A"));
+    assert_eq!(a.option_index(), 1);
+    assert_eq!(a.pre_gsub(), "a2");
+    assert_eq!(a.post_gsub(), "atwo");
+    assert!(a.children().is_empty());
+
+    let b = &root.children()[1];
+    assert_eq!(b.nonterminal(), Some("B"));
+    assert_eq!(b.option_index(), 1);
+    assert_eq!(b.post_gsub(), "x");
+    assert_eq!(b.context_keys(), ["X"]);
+    assert!(b.children().is_empty());
+
+    let c = &root.children()[2];
+    assert_eq!(c.nonterminal(), None);
+    assert_eq!(c.option_index(), 2);
+    assert_eq!(c.post_gsub(), "c3");
+}
+
+#[test]
+fn test_trace_syntax_id() {
+    let mut ph: Generator<Point9NG> = Generator::new();
+    ph.add("main = first".parse().unwrap()).unwrap();
+    let id = ph.add("main = second".parse().unwrap()).unwrap();
+    let (phrase, trace) = ph.generate_traced();
+    assert_eq!(phrase, "second");
+    assert_eq!(trace.syntax_id(), Some(id));
+}
+
+#[test]
+fn test_trace_same_as_generate() {
+    let syntax = r#"
+        main = {A}{B}{C}
+        A = a | b | c | d
+        B = e | f | g | h
+        C = i | j | k | l
+    "#;
+    let mut ph1: Generator = Generator::with_seed(42);
+    ph1.add(syntax.parse().unwrap()).unwrap();
+    let mut ph2: Generator = Generator::with_seed(42);
+    ph2.add(syntax.parse().unwrap()).unwrap();
+    for _ in 0..100 {
+        let (phrase, trace) = ph2.generate_traced();
+        assert_eq!(ph1.generate(), phrase);
+        assert_eq!(trace.root().unwrap().post_gsub(), phrase);
+    }
+}
+
+#[test]
+fn test_trace_empty() {
+    let mut ph: Generator = Generator::new();
+    let (phrase, trace) = ph.generate_traced();
+    assert_eq!(phrase, "nil");
+    assert_eq!(trace.syntax_id(), None);
+    assert!(trace.root().is_none());
+    assert_eq!(trace.to_string(), "nil\n");
+}
+
+#[test]
+fn test_trace_display() {
+    let mut ph: Generator<Point9NG> = Generator::new();
+    ph.add(
+        "main = {A}-{_B}\nA = a | b ~ /b/B/\n_B = {X}"
+            .parse()
+            .unwrap(),
+    )
+    .unwrap();
+    let ext_context = ExtContext::from([("X".to_string(), "x".to_string())]);
+    let (_, trace) = ph.generate_traced_with_context(&ext_context);
+    assert_eq!(
+        trace.to_string(),
+        "syntax 1\n  main #0 \"B-x\"\n    A #1 \"B\" <- \"b\"\n    (anonymous) #0 \"x\" [X]\n"
+    );
+}
@@ -0,0 +1,213 @@
+//! Test for the weight overrides
+//
+// Copyright © 2025 OOTA, Masato
+//
+// This file is part of TPhrase for Rust.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+// OR
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use TPhrase for Rust except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+
+extern crate tphrase;
+use tphrase::*;
+
+#[test]
+fn test_set_weight_propagates() {
+    let mut ph: Generator = Generator::new();
+    let id = ph
+        .add(
+            r#"
+        main = {A} | {B}
+        A = a1 | a2 | a3
+        B = {C}
+        C = b
+    "#
+            .parse()
+            .unwrap(),
+        )
+        .unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.probability_of("b", &no_context).unwrap(), 0.25);
+    // The weight of C is propagated to B, and the text "{B}".
+    ph.set_weight(id, "C", Some(9.0)).unwrap();
+    assert_eq!(ph.probability_of("b", &no_context).unwrap(), 0.75);
+    ph.set_weight(id, "C", None).unwrap();
+    assert_eq!(ph.probability_of("b", &no_context).unwrap(), 0.25);
+    ph.set_weight(id, "A", Some(1.0)).unwrap();
+    assert_eq!(ph.probability_of("b", &no_context).unwrap(), 0.5);
+    assert_eq!(ph.probability_of("a1", &no_context).unwrap(), 0.5 / 3.0);
+}
+
+#[test]
+fn test_set_option_weight() {
+    let mut ph: Generator = Generator::new();
+    let id = ph
+        .add(
+            r#"
+        main = {A}
+        A = "x" 2 | y | z
+    "#
+            .parse()
+            .unwrap(),
+        )
+        .unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.probability_of("x", &no_context).unwrap(), 0.5);
+    ph.set_option_weight(id, "A", 1, Some(6.0)).unwrap();
+    assert_eq!(ph.probability_of("x", &no_context).unwrap(), 2.0 / 9.0);
+    assert_eq!(ph.probability_of("y", &no_context).unwrap(), 6.0 / 9.0);
+    // The weight specified by the syntax is reset to the default.
+    ph.set_option_weight(id, "A", 0, None).unwrap();
+    assert_eq!(ph.probability_of("x", &no_context).unwrap(), 1.0 / 8.0);
+}
+
+#[test]
+fn test_set_option_weight_by_label() {
+    let mut ph: Generator = Generator::new();
+    let id = ph
+        .add(
+            r#"
+        main = {A} | {C}
+        C = "{B} x" 2 | ' y {B}' | z {* comment *}  | {B}{(}w{)}  {* comment *}
+        A = 'a' | a
+        B = b
+    "#
+            .parse()
+            .unwrap(),
+        )
+        .unwrap();
+    let no_context = ExtContext::new();
+    assert!((ph.probability_of("b x", &no_context).unwrap() - 2.0 / 7.0).abs() < 1e-12);
+    ph.set_option_weight_by_label(id, "C", "{B} x", Some(0.0))
+        .unwrap();
+    assert_eq!(ph.probability_of("b x", &no_context).unwrap(), 0.0);
+    ph.set_option_weight_by_label(id, "C", " y {B}", Some(0.0))
+        .unwrap();
+    ph.set_option_weight_by_label(id, "C", "z {* comment *}", Some(0.0))
+        .unwrap();
+    assert_eq!(ph.probability_of("b{w}", &no_context).unwrap(), 1.0 / 3.0);
+    ph.set_option_weight_by_label(id, "C", "{B}{(}w{)}  {* comment *}", Some(0.0))
+        .unwrap();
+    // All the options labeled "a" are overridden.
+    ph.set_option_weight_by_label(id, "A", "a", Some(3.0))
+        .unwrap();
+    assert_eq!(ph.probability_of("a", &no_context).unwrap(), 1.0);
+    for _ in 0..20 {
+        assert_eq!(ph.generate(), "a");
+    }
+    let err = ph
+        .set_option_weight_by_label(id, "C", "x", Some(1.0))
+        .unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The option labeled \"x\" of \"C\" doesn't exist."
+    );
+}
+
+#[test]
+fn test_set_weight_syntax_selection() {
+    let mut ph: Generator = Generator::new();
+    let id1 = ph.add("main = {A}\nA = a | b".parse().unwrap()).unwrap();
+    let _ = ph.add("main = c".parse().unwrap()).unwrap();
+    let no_context = ExtContext::new();
+    assert_eq!(ph.probability_of("c", &no_context).unwrap(), 1.0 / 3.0);
+    ph.set_weight(id1, "main", Some(1.0)).unwrap();
+    assert_eq!(ph.probability_of("c", &no_context).unwrap(), 0.5);
+    ph.set_option_weight(id1, "A", 0, Some(0.0)).unwrap();
+    ph.set_option_weight(id1, "A", 1, Some(0.0)).unwrap();
+    ph.set_weight(id1, "main", None).unwrap();
+    for _ in 0..20 {
+        assert_eq!(ph.generate(), "c");
+    }
+}
+
+#[test]
+fn test_set_weight_errors() {
+    let mut ph: Generator = Generator::new();
+    let id = ph.add("main = x | y".parse().unwrap()).unwrap();
+    let err = ph.set_weight(id + 1, "main", Some(1.0)).unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        &format!("The syntax ID {} doesn't exist.", id + 1)
+    );
+    let err = ph.set_weight(id, "sub", Some(1.0)).unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The nonterminal \"sub\" doesn't exist."
+    );
+    let err = ph.set_option_weight(id, "main", 2, Some(1.0)).unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The option index 2 of \"main\" is out of range."
+    );
+    let err = ph.set_option_weight(id, "main", 0, Some(-1.0)).unwrap_err();
+    assert_eq!(
+        err.error_message(),
+        "The weight -1 is not a finite non-negative number."
+    );
+    assert!(ph.set_weight(id, "main", Some(f64::NAN)).is_err());
+    assert!(ph.set_weight(id, "main", Some(f64::INFINITY)).is_err());
+    // The failed overrides don't change anything.
+    assert_eq!(ph.probability_of("x", &ExtContext::new()).unwrap(), 0.5);
+}
+
+#[test]
+fn test_set_weight_unbound_syntax() {
+    let mut syntax: Syntax = "main = {A}\nA = a | b".parse().unwrap();
+    syntax.set_option_weight("A", 0, Some(0.0)).unwrap();
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(syntax).unwrap();
+    for _ in 0..20 {
+        assert_eq!(ph.generate(), "b");
+    }
+}
+
+#[test]
+fn test_set_weight_binary() {
+    let mut syntax: Syntax = "main = a | b".parse().unwrap();
+    syntax.bind_syntax("main").unwrap();
+    syntax.set_option_weight("main", 1, Some(0.0)).unwrap();
+    let mut restored = Syntax::from_bytes(&syntax.to_bytes()).unwrap();
+    restored
+        .set_option_weight_by_label("main", "b", Some(1.0))
+        .unwrap();
+    restored
+        .set_option_weight_by_label("main", "a", Some(0.0))
+        .unwrap();
+    let mut ph: Generator = Generator::new();
+    let _ = ph.add(restored).unwrap();
+    for _ in 0..20 {
+        assert_eq!(ph.generate(), "b");
+    }
+}
+
+#[test]
+fn test_set_weight_clone() {
+    let mut ph: Generator = Generator::new();
+    let id = ph
+        .add(
+            r#"
+        main = {A} | {B}
+        A = a
+        B = b
+    "#
+            .parse()
+            .unwrap(),
+        )
+        .unwrap();
+    ph.set_weight(id, "A", Some(0.0)).unwrap();
+    let mut ph2 = ph.clone();
+    for _ in 0..20 {
+        assert_eq!(ph2.generate(), "b");
+    }
+}
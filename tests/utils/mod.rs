@@ -23,6 +23,7 @@ use tphrase::*;
 // Random number generators to test a phrase generation.
 
 #[derive(Clone, Debug)]
+#[allow(dead_code)]
 pub struct ZeroNG {}
 impl RandomNumberGenerator for ZeroNG {
     fn new() -> Self {
@@ -34,6 +35,7 @@ impl RandomNumberGenerator for ZeroNG {
 }
 
 #[derive(Clone, Debug)]
+#[allow(dead_code)]
 pub struct Point9NG {}
 impl RandomNumberGenerator for Point9NG {
     fn new() -> Self {
@@ -141,6 +143,7 @@ pub fn check_distribution<R: RandomNumberGenerator, S: Substitutor>(
 }
 
 // Alternative RNG
+#[allow(dead_code)]
 pub struct PosixRng {
     n: u64,
 }
@@ -156,6 +159,7 @@ impl RandomNumberGenerator for PosixRng {
 }
 
 // Alternative Substitutor
+#[allow(dead_code)]
 pub struct PlainSubst {
     replaces: Vec<(String, String)>,
 }